@@ -0,0 +1,345 @@
+use crate::commands::remote;
+use crate::config::{schema, SourceKind};
+use crate::db::backups::{self, BackupRecord, NewBackup};
+use crate::db::settings;
+use crate::file_version::{self, FileVersion};
+use crate::local_fs;
+use crate::ssh::{pool::SshPool, sftp};
+use crate::sync::diff_generator::{self, DiffLine};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+const RETENTION_SETTING: &str = "backup_retention";
+
+/// How many backups to keep. Applied per config file after every backup and on demand.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupRetention {
+    /// Newest backups kept for each file
+    pub keep_per_file: usize,
+    /// Backups older than this are deleted, except the newest one of each file
+    pub max_age_days: Option<u64>,
+}
+
+impl Default for BackupRetention {
+    fn default() -> Self {
+        BackupRetention { keep_per_file: 20, max_age_days: Some(30) }
+    }
+}
+
+/// A backup compared with the file it was taken from, as it is now
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupDiff {
+    pub backup: BackupRecord,
+    pub current_exists: bool,
+    pub unified_diff: String,
+    pub diff_lines: Vec<DiffLine>,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+/// Backups sit next to the original so they stay on the same machine and filesystem
+fn backup_path_for(path: &str, now: &SystemTime) -> String {
+    let millis = now.duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or_default();
+    format!("{}.bak.{}", path, millis)
+}
+
+fn unix_secs(now: &SystemTime) -> i64 {
+    now.duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or_default()
+}
+
+/// Save `content` (what was just read from `path`) as a cataloged backup before modifying a local file
+pub(crate) async fn backup_local_file(pool: &Pool<Sqlite>, path: &str, content: &str, reason: &str) -> Result<(), String> {
+    let now = SystemTime::now();
+    let backup_path = backup_path_for(path, &now);
+    local_fs::write_atomic(&backup_path, content, Some(&FileVersion::missing()))
+        .map_err(|e| format!("Failed to create backup: {}", e))?;
+
+    let hash = file_version::content_hash(content);
+    let backup = NewBackup {
+        machine_id: None,
+        path,
+        backup_path: &backup_path,
+        content_hash: &hash,
+        size: content.len() as i64,
+        reason,
+        created_at: unix_secs(&now),
+    };
+    record_and_prune(pool, None, &backup).await
+}
+
+/// Save `content` (what was just read from `path`) as a cataloged backup on the machine itself
+pub(crate) async fn backup_remote_file(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    machine_id: i64,
+    path: &str,
+    content: &str,
+    reason: &str,
+) -> Result<(), String> {
+    let now = SystemTime::now();
+    let backup_path = backup_path_for(path, &now);
+    remote::write_remote_file(pool, ssh_pool, machine_id, &backup_path, content, Some(&FileVersion::missing())).await
+        .map_err(|e| {
+            println!("[Backup] Failed to back up {} on machine {}: {}", path, machine_id, e);
+            format!("Failed to create backup: {}", e)
+        })?;
+
+    let hash = file_version::content_hash(content);
+    let backup = NewBackup {
+        machine_id: Some(machine_id),
+        path,
+        backup_path: &backup_path,
+        content_hash: &hash,
+        size: content.len() as i64,
+        reason,
+        created_at: unix_secs(&now),
+    };
+    record_and_prune(pool, Some(ssh_pool), &backup).await
+}
+
+async fn record_and_prune(pool: &Pool<Sqlite>, ssh_pool: Option<&SshPool>, backup: &NewBackup<'_>) -> Result<(), String> {
+    backups::insert_backup(pool, backup).await?;
+
+    let records = backups::list_backups(pool, backup.machine_id, Some(backup.path)).await?;
+    let policy = load_retention(pool).await?;
+    prune(pool, ssh_pool, &records, &policy, backup.created_at).await;
+    Ok(())
+}
+
+async fn load_retention(pool: &Pool<Sqlite>) -> Result<BackupRetention, String> {
+    match settings::get_setting(pool, RETENTION_SETTING).await? {
+        Some(json) => Ok(serde_json::from_str(&json).unwrap_or_default()),
+        None => Ok(BackupRetention::default()),
+    }
+}
+
+/// Backups the policy says to delete. The newest backup of every file is always kept.
+fn expired_backups<'a>(records: &'a [BackupRecord], policy: &BackupRetention, now: i64) -> Vec<&'a BackupRecord> {
+    let mut by_file: HashMap<(Option<i64>, &str), Vec<&BackupRecord>> = HashMap::new();
+    for record in records {
+        by_file.entry((record.machine_id, record.path.as_str())).or_default().push(record);
+    }
+
+    let max_age_secs = policy.max_age_days.map(|days| days as i64 * 86_400);
+    let mut expired = Vec::new();
+    for mut file_backups in by_file.into_values() {
+        file_backups.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+        for (rank, record) in file_backups.into_iter().enumerate() {
+            let too_many = rank >= policy.keep_per_file.max(1);
+            let too_old = rank > 0 && max_age_secs.is_some_and(|max| now - record.created_at > max);
+            if too_many || too_old {
+                expired.push(record);
+            }
+        }
+    }
+    expired
+}
+
+/// Delete expired backups. Failures (e.g. an offline machine) are logged and retried on the next prune.
+async fn prune(pool: &Pool<Sqlite>, ssh_pool: Option<&SshPool>, records: &[BackupRecord], policy: &BackupRetention, now: i64) -> usize {
+    let mut deleted = 0;
+    for record in expired_backups(records, policy, now) {
+        match remove_backup(pool, ssh_pool, record).await {
+            Ok(()) => deleted += 1,
+            Err(e) => println!("[Backup] Could not prune {}: {}", record.backup_path, e),
+        }
+    }
+    deleted
+}
+
+/// Delete the backup file, then its catalog entry
+async fn remove_backup(pool: &Pool<Sqlite>, ssh_pool: Option<&SshPool>, record: &BackupRecord) -> Result<(), String> {
+    match record.machine_id {
+        Some(machine_id) => {
+            let ssh_pool = ssh_pool.ok_or_else(|| "No SSH connection available".to_string())?;
+            let (target, _) = remote::get_connection_info(pool, machine_id).await?;
+            sftp::sftp_remove_file_with_pool(ssh_pool.clone(), &target, &record.backup_path).await?;
+        }
+        None => match std::fs::remove_file(&record.backup_path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to delete backup: {}", e)),
+        },
+    }
+    backups::delete_backup(pool, record.id).await
+}
+
+/// Backup content, refusing copies that were changed after they were taken
+async fn read_backup_content(pool: &Pool<Sqlite>, ssh_pool: &SshPool, record: &BackupRecord) -> Result<String, String> {
+    let content = match record.machine_id {
+        Some(machine_id) => remote::read_remote_file_versioned(pool, ssh_pool, machine_id, &record.backup_path).await?.0,
+        None => local_fs::read_versioned(&record.backup_path)?.0,
+    }
+    .ok_or_else(|| format!("Backup file {} no longer exists", record.backup_path))?;
+
+    if file_version::content_hash(&content) != record.content_hash {
+        return Err(format!("Backup file {} was modified after it was taken", record.backup_path));
+    }
+    Ok(content)
+}
+
+async fn read_current(pool: &Pool<Sqlite>, ssh_pool: &SshPool, record: &BackupRecord) -> Result<(Option<String>, FileVersion), String> {
+    match record.machine_id {
+        Some(machine_id) => remote::read_remote_file_versioned(pool, ssh_pool, machine_id, &record.path).await,
+        None => local_fs::read_versioned(&record.path),
+    }
+}
+
+#[tauri::command]
+pub async fn list_backups(
+    pool: State<'_, Pool<Sqlite>>,
+    machine_id: Option<i64>,
+    path: Option<String>,
+) -> Result<Vec<BackupRecord>, String> {
+    backups::list_backups(&pool, machine_id, path.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn diff_backup(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    backup_id: i64,
+) -> Result<BackupDiff, String> {
+    let backup = backups::get_backup(&pool, backup_id).await?;
+    let backup_content = read_backup_content(&pool, &ssh_pool, &backup).await?;
+    let (current, _) = read_current(&pool, &ssh_pool, &backup).await?;
+    let current_content = current.as_deref().unwrap_or("");
+
+    let unified_diff = diff_generator::generate_unified_diff(&backup_content, current_content, &backup.backup_path, &backup.path);
+    let diff = diff_generator::generate_diff_lines(&backup_content, current_content);
+
+    Ok(BackupDiff {
+        current_exists: current.is_some(),
+        unified_diff,
+        diff_lines: diff.lines,
+        additions: diff.additions,
+        deletions: diff.deletions,
+        backup,
+    })
+}
+
+/// Put a backup's content back in place. The current file is backed up first, so a restore can be undone.
+/// A client's config is checked like any other write, so a restore can't bring back a server it would reject.
+#[tauri::command]
+pub async fn restore_backup(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    backup_id: i64,
+) -> Result<(), String> {
+    let backup = backups::get_backup(&pool, backup_id).await?;
+    let content = read_backup_content(&pool, &ssh_pool, &backup).await?;
+    let (current, version) = read_current(&pool, &ssh_pool, &backup).await?;
+    if let Some(kind) = SourceKind::for_path(&backup.path) {
+        schema::check_write(kind.source(), &backup.path, current.as_deref(), &content)?;
+    }
+
+    match backup.machine_id {
+        Some(machine_id) => {
+            if let Some(current) = &current {
+                backup_remote_file(&pool, &ssh_pool, machine_id, &backup.path, current, "restore").await?;
+            }
+            remote::write_remote_file(&pool, &ssh_pool, machine_id, &backup.path, &content, Some(&version)).await?;
+        }
+        None => {
+            if let Some(current) = &current {
+                backup_local_file(&pool, &backup.path, current, "restore").await?;
+            }
+            local_fs::write_atomic(&backup.path, &content, Some(&version))?;
+        }
+    }
+
+    println!("[Backup] Restored {} from {}", backup.path, backup.backup_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_backup(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    backup_id: i64,
+) -> Result<(), String> {
+    let backup = backups::get_backup(&pool, backup_id).await?;
+    remove_backup(&pool, Some(&ssh_pool), &backup).await
+}
+
+#[tauri::command]
+pub async fn get_backup_retention(pool: State<'_, Pool<Sqlite>>) -> Result<BackupRetention, String> {
+    load_retention(&pool).await
+}
+
+#[tauri::command]
+pub async fn set_backup_retention(
+    pool: State<'_, Pool<Sqlite>>,
+    retention: BackupRetention,
+) -> Result<(), String> {
+    if retention.keep_per_file == 0 {
+        return Err("At least one backup per file must be kept".to_string());
+    }
+    let json = serde_json::to_string(&retention).map_err(|e| e.to_string())?;
+    settings::set_setting(&pool, RETENTION_SETTING, &json).await
+}
+
+/// Apply the retention policy to every cataloged backup. Returns how many were deleted.
+#[tauri::command]
+pub async fn apply_backup_retention(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+) -> Result<usize, String> {
+    let records = backups::list_all_backups(&pool).await?;
+    let policy = load_retention(&pool).await?;
+    let deleted = prune(&pool, Some(&ssh_pool), &records, &policy, unix_secs(&SystemTime::now())).await;
+    println!("[Backup] Retention removed {} backup(s)", deleted);
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: i64, machine_id: Option<i64>, path: &str, created_at: i64) -> BackupRecord {
+        BackupRecord {
+            id,
+            machine_id,
+            path: path.to_string(),
+            backup_path: format!("{}.bak.{}", path, created_at),
+            content_hash: String::new(),
+            size: 0,
+            reason: "test".to_string(),
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_expired_backups() {
+        let day = 86_400;
+        let now = 100 * day;
+        let records = vec![
+            record(1, None, "/a.json", now - 40 * day),
+            record(2, None, "/a.json", now - 2 * day),
+            record(3, None, "/a.json", now - day),
+            record(4, None, "/a.json", now),
+            // Same path on a machine is a different file
+            record(5, Some(1), "/a.json", now - 50 * day),
+        ];
+
+        let policy = BackupRetention { keep_per_file: 3, max_age_days: None };
+        let ids: Vec<i64> = expired_backups(&records, &policy, now).iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![1]);
+
+        // Age limit never removes the newest backup of a file
+        let policy = BackupRetention { keep_per_file: 10, max_age_days: Some(30) };
+        let mut ids: Vec<i64> = expired_backups(&records, &policy, now).iter().map(|r| r.id).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1]);
+
+        let policy = BackupRetention { keep_per_file: 1, max_age_days: Some(30) };
+        let mut ids: Vec<i64> = expired_backups(&records, &policy, now).iter().map(|r| r.id).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+}
@@ -1,160 +1,102 @@
-use crate::config::{ClaudeConfig, ClaudeMCPServer, OpenCodeConfig, OpenCodeMCPServer};
-use std::fs;
+use crate::commands::{backups, remote, sources};
+use crate::config::{set_claude_server_enabled, ClaudeMCPServer, OpenCodeMCPServer, SourceKind};
+use crate::local_fs;
+use crate::ssh::pool::SshPool;
+use sqlx::{Pool, Sqlite};
+use std::collections::HashMap;
+use tauri::State;
+
+/// Read a config file locally (`machine_id: None`) or on a machine; `None` if it doesn't exist
+pub(crate) async fn read_config_file(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    machine_id: Option<i64>,
+    path: &str,
+) -> Result<Option<String>, String> {
+    match machine_id {
+        Some(id) => Ok(remote::read_remote_file_versioned(pool, ssh_pool, id, path).await?.0),
+        None => Ok(local_fs::read_versioned(path)?.0),
+    }
+}
+
+/// Read-modify-write a config file locally or on a machine. `modify` gets `None` when the file doesn't exist yet.
+/// The edit is made first so a rejected one leaves no backup behind. Existing content is then backed up (the
+/// write is abandoned if that fails), and the write is refused if the file changed in between.
+pub(crate) async fn update_config_file(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    machine_id: Option<i64>,
+    path: &str,
+    reason: &str,
+    modify: impl FnOnce(Option<&str>) -> Result<String, String>,
+) -> Result<(), String> {
+    match machine_id {
+        Some(id) => {
+            let (content, version) = remote::read_remote_file_versioned(pool, ssh_pool, id, path).await?;
+            let output = modify(content.as_deref())?;
+            if let Some(content) = &content {
+                backups::backup_remote_file(pool, ssh_pool, id, path, content, reason).await?;
+            }
+            remote::write_remote_file(pool, ssh_pool, id, path, &output, Some(&version)).await?;
+        }
+        None => {
+            let (content, version) = local_fs::read_versioned(path)?;
+            let output = modify(content.as_deref())?;
+            if let Some(content) = &content {
+                backups::backup_local_file(pool, path, content, reason).await?;
+            }
+            local_fs::write_atomic(path, &output, Some(&version))?;
+        }
+    }
+    Ok(())
+}
+
+// The Claude/OpenCode commands below predate `sources` and are kept for the current UI
 
 #[tauri::command]
 pub async fn update_claude_server(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
     path: String,
     server_name: String,
     server_config: ClaudeMCPServer,
 ) -> Result<(), String> {
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-
-    let backup_path = format!("{}.bak", path);
-    fs::write(&backup_path, &content)
-        .map_err(|e| format!("Failed to create backup: {}", e))?;
-
-    let mut config: ClaudeConfig = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-
-    config.mcp_servers.insert(server_name, server_config);
-
-    let output = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
-
-    fs::write(&path, output)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
-
-    Ok(())
+    let server = serde_json::to_value(server_config).map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+    sources::put_source_server(&pool, &ssh_pool, SourceKind::Claude.source(), None, Some(path), server_name, server).await
 }
 
 #[tauri::command]
 pub async fn update_opencode_server(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
     path: String,
     server_name: String,
     server_config: OpenCodeMCPServer,
 ) -> Result<(), String> {
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-
-    let backup_path = format!("{}.bak", path);
-    fs::write(&backup_path, &content)
-        .map_err(|e| format!("Failed to create backup: {}", e))?;
-
-    let mut config: OpenCodeConfig = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-
-    config.mcp.insert(server_name, server_config);
-
-    let output = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
-
-    fs::write(&path, output)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
-
-    Ok(())
+    let server = serde_json::to_value(server_config).map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+    sources::put_source_server(&pool, &ssh_pool, SourceKind::OpenCode.source(), None, Some(path), server_name, server).await
 }
 
 #[tauri::command]
-pub async fn disable_claude_server(path: String, server_name: String) -> Result<(), String> {
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-
-    let backup_path = format!("{}.bak", path);
-    fs::write(&backup_path, &content)
-        .map_err(|e| format!("Failed to create backup: {}", e))?;
-
-    let mut config: ClaudeConfig = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-
-    if let Some(mut server) = config.mcp_servers.remove(&server_name) {
-        server.is_active = Some(false);
-        let disabled_name = format!("_disabled_{}", server_name);
-        config.mcp_servers.insert(disabled_name, server);
-    }
-
-    let output = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
-
-    fs::write(&path, output)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
-
-    Ok(())
+pub async fn disable_claude_server(pool: State<'_, Pool<Sqlite>>, ssh_pool: State<'_, SshPool>, path: String, server_name: String) -> Result<(), String> {
+    let items = vec![BatchToggleItem { name: server_name, enabled: false }];
+    sources::toggle_servers(&pool, &ssh_pool, SourceKind::Claude.source(), None, Some(path), items).await
 }
 
 #[tauri::command]
-pub async fn enable_claude_server(path: String, server_name: String) -> Result<(), String> {
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-
-    let backup_path = format!("{}.bak", path);
-    fs::write(&backup_path, &content)
-        .map_err(|e| format!("Failed to create backup: {}", e))?;
-
-    let mut config: ClaudeConfig = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-
-    let disabled_name = format!("_disabled_{}", server_name);
-    if let Some(mut server) = config.mcp_servers.remove(&disabled_name) {
-        server.is_active = Some(true);
-        config.mcp_servers.insert(server_name, server);
-    }
-
-    let output = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
-
-    fs::write(&path, output)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
-
-    Ok(())
+pub async fn enable_claude_server(pool: State<'_, Pool<Sqlite>>, ssh_pool: State<'_, SshPool>, path: String, server_name: String) -> Result<(), String> {
+    let items = vec![BatchToggleItem { name: server_name, enabled: true }];
+    sources::toggle_servers(&pool, &ssh_pool, SourceKind::Claude.source(), None, Some(path), items).await
 }
 
 #[tauri::command]
-pub async fn delete_claude_server(path: String, server_name: String) -> Result<(), String> {
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-
-    let backup_path = format!("{}.bak", path);
-    fs::write(&backup_path, &content)
-        .map_err(|e| format!("Failed to create backup: {}", e))?;
-
-    let mut config: ClaudeConfig = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-
-    config.mcp_servers.remove(&server_name);
-    let disabled_name = format!("_disabled_{}", server_name);
-    config.mcp_servers.remove(&disabled_name);
-
-    let output = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
-
-    fs::write(&path, output)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
-
-    Ok(())
+pub async fn delete_claude_server(pool: State<'_, Pool<Sqlite>>, ssh_pool: State<'_, SshPool>, path: String, server_name: String) -> Result<(), String> {
+    sources::remove_source_server(&pool, &ssh_pool, SourceKind::Claude.source(), None, Some(path), server_name).await
 }
 
 #[tauri::command]
-pub async fn delete_opencode_server(path: String, server_name: String) -> Result<(), String> {
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-
-    let backup_path = format!("{}.bak", path);
-    fs::write(&backup_path, &content)
-        .map_err(|e| format!("Failed to create backup: {}", e))?;
-
-    let mut config: OpenCodeConfig = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-
-    config.mcp.remove(&server_name);
-
-    let output = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
-
-    fs::write(&path, output)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
-
-    Ok(())
+pub async fn delete_opencode_server(pool: State<'_, Pool<Sqlite>>, ssh_pool: State<'_, SshPool>, path: String, server_name: String) -> Result<(), String> {
+    sources::remove_source_server(&pool, &ssh_pool, SourceKind::OpenCode.source(), None, Some(path), server_name).await
 }
 
 /// Request struct for batch toggle operations
@@ -167,90 +109,51 @@ pub struct BatchToggleItem {
 /// Batch enable/disable Claude servers in a single file operation
 #[tauri::command]
 pub async fn batch_toggle_claude_servers(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
     path: String,
     items: Vec<BatchToggleItem>,
 ) -> Result<(), String> {
-    if items.is_empty() {
-        return Ok(());
-    }
-
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-
-    let backup_path = format!("{}.bak", path);
-    fs::write(&backup_path, &content)
-        .map_err(|e| format!("Failed to create backup: {}", e))?;
-
-    let mut config: ClaudeConfig = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+    sources::toggle_servers(&pool, &ssh_pool, SourceKind::Claude.source(), None, Some(path), items).await
+}
 
+/// Enable/disable Claude servers using the `_disabled_<name>` convention (global or project `mcpServers`).
+/// Names that aren't there are skipped.
+pub(crate) fn apply_claude_toggles(servers: &mut HashMap<String, ClaudeMCPServer>, items: Vec<BatchToggleItem>) {
     for item in items {
-        let disabled_name = format!("_disabled_{}", item.name);
-        
-        if item.enabled {
-            // Enable: move from _disabled_<name> to <name>
-            if let Some(mut server) = config.mcp_servers.remove(&disabled_name) {
-                server.is_active = Some(true);
-                config.mcp_servers.insert(item.name, server);
-            }
-        } else {
-            // Disable: move from <name> to _disabled_<name>
-            if let Some(mut server) = config.mcp_servers.remove(&item.name) {
-                server.is_active = Some(false);
-                config.mcp_servers.insert(disabled_name, server);
-            }
-        }
+        set_claude_server_enabled(servers, &item.name, item.enabled);
     }
-
-    let output = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
-
-    fs::write(&path, output)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
-
-    Ok(())
 }
 
 /// Batch enable/disable OpenCode servers in a single file operation
 #[tauri::command]
 pub async fn batch_toggle_opencode_servers(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
     path: String,
     items: Vec<BatchToggleItem>,
 ) -> Result<(), String> {
-    if items.is_empty() {
-        return Ok(());
-    }
-
-    let content = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-
-    let backup_path = format!("{}.bak", path);
-    fs::write(&backup_path, &content)
-        .map_err(|e| format!("Failed to create backup: {}", e))?;
-
-    let mut config: OpenCodeConfig = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-
-    for item in items {
-        if let Some(server) = config.mcp.get_mut(&item.name) {
-            server.enabled = Some(item.enabled);
-        }
-    }
-
-    let output = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
-
-    fs::write(&path, output)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
-
-    Ok(())
+    sources::toggle_servers(&pool, &ssh_pool, SourceKind::OpenCode.source(), None, Some(path), items).await
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{ClaudeConfig, OpenCodeConfig};
+    use std::fs;
     use tempfile::NamedTempFile;
     use std::io::Write;
+    use crate::db::test_pool;
+
+    async fn toggle_claude_servers(pool: &Pool<Sqlite>, path: &str, items: Vec<BatchToggleItem>) -> Result<(), String> {
+        let source = SourceKind::Claude.source();
+        sources::toggle_servers(pool, &SshPool::new(), source, None, Some(path.to_string()), items).await
+    }
+
+    async fn toggle_opencode_servers(pool: &Pool<Sqlite>, path: &str, items: Vec<BatchToggleItem>) -> Result<(), String> {
+        let source = SourceKind::OpenCode.source();
+        sources::toggle_servers(pool, &SshPool::new(), source, None, Some(path.to_string()), items).await
+    }
 
     fn create_test_claude_config() -> String {
         r#"{
@@ -283,7 +186,7 @@ mod tests {
             BatchToggleItem { name: "server2".to_string(), enabled: false },
         ];
 
-        let result = batch_toggle_claude_servers(path.clone(), items).await;
+        let result = toggle_claude_servers(&test_pool().await, &path, items).await;
         assert!(result.is_ok());
 
         let content = fs::read_to_string(&path).unwrap();
@@ -306,7 +209,7 @@ mod tests {
             BatchToggleItem { name: "server3".to_string(), enabled: true },
         ];
 
-        let result = batch_toggle_claude_servers(path.clone(), items).await;
+        let result = toggle_claude_servers(&test_pool().await, &path, items).await;
         assert!(result.is_ok());
 
         let content = fs::read_to_string(&path).unwrap();
@@ -328,7 +231,7 @@ mod tests {
             BatchToggleItem { name: "server3".to_string(), enabled: true },   // enable
         ];
 
-        let result = batch_toggle_claude_servers(path.clone(), items).await;
+        let result = toggle_claude_servers(&test_pool().await, &path, items).await;
         assert!(result.is_ok());
 
         let content = fs::read_to_string(&path).unwrap();
@@ -346,7 +249,7 @@ mod tests {
         file.write_all(create_test_claude_config().as_bytes()).unwrap();
         let path = file.path().to_string_lossy().to_string();
 
-        let result = batch_toggle_claude_servers(path.clone(), vec![]).await;
+        let result = toggle_claude_servers(&test_pool().await, &path, vec![]).await;
         assert!(result.is_ok());
     }
 
@@ -362,7 +265,7 @@ mod tests {
             BatchToggleItem { name: "server3".to_string(), enabled: true },
         ];
 
-        let result = batch_toggle_opencode_servers(path.clone(), items).await;
+        let result = toggle_opencode_servers(&test_pool().await, &path, items).await;
         assert!(result.is_ok());
 
         let content = fs::read_to_string(&path).unwrap();
@@ -379,7 +282,7 @@ mod tests {
         file.write_all(create_test_opencode_config().as_bytes()).unwrap();
         let path = file.path().to_string_lossy().to_string();
 
-        let result = batch_toggle_opencode_servers(path.clone(), vec![]).await;
+        let result = toggle_opencode_servers(&test_pool().await, &path, vec![]).await;
         assert!(result.is_ok());
     }
 }
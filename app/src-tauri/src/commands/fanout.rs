@@ -0,0 +1,163 @@
+use crate::commands::{remote, sources};
+use crate::config::SourceKind;
+use crate::ssh::{connection, pool::SshPool};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+use tauri::State;
+
+/// A single server definition to roll out, in the format of the source it targets
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerDefinition {
+    pub source: SourceKind,
+    pub config: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum FanOutOutcome {
+    Success,
+    Failed { error: String },
+    /// The machine could not be reached, so nothing was attempted
+    Skipped { reason: String },
+}
+
+#[derive(Debug, Serialize)]
+pub struct FanOutResult {
+    pub machine_id: i64,
+    pub path: Option<String>,
+    #[serde(flatten)]
+    pub outcome: FanOutOutcome,
+}
+
+/// Write one server definition to every target machine in parallel.
+///
+/// Each machine gets its own copy normalized for its platform. `path` overrides
+/// the config location on every machine; otherwise it is detected per machine.
+#[tauri::command]
+pub async fn apply_server_to_machines(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    server_name: String,
+    server: ServerDefinition,
+    machine_ids: Vec<i64>,
+    path: Option<String>,
+) -> Result<Vec<FanOutResult>, String> {
+    let handles: Vec<_> = machine_ids
+        .into_iter()
+        .map(|machine_id| {
+            let pool = pool.inner().clone();
+            let ssh_pool = ssh_pool.inner().clone();
+            let server_name = server_name.clone();
+            let server = server.clone();
+            let path = path.clone();
+
+            let handle = tokio::spawn(async move {
+                apply_to_machine(&pool, &ssh_pool, machine_id, server_name, server, path).await
+            });
+            (machine_id, handle)
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(handles.len());
+    for (machine_id, handle) in handles {
+        let result = handle.await.unwrap_or_else(|e| FanOutResult {
+            machine_id,
+            path: None,
+            outcome: FanOutOutcome::Failed { error: format!("Task join error: {}", e) },
+        });
+        results.push(result);
+    }
+
+    Ok(results)
+}
+
+async fn apply_to_machine(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    machine_id: i64,
+    server_name: String,
+    server: ServerDefinition,
+    path: Option<String>,
+) -> FanOutResult {
+    let failed = |path: Option<String>, error: String| FanOutResult {
+        machine_id,
+        path,
+        outcome: FanOutOutcome::Failed { error },
+    };
+
+    let (target, _) = match remote::get_connection_info(pool, machine_id).await {
+        Ok(info) => info,
+        Err(e) => return failed(None, e),
+    };
+
+    if let Err(e) = connection::ensure_connection_with_pool(ssh_pool.clone(), &target).await {
+        println!("[FanOut] Machine {} unreachable: {}", machine_id, e);
+        return FanOutResult {
+            machine_id,
+            path: None,
+            outcome: FanOutOutcome::Skipped { reason: e },
+        };
+    }
+
+    let source = server.source.source();
+    let resolved_path = match sources::resolve_source_path(pool, ssh_pool, source, Some(machine_id), path).await {
+        Ok(p) => p,
+        Err(e) => return failed(None, e),
+    };
+
+    let result = sources::put_source_server(
+        pool,
+        ssh_pool,
+        source,
+        Some(machine_id),
+        Some(resolved_path.clone()),
+        server_name,
+        server.config,
+    )
+    .await;
+
+    match result {
+        Ok(()) => FanOutResult {
+            machine_id,
+            path: Some(resolved_path),
+            outcome: FanOutOutcome::Success,
+        },
+        Err(e) => failed(Some(resolved_path), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_definition_deserialize() {
+        let claude: ServerDefinition = serde_json::from_str(
+            r#"{"source": "claude", "config": {"command": "npx", "args": ["-y", "pkg"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(claude.source, SourceKind::Claude);
+        assert_eq!(claude.config["command"], "npx");
+
+        let opencode: ServerDefinition = serde_json::from_str(
+            r#"{"source": "opencode", "config": {"type": "local", "command": ["npx", "-y", "pkg"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(opencode.source, SourceKind::OpenCode);
+        assert_eq!(opencode.config["type"], "local");
+    }
+
+    #[test]
+    fn test_fan_out_result_serialize() {
+        let result = FanOutResult {
+            machine_id: 7,
+            path: None,
+            outcome: FanOutOutcome::Skipped { reason: "TCP connection failed".to_string() },
+        };
+        let json = serde_json::to_value(&result).unwrap();
+
+        assert_eq!(json["machine_id"], 7);
+        assert_eq!(json["status"], "skipped");
+        assert_eq!(json["reason"], "TCP connection failed");
+    }
+}
@@ -0,0 +1,111 @@
+use crate::config::SourceKind;
+use crate::db::inventory::{self, InventoryChanges, InventoryItem, InventorySnapshot, ItemKind, ItemProvider};
+use crate::probe::Inventory;
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+/// The latest recorded inventory of one server
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInventory {
+    pub snapshot: InventorySnapshot,
+    pub items: Vec<InventoryItem>,
+}
+
+/// How a server's inventory changed between two probes
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryHistory {
+    /// Inventory the comparison starts from: the one current at `since`, or the oldest recorded if none was
+    pub from: Option<InventorySnapshot>,
+    pub to: Option<InventorySnapshot>,
+    pub changes: InventoryChanges,
+}
+
+/// Store what a successful probe listed, keyed by machine (`None` = local), source and server
+pub(crate) async fn record_probe_inventory(
+    pool: &Pool<Sqlite>,
+    machine_id: Option<i64>,
+    source: SourceKind,
+    server_name: &str,
+    inventory: &Inventory,
+) -> Result<(), String> {
+    let listed = [
+        (ItemKind::Tool, &inventory.tools),
+        (ItemKind::Resource, &inventory.resources),
+        (ItemKind::Prompt, &inventory.prompts),
+    ];
+    let items = listed
+        .iter()
+        .flat_map(|(kind, values)| values.iter().filter_map(move |value| InventoryItem::from_listed(*kind, value)))
+        .collect();
+    inventory::record_inventory(pool, machine_id, source.as_str(), server_name, items, now()).await?;
+    Ok(())
+}
+
+/// A failed probe leaves the last inventory in place, flagged stale so searches stop offering it
+pub(crate) async fn record_probe_failure(
+    pool: &Pool<Sqlite>,
+    machine_id: Option<i64>,
+    source: SourceKind,
+    server_name: &str,
+) -> Result<(), String> {
+    inventory::mark_stale(pool, machine_id, source.as_str(), server_name, now()).await
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or_default()
+}
+
+/// Tools, resources and prompts a server listed the last time it was probed
+#[tauri::command]
+pub async fn get_server_inventory(
+    pool: State<'_, Pool<Sqlite>>,
+    source: SourceKind,
+    machine_id: Option<i64>,
+    server_name: String,
+) -> Result<Option<ServerInventory>, String> {
+    let Some(snapshot) = inventory::latest_snapshot(&pool, machine_id, source.as_str(), &server_name, None).await? else {
+        return Ok(None);
+    };
+    let items = inventory::snapshot_items(&pool, snapshot.id).await?;
+    Ok(Some(ServerInventory { snapshot, items }))
+}
+
+/// Which machines, sources and servers currently expose a tool (or resource URI / prompt, with `kind`)
+#[tauri::command]
+pub async fn find_inventory_providers(
+    pool: State<'_, Pool<Sqlite>>,
+    name: String,
+    kind: Option<ItemKind>,
+) -> Result<Vec<ItemProvider>, String> {
+    inventory::find_providers(&pool, kind.unwrap_or(ItemKind::Tool), &name).await
+}
+
+/// What changed in a server's inventory since `since` (unix seconds), up to its latest probe
+#[tauri::command]
+pub async fn get_inventory_changes(
+    pool: State<'_, Pool<Sqlite>>,
+    source: SourceKind,
+    machine_id: Option<i64>,
+    server_name: String,
+    since: i64,
+) -> Result<InventoryHistory, String> {
+    let source = source.as_str();
+    let to = inventory::latest_snapshot(&pool, machine_id, source, &server_name, None).await?;
+    let from = match inventory::latest_snapshot(&pool, machine_id, source, &server_name, Some(since)).await? {
+        Some(snapshot) => Some(snapshot),
+        None => inventory::first_snapshot(&pool, machine_id, source, &server_name).await?,
+    };
+
+    let changes = match (&from, &to) {
+        (Some(from), Some(to)) if from.id != to.id => inventory::diff_items(
+            &inventory::snapshot_items(&pool, from.id).await?,
+            &inventory::snapshot_items(&pool, to.id).await?,
+        ),
+        _ => InventoryChanges::default(),
+    };
+    Ok(InventoryHistory { from, to, changes })
+}
@@ -5,15 +5,9 @@ use tauri::State;
 #[tauri::command]
 pub async fn add_machine(
     pool: State<'_, Pool<Sqlite>>,
-    name: String,
-    host: String,
-    username: String,
-    ssh_key_id: i64,
-    port: i64,
-    platform: Option<String>,
+    machine: machines::NewMachine,
 ) -> Result<i64, String> {
-    let platform_val = platform.unwrap_or_else(|| "linux".to_string());
-    machines::add_machine(&pool, &name, &host, &username, ssh_key_id, port, &platform_val).await
+    machines::add_machine(&pool, &machine).await
 }
 
 #[tauri::command]
@@ -35,13 +29,7 @@ pub async fn delete_machine(
 pub async fn update_machine(
     pool: State<'_, Pool<Sqlite>>,
     id: i64,
-    name: String,
-    host: String,
-    username: String,
-    ssh_key_id: i64,
-    port: i64,
-    platform: Option<String>,
+    machine: machines::NewMachine,
 ) -> Result<(), String> {
-    let platform_val = platform.unwrap_or_else(|| "linux".to_string());
-    machines::update_machine(&pool, id, &name, &host, &username, ssh_key_id, port, &platform_val).await
+    machines::update_machine(&pool, id, &machine).await
 }
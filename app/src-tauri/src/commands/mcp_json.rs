@@ -0,0 +1,359 @@
+use crate::commands::{config, remote, sources};
+use crate::config::json_edit::write_json;
+use crate::config::schema;
+use crate::config::{ClaudeConfig, ClaudeMCPServer, McpJsonConfig, SourceKind, MCP_JSON_FILE_NAME};
+use crate::db::workspace_roots::{self, WorkspaceRoot};
+use crate::ssh::{pool::SshPool, sftp};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// Dependency and build directories never searched for `.mcp.json` (hidden directories are skipped too)
+const SKIP_DIRS: &[&str] = &["node_modules", "target", "dist", "build", "vendor", "venv", "__pycache__"];
+/// How far below a workspace root a project may sit (root/org/repo/packages/app)
+const MAX_DEPTH: usize = 4;
+
+/// A discovered `.mcp.json`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpJsonFile {
+    pub path: String,
+    pub project_path: String,
+    pub root_id: i64,
+    pub server_names: Vec<String>,
+    /// Set when the file could not be read or parsed
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedWorkspaceRoot {
+    pub root_id: i64,
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpJsonDiscovery {
+    pub files: Vec<McpJsonFile>,
+    pub failed_roots: Vec<FailedWorkspaceRoot>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum McpJsonSyncDirection {
+    /// Project `.mcp.json` -> the user's global `mcpServers`
+    ToGlobal,
+    /// Global `mcpServers` -> project `.mcp.json`
+    FromGlobal,
+}
+
+/// Project directory a `.mcp.json` belongs to
+fn project_dir(path: &str) -> String {
+    let dir = path.strip_suffix(MCP_JSON_FILE_NAME).unwrap_or(path);
+    let dir = dir.trim_end_matches(['/', '\\']);
+    if dir.is_empty() { "/".to_string() } else { dir.to_string() }
+}
+
+fn expand_local_root(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            home.join(rest.trim_start_matches(['/', '\\']))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Local counterpart of `sftp_find_files_with_pool`
+fn find_local_mcp_json(root: &Path, max_depth: usize) -> Result<Vec<String>, String> {
+    let mut found = Vec::new();
+    let mut queue = VecDeque::from([(root.to_path_buf(), 0usize)]);
+
+    while let Some((dir, depth)) = queue.pop_front() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if dir == root => return Err(format!("Failed to list '{}': {}", dir.display(), e)),
+            Err(_) => continue,
+        };
+
+        for entry in entries.filter_map(|e| e.ok()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let file_type = match entry.file_type() {
+                Ok(t) => t,
+                Err(_) => continue,
+            };
+            if file_type.is_file() && name == MCP_JSON_FILE_NAME {
+                found.push(entry.path().to_string_lossy().to_string());
+            } else if file_type.is_dir() && depth < max_depth && !name.starts_with('.') && !SKIP_DIRS.contains(&name.as_str()) {
+                queue.push_back((entry.path(), depth + 1));
+            }
+        }
+    }
+
+    found.sort();
+    Ok(found)
+}
+
+async fn find_mcp_json_files(pool: &Pool<Sqlite>, ssh_pool: &SshPool, root: &WorkspaceRoot) -> Result<Vec<String>, String> {
+    match root.machine_id {
+        Some(id) => {
+            let (target, _) = remote::get_connection_info(pool, id).await?;
+            let skip = SKIP_DIRS.iter().map(|d| d.to_string()).collect();
+            sftp::sftp_find_files_with_pool(ssh_pool.clone(), &target, &root.path, MCP_JSON_FILE_NAME, MAX_DEPTH, skip).await
+        }
+        None => find_local_mcp_json(&expand_local_root(&root.path), MAX_DEPTH),
+    }
+}
+
+fn parse_mcp_json(content: Option<&str>) -> Result<McpJsonConfig, String> {
+    match content {
+        Some(c) => serde_json::from_str(c).map_err(|e| format!("Failed to parse .mcp.json: {}", e)),
+        None => Ok(McpJsonConfig::default()),
+    }
+}
+
+/// Serialize an edit of `path`, refused if Claude Code would reject a server it adds or changes.
+/// A `.mcp.json` holds servers in the same format as the global config.
+fn write_checked<T: Serialize>(path: &str, original: Option<&str>, config: &T) -> Result<String, String> {
+    let output = write_json(original, config)?;
+    schema::check_write(SourceKind::Claude.source(), path, original, &output)?;
+    Ok(output)
+}
+
+/// Copy active global servers into a `.mcp.json`. Disabled ones (and the `isActive` flag) stay out of the shared file.
+fn copy_from_global(global: &ClaudeConfig, mcp_json: &mut McpJsonConfig, server_names: &[String]) -> usize {
+    let mut copied = 0;
+    for name in server_names {
+        if let Some(server) = global.mcp_servers.get(name) {
+            mcp_json.mcp_servers.insert(name.clone(), ClaudeMCPServer { is_active: None, ..server.clone() });
+            copied += 1;
+        }
+    }
+    copied
+}
+
+fn copy_to_global(mcp_json: &McpJsonConfig, global: &mut ClaudeConfig, server_names: &[String]) -> usize {
+    let mut copied = 0;
+    for name in server_names {
+        if let Some(server) = mcp_json.mcp_servers.get(name) {
+            global.mcp_servers.remove(&format!("_disabled_{}", name));
+            global.mcp_servers.insert(name.clone(), server.clone());
+            copied += 1;
+        }
+    }
+    copied
+}
+
+#[tauri::command]
+pub async fn list_workspace_roots(
+    pool: State<'_, Pool<Sqlite>>,
+    machine_id: Option<i64>,
+) -> Result<Vec<WorkspaceRoot>, String> {
+    workspace_roots::list_workspace_roots(&pool, machine_id).await
+}
+
+#[tauri::command]
+pub async fn add_workspace_root(
+    pool: State<'_, Pool<Sqlite>>,
+    machine_id: Option<i64>,
+    path: String,
+) -> Result<i64, String> {
+    let path = path.trim().to_string();
+    if path.is_empty() {
+        return Err("Workspace root path is empty".to_string());
+    }
+    if machine_id.is_none() && !expand_local_root(&path).is_dir() {
+        return Err(format!("'{}' is not a directory", path));
+    }
+    workspace_roots::add_workspace_root(&pool, machine_id, &path).await
+}
+
+#[tauri::command]
+pub async fn remove_workspace_root(pool: State<'_, Pool<Sqlite>>, root_id: i64) -> Result<(), String> {
+    workspace_roots::delete_workspace_root(&pool, root_id).await
+}
+
+/// Find every `.mcp.json` under the machine's (or the local) workspace roots
+#[tauri::command]
+pub async fn discover_mcp_json_files(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    machine_id: Option<i64>,
+) -> Result<McpJsonDiscovery, String> {
+    let mut files = Vec::new();
+    let mut failed_roots = Vec::new();
+
+    for root in workspace_roots::list_workspace_roots(&pool, machine_id).await? {
+        let paths = match find_mcp_json_files(&pool, &ssh_pool, &root).await {
+            Ok(paths) => paths,
+            Err(error) => {
+                println!("[MCP JSON] Failed to search {}: {}", root.path, error);
+                failed_roots.push(FailedWorkspaceRoot { root_id: root.id, path: root.path, error });
+                continue;
+            }
+        };
+
+        for path in paths {
+            // Overlapping roots find the same file twice
+            if files.iter().any(|f: &McpJsonFile| f.path == path) {
+                continue;
+            }
+            let parsed = config::read_config_file(&pool, &ssh_pool, machine_id, &path)
+                .await
+                .and_then(|content| parse_mcp_json(content.as_deref()));
+            let (server_names, error) = match parsed {
+                Ok(mcp_json) => {
+                    let mut names: Vec<String> = mcp_json.mcp_servers.into_keys().collect();
+                    names.sort();
+                    (names, None)
+                }
+                Err(e) => (vec![], Some(e)),
+            };
+            files.push(McpJsonFile { project_path: project_dir(&path), path, root_id: root.id, server_names, error });
+        }
+    }
+
+    println!("[MCP JSON] Found {} .mcp.json file(s)", files.len());
+    Ok(McpJsonDiscovery { files, failed_roots })
+}
+
+#[tauri::command]
+pub async fn read_mcp_json(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    machine_id: Option<i64>,
+    path: String,
+) -> Result<McpJsonConfig, String> {
+    let content = config::read_config_file(&pool, &ssh_pool, machine_id, &path)
+        .await?
+        .ok_or_else(|| format!("{} does not exist", path))?;
+    parse_mcp_json(Some(&content))
+}
+
+/// Insert or replace a server; creates the `.mcp.json` if the project doesn't have one yet
+#[tauri::command]
+pub async fn update_mcp_json_server(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    machine_id: Option<i64>,
+    path: String,
+    server_name: String,
+    server_config: ClaudeMCPServer,
+) -> Result<(), String> {
+    config::update_config_file(&pool, &ssh_pool, machine_id, &path, "update_server", |content| {
+        let mut mcp_json = parse_mcp_json(content)?;
+        mcp_json.mcp_servers.insert(server_name, server_config);
+        write_checked(&path, content, &mcp_json)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_mcp_json_server(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    machine_id: Option<i64>,
+    path: String,
+    server_name: String,
+) -> Result<(), String> {
+    config::update_config_file(&pool, &ssh_pool, machine_id, &path, "delete_server", |content| {
+        let content = content.ok_or_else(|| format!("{} does not exist", path))?;
+        let mut mcp_json = parse_mcp_json(Some(content))?;
+        mcp_json.mcp_servers.remove(&server_name);
+        write_checked(&path, Some(content), &mcp_json)
+    })
+    .await
+}
+
+/// Copy servers between a project's `.mcp.json` and the user's global Claude config on the same machine.
+/// Only the target file is written. Returns how many servers were copied.
+#[tauri::command]
+pub async fn sync_mcp_json_servers(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    machine_id: Option<i64>,
+    path: String,
+    claude_path: Option<String>,
+    server_names: Vec<String>,
+    direction: McpJsonSyncDirection,
+) -> Result<usize, String> {
+    let claude_path = sources::resolve_source_path(&pool, &ssh_pool, SourceKind::Claude.source(), machine_id, claude_path).await?;
+    let mut copied = 0;
+
+    match direction {
+        McpJsonSyncDirection::FromGlobal => {
+            let content = config::read_config_file(&pool, &ssh_pool, machine_id, &claude_path).await?;
+            let global: ClaudeConfig = match content {
+                Some(c) => serde_json::from_str(&c).map_err(|e| format!("Failed to parse Claude config: {}", e))?,
+                None => return Err(format!("{} does not exist", claude_path)),
+            };
+            config::update_config_file(&pool, &ssh_pool, machine_id, &path, "sync", |content| {
+                let mut mcp_json = parse_mcp_json(content)?;
+                copied = copy_from_global(&global, &mut mcp_json, &server_names);
+                write_checked(&path, content, &mcp_json)
+            })
+            .await?;
+        }
+        McpJsonSyncDirection::ToGlobal => {
+            let content = config::read_config_file(&pool, &ssh_pool, machine_id, &path)
+                .await?
+                .ok_or_else(|| format!("{} does not exist", path))?;
+            let mcp_json = parse_mcp_json(Some(&content))?;
+            config::update_config_file(&pool, &ssh_pool, machine_id, &claude_path, "sync", |content| {
+                let mut global: ClaudeConfig = match content {
+                    Some(c) => serde_json::from_str(c).map_err(|e| format!("Failed to parse Claude config: {}", e))?,
+                    None => ClaudeConfig::default(),
+                };
+                copied = copy_to_global(&mcp_json, &mut global, &server_names);
+                write_checked(&claude_path, content, &global)
+            })
+            .await?;
+        }
+    }
+
+    Ok(copied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_local_mcp_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for project in ["app", "org/service", "app/node_modules/pkg", ".cache/x", "a/b/c/d/e"] {
+            fs::create_dir_all(root.join(project)).unwrap();
+            fs::write(root.join(project).join(MCP_JSON_FILE_NAME), "{}").unwrap();
+        }
+
+        let found = find_local_mcp_json(root, MAX_DEPTH).unwrap();
+        let projects: Vec<String> = found
+            .iter()
+            .map(|p| project_dir(p).strip_prefix(&*root.to_string_lossy()).unwrap().to_string())
+            .collect();
+        assert_eq!(projects, vec!["/app", "/org/service"]);
+    }
+
+    #[test]
+    fn test_copy_between_global_and_mcp_json() {
+        let mut global: ClaudeConfig = serde_json::from_str(
+            r#"{"mcpServers": {"github": {"command": "npx", "isActive": true}, "_disabled_db": {"command": "db"}}}"#,
+        )
+        .unwrap();
+        let mut mcp_json = McpJsonConfig::default();
+
+        let names = vec!["github".to_string(), "db".to_string()];
+        assert_eq!(copy_from_global(&global, &mut mcp_json, &names), 1);
+        assert_eq!(mcp_json.mcp_servers["github"].is_active, None);
+
+        mcp_json.mcp_servers.insert("db".to_string(), ClaudeMCPServer { command: Some("db2".to_string()), ..Default::default() });
+        assert_eq!(copy_to_global(&mcp_json, &mut global, &["db".to_string()]), 1);
+        assert!(!global.mcp_servers.contains_key("_disabled_db"));
+        assert_eq!(global.mcp_servers["db"].command.as_deref(), Some("db2"));
+    }
+}
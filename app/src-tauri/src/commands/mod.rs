@@ -1,18 +1,34 @@
+pub mod backups;
 pub mod config;
 pub mod read;
 pub mod ssh;
 pub mod machines;
+pub mod mcp_json;
 pub mod remote;
 pub mod sync;
 pub mod ssh_keys;
 pub mod system;
+pub mod fanout;
+pub mod projects;
+pub mod ssh_config;
+pub mod sources;
+pub mod probe;
+pub mod inventory;
 
+pub use backups::*;
 pub use config::*;
 pub use read::*;
 pub use ssh::*;
 pub use machines::*;
+pub use mcp_json::*;
 pub use remote::*;
 pub use sync::*;
 pub use ssh_keys::*;
 pub use system::*;
+pub use fanout::*;
+pub use projects::*;
+pub use ssh_config::*;
+pub use sources::*;
+pub use probe::*;
+pub use inventory::*;
 
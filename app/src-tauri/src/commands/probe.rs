@@ -0,0 +1,60 @@
+use crate::commands::{inventory, remote, sources, sync};
+use crate::config::SourceKind;
+use crate::probe::{self, http, stdio, ProbeReport};
+use crate::ssh::pool::SshPool;
+use sqlx::{Pool, Sqlite};
+use std::time::Duration;
+use tauri::State;
+
+/// Start (stdio) or connect to (HTTP/SSE) one of a source's servers from where it is configured, this host or
+/// the machine, and check that it answers `initialize` and `tools/list`. What it lists goes into the inventory catalog.
+#[tauri::command]
+pub async fn probe_source_server(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    source: SourceKind,
+    machine_id: Option<i64>,
+    path: Option<String>,
+    server_name: String,
+) -> Result<ProbeReport, String> {
+    let config_source = source.source();
+    let (path, content) = sources::read_source_file(&pool, &ssh_pool, config_source, machine_id, path).await?;
+    let doc = config_source.parse(&path, content.as_deref()).map_err(|e| format!("{} ({})", e, path))?;
+    let native = doc
+        .servers()?
+        .remove(&server_name)
+        .ok_or_else(|| format!("No server '{}' in {}", server_name, path))?;
+
+    let platform = sync::resolve_platform(&pool, machine_id).await?;
+    let server = config_source.to_canonical(&native, platform)?;
+    let timeout = server.startup_timeout_ms.map(Duration::from_millis).unwrap_or(probe::DEFAULT_TIMEOUT);
+
+    // A remote server is reached from the machine itself, so its network path is what gets tested
+    let report = match (machine_id, server.transport.is_remote()) {
+        (Some(id), remote_server) => {
+            let (target, _) = remote::get_connection_info(&pool, id).await?;
+            let ssh_pool = ssh_pool.inner().clone();
+            if remote_server {
+                http::probe_remote(ssh_pool, &target, &server, platform, timeout).await
+            } else {
+                stdio::probe_remote(ssh_pool, &target, &server, platform, timeout).await
+            }
+        }
+        (None, true) => http::probe_local(&server, timeout).await,
+        (None, false) => stdio::probe_local(&server, timeout).await,
+    };
+    match &report.error {
+        Some(error) => println!("[Probe] '{}' failed: {}", server_name, error),
+        None => println!("[Probe] '{}' answered in {}ms", server_name, report.startup_ms.unwrap_or_default()),
+    }
+    // A catalog failure shouldn't hide the probe result
+    let recorded = match (&report.error, &report.inventory) {
+        (None, Some(listed)) => inventory::record_probe_inventory(&pool, machine_id, source, &server_name, listed).await,
+        (Some(_), _) => inventory::record_probe_failure(&pool, machine_id, source, &server_name).await,
+        (None, None) => Ok(()),
+    };
+    if let Err(e) = recorded {
+        println!("[Inventory] Failed to record '{}': {}", server_name, e);
+    }
+    Ok(report)
+}
@@ -0,0 +1,288 @@
+use crate::commands::config::{self, BatchToggleItem};
+use crate::commands::sources;
+use crate::config::json_edit::write_json;
+use crate::config::schema;
+use crate::config::{put_claude_server, ClaudeConfig, ClaudeMCPServer, ClaudeProjectConfig, SourceKind};
+use crate::ssh::pool::SshPool;
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use std::collections::HashMap;
+use tauri::State;
+
+/// MCP-related state of one project in `.claude.json` (the rest of the project entry is left alone)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaudeProjectEntry {
+    pub project_path: String,
+    pub mcp_servers: HashMap<String, ClaudeMCPServer>,
+    pub enabled_mcpjson_servers: Vec<String>,
+    pub disabled_mcpjson_servers: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaudeProjectsResponse {
+    pub path: String,
+    pub exists: bool,
+    pub projects: Vec<ClaudeProjectEntry>,
+}
+
+fn parse_claude_config(content: Option<&str>) -> Result<ClaudeConfig, String> {
+    match content {
+        Some(c) => serde_json::from_str(c).map_err(|e| format!("Failed to parse JSON: {}", e)),
+        None => Ok(ClaudeConfig::default()),
+    }
+}
+
+fn existing_project<'a>(config: &'a mut ClaudeConfig, project_path: &str) -> Result<&'a mut ClaudeProjectConfig, String> {
+    config
+        .projects
+        .get_mut(project_path)
+        .ok_or_else(|| format!("Project '{}' not found in Claude config", project_path))
+}
+
+/// Servers of a scope: the global `mcpServers` (`None`) or a project's
+fn scope_servers<'a>(config: &'a mut ClaudeConfig, project_path: Option<&str>) -> &'a mut HashMap<String, ClaudeMCPServer> {
+    match project_path {
+        Some(p) => &mut config.projects.entry(p.to_string()).or_default().mcp_servers,
+        None => &mut config.mcp_servers,
+    }
+}
+
+/// Copy servers between the global scope and projects. Disabled servers are copied with their disabled name.
+fn copy_servers(config: &mut ClaudeConfig, from: Option<&str>, to: Option<&str>, server_names: &[String]) -> Result<usize, String> {
+    if from == to {
+        return Err("Source and target scope are the same".to_string());
+    }
+
+    let source = match from {
+        Some(p) => &existing_project(config, p)?.mcp_servers,
+        None => &config.mcp_servers,
+    };
+    let copied: Vec<(String, ClaudeMCPServer)> = server_names
+        .iter()
+        .filter_map(|name| {
+            let disabled_name = format!("_disabled_{}", name);
+            source
+                .get(name)
+                .map(|s| (name.clone(), s.clone()))
+                .or_else(|| source.get(&disabled_name).map(|s| (disabled_name, s.clone())))
+        })
+        .collect();
+
+    let target = scope_servers(config, to);
+    for (name, server) in &copied {
+        // Replace whichever state the target had
+        let base = name.strip_prefix("_disabled_").unwrap_or(name);
+        target.remove(base);
+        target.remove(&format!("_disabled_{}", base));
+        target.insert(name.clone(), server.clone());
+    }
+    Ok(copied.len())
+}
+
+/// Add or replace a project's server, keeping a disabled one disabled
+fn put_project_server(config: &mut ClaudeConfig, project_path: &str, server_name: &str, server: ClaudeMCPServer) {
+    put_claude_server(scope_servers(config, Some(project_path)), server_name, server);
+}
+
+/// Read-modify-write the Claude config's project data, locally or on a machine
+async fn modify_claude_config(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    machine_id: Option<i64>,
+    path: Option<String>,
+    reason: &str,
+    modify: impl FnOnce(&mut ClaudeConfig) -> Result<(), String>,
+) -> Result<(), String> {
+    let path = sources::resolve_source_path(pool, ssh_pool, SourceKind::Claude.source(), machine_id, path).await?;
+    config::update_config_file(pool, ssh_pool, machine_id, &path, reason, |content| {
+        let mut claude = parse_claude_config(content)?;
+        modify(&mut claude)?;
+        let output = write_json(content, &claude)?;
+        schema::check_write(SourceKind::Claude.source(), &path, content, &output)?;
+        Ok(output)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn list_claude_projects(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    machine_id: Option<i64>,
+    path: Option<String>,
+) -> Result<ClaudeProjectsResponse, String> {
+    let path = sources::resolve_source_path(&pool, &ssh_pool, SourceKind::Claude.source(), machine_id, path).await?;
+    let content = config::read_config_file(&pool, &ssh_pool, machine_id, &path).await?;
+    let claude = parse_claude_config(content.as_deref())?;
+
+    let mut projects: Vec<ClaudeProjectEntry> = claude
+        .projects
+        .into_iter()
+        .map(|(project_path, project)| ClaudeProjectEntry {
+            project_path,
+            mcp_servers: project.mcp_servers,
+            enabled_mcpjson_servers: project.enabled_mcpjson_servers,
+            disabled_mcpjson_servers: project.disabled_mcpjson_servers,
+        })
+        .collect();
+    projects.sort_by(|a, b| a.project_path.cmp(&b.project_path));
+
+    Ok(ClaudeProjectsResponse { path, exists: content.is_some(), projects })
+}
+
+#[tauri::command]
+pub async fn update_claude_project_server(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    machine_id: Option<i64>,
+    path: Option<String>,
+    project_path: String,
+    server_name: String,
+    server_config: ClaudeMCPServer,
+) -> Result<(), String> {
+    modify_claude_config(&pool, &ssh_pool, machine_id, path, "update_project_server", |claude| {
+        put_project_server(claude, &project_path, &server_name, server_config);
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_claude_project_server(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    machine_id: Option<i64>,
+    path: Option<String>,
+    project_path: String,
+    server_name: String,
+) -> Result<(), String> {
+    modify_claude_config(&pool, &ssh_pool, machine_id, path, "delete_project_server", |claude| {
+        let project = existing_project(claude, &project_path)?;
+        project.mcp_servers.remove(&server_name);
+        project.mcp_servers.remove(&format!("_disabled_{}", server_name));
+        Ok(())
+    })
+    .await
+}
+
+/// Enable/disable a project's own servers (same `_disabled_` convention as global servers)
+#[tauri::command]
+pub async fn toggle_claude_project_servers(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    machine_id: Option<i64>,
+    path: Option<String>,
+    project_path: String,
+    items: Vec<BatchToggleItem>,
+) -> Result<(), String> {
+    if items.is_empty() {
+        return Ok(());
+    }
+    modify_claude_config(&pool, &ssh_pool, machine_id, path, "toggle_project_servers", |claude| {
+        config::apply_claude_toggles(&mut existing_project(claude, &project_path)?.mcp_servers, items);
+        Ok(())
+    })
+    .await
+}
+
+/// Approve or reject servers from the project's `.mcp.json`, as Claude Code's own prompt does
+#[tauri::command]
+pub async fn set_claude_project_mcpjson_servers(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    machine_id: Option<i64>,
+    path: Option<String>,
+    project_path: String,
+    items: Vec<BatchToggleItem>,
+) -> Result<(), String> {
+    if items.is_empty() {
+        return Ok(());
+    }
+    modify_claude_config(&pool, &ssh_pool, machine_id, path, "toggle_project_servers", |claude| {
+        let project = existing_project(claude, &project_path)?;
+        for item in items {
+            project.enabled_mcpjson_servers.retain(|n| n != &item.name);
+            project.disabled_mcpjson_servers.retain(|n| n != &item.name);
+            if item.enabled {
+                project.enabled_mcpjson_servers.push(item.name);
+            } else {
+                project.disabled_mcpjson_servers.push(item.name);
+            }
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Copy servers between the global scope (`None`) and a project, or between two projects, in one config file
+#[tauri::command]
+pub async fn sync_claude_project_servers(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    machine_id: Option<i64>,
+    path: Option<String>,
+    from_project: Option<String>,
+    to_project: Option<String>,
+    server_names: Vec<String>,
+) -> Result<usize, String> {
+    let mut copied = 0;
+    modify_claude_config(&pool, &ssh_pool, machine_id, path, "sync", |claude| {
+        copied = copy_servers(claude, from_project.as_deref(), to_project.as_deref(), &server_names)?;
+        Ok(())
+    })
+    .await?;
+    Ok(copied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projects_round_trip_and_copy() {
+        let json = r#"{
+            "mcpServers": {"github": {"command": "npx", "args": ["-y", "gh"]}},
+            "projects": {
+                "/home/me/app": {
+                    "mcpServers": {"_disabled_db": {"command": "db-mcp", "isActive": false}},
+                    "enabledMcpjsonServers": ["docs"],
+                    "disabledMcpjsonServers": [],
+                    "allowedTools": ["Bash"],
+                    "history": [{"display": "hi"}]
+                }
+            },
+            "numStartups": 12
+        }"#;
+        let mut claude: ClaudeConfig = serde_json::from_str(json).unwrap();
+        let project = &claude.projects["/home/me/app"];
+        assert_eq!(project.enabled_mcpjson_servers, vec!["docs"]);
+        assert!(project.other_fields.contains_key("history"));
+
+        // Global -> project, and a disabled project server back to global
+        copy_servers(&mut claude, None, Some("/home/me/app"), &["github".to_string()]).unwrap();
+        copy_servers(&mut claude, Some("/home/me/app"), None, &["db".to_string()]).unwrap();
+        assert!(claude.projects["/home/me/app"].mcp_servers.contains_key("github"));
+        assert!(claude.mcp_servers.contains_key("_disabled_db"));
+        assert!(copy_servers(&mut claude, Some("/nope"), None, &["db".to_string()]).is_err());
+
+        // Unmodelled fields survive a write
+        let out: serde_json::Value = serde_json::to_value(&claude).unwrap();
+        assert_eq!(out["numStartups"], 12);
+        assert_eq!(out["projects"]["/home/me/app"]["allowedTools"][0], "Bash");
+    }
+
+    #[test]
+    fn test_editing_disabled_project_server_keeps_it_disabled() {
+        let json = r#"{"projects": {"/home/me/app": {"mcpServers": {"_disabled_db": {"command": "db-mcp", "isActive": false}}}}}"#;
+        let mut claude: ClaudeConfig = serde_json::from_str(json).unwrap();
+
+        let edited = ClaudeMCPServer { command: Some("db-mcp-v2".to_string()), is_active: Some(false), ..Default::default() };
+        put_project_server(&mut claude, "/home/me/app", "db", edited);
+
+        let servers = &claude.projects["/home/me/app"].mcp_servers;
+        assert!(!servers.contains_key("db"));
+        assert_eq!(servers["_disabled_db"].command.as_deref(), Some("db-mcp-v2"));
+    }
+}
@@ -1,5 +1,5 @@
-use crate::config::{ClaudeConfig, OpenCodeConfig};
-use crate::paths::{get_app_paths, AppPaths};
+use crate::config::{ClaudeConfig, OpenCodeConfig, SourceKind};
+use crate::paths::get_app_paths;
 
 #[tauri::command]
 pub async fn read_claude_config(path: String) -> Result<ClaudeConfig, String> {
@@ -23,7 +23,7 @@ pub async fn read_opencode_config(path: String) -> Result<OpenCodeConfig, String
             err_msg
         })?;
 
-    let config: OpenCodeConfig = serde_json::from_str(&content)
+    let config = OpenCodeConfig::parse(Some(&content))
         .map_err(|e| {
             let err_msg = format!("Failed to parse JSON in '{}': {}", path, e);
             println!("{}", err_msg);
@@ -39,16 +39,36 @@ pub async fn get_default_config_paths() -> Result<ConfigPaths, String> {
     let paths = get_app_paths()
         .ok_or_else(|| "Could not determine home directory".to_string())?;
 
+    let vscode_path = SourceKind::VsCode.source().local_path(&paths);
+    let opencode_path = SourceKind::OpenCode.source().local_path(&paths);
     let claude_path = paths.claude_path;
-    let opencode_path = paths.opencode_path;
+    let claude_desktop_path = paths.claude_desktop_path;
+    let codex_path = paths.codex_path;
+    let cursor_path = paths.cursor_path;
+    let gemini_path = paths.gemini_path;
 
     println!("Detected paths:");
     println!("  Claude: {}", claude_path.display());
+    println!("  Claude Desktop: {}", claude_desktop_path.display());
+    println!("  Codex: {}", codex_path.display());
+    println!("  Cursor: {}", cursor_path.display());
+    println!("  Gemini: {}", gemini_path.display());
+    println!("  VS Code: {}", vscode_path.display());
     println!("  OpenCode: {}", opencode_path.display());
 
     Ok(ConfigPaths {
         claude: claude_path.to_string_lossy().to_string(),
         claude_exists: claude_path.exists(),
+        claude_desktop: claude_desktop_path.to_string_lossy().to_string(),
+        claude_desktop_exists: claude_desktop_path.exists(),
+        codex: codex_path.to_string_lossy().to_string(),
+        codex_exists: codex_path.exists(),
+        cursor: cursor_path.to_string_lossy().to_string(),
+        cursor_exists: cursor_path.exists(),
+        gemini: gemini_path.to_string_lossy().to_string(),
+        gemini_exists: gemini_path.exists(),
+        vscode: vscode_path.to_string_lossy().to_string(),
+        vscode_exists: vscode_path.exists(),
         opencode: opencode_path.to_string_lossy().to_string(),
         opencode_exists: opencode_path.exists(),
     })
@@ -58,6 +78,16 @@ pub async fn get_default_config_paths() -> Result<ConfigPaths, String> {
 pub struct ConfigPaths {
     pub claude: String,
     pub claude_exists: bool,
+    pub claude_desktop: String,
+    pub claude_desktop_exists: bool,
+    pub codex: String,
+    pub codex_exists: bool,
+    pub cursor: String,
+    pub cursor_exists: bool,
+    pub gemini: String,
+    pub gemini_exists: bool,
+    pub vscode: String,
+    pub vscode_exists: bool,
     pub opencode: String,
     pub opencode_exists: bool,
 }
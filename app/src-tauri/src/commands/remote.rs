@@ -1,6 +1,10 @@
-use crate::config::{ClaudeConfig, OpenCodeConfig, ClaudeMCPServer, OpenCodeMCPServer, platform_adapter};
-use crate::ssh::{key_manager, sftp, connection, pool::SshPool};
+use crate::config::{to_json, ClaudeConfig, ClaudeMCPServer, ConfigSource, InstallCheck, OpenCodeConfig, OpenCodeMCPServer, SourceKind};
+use crate::ssh::{key_manager, sftp, connection, host_keys, passphrase, pool::SshPool};
+use crate::ssh::connection::{SshAuth, SshTarget};
+use crate::commands::config::BatchToggleItem;
+use crate::commands::sources;
 use crate::db::{machines, ssh_keys};
+use crate::file_version::FileVersion;
 use sqlx::{Pool, Sqlite};
 use tauri::State;
 use serde::Serialize;
@@ -13,97 +17,147 @@ pub struct RemoteConfigResponse<T> {
     pub app_installed: bool,
 }
 
-pub(crate) async fn get_connection_info(pool: &Pool<Sqlite>, machine_id: i64) -> Result<(String, u16, String, String, String), String> {
+/// Resolve a machine into an SSH target (decrypted key + trusted host key + jump chain) and its platform
+pub(crate) async fn get_connection_info(pool: &Pool<Sqlite>, machine_id: i64) -> Result<(SshTarget, String), String> {
     let machine = machines::get_machine(pool, machine_id).await?;
-    let master_key = key_manager::get_or_create_master_key()?;
-    let private_key = ssh_keys::get_ssh_key(pool, machine.ssh_key_id, &master_key).await?;
+    let jump = jump_target(pool, &machine).await?;
+    let target = machine_target(pool, &machine, jump).await?;
+    Ok((target, machine.platform))
+}
+
+/// Target for the machine's jump host, with the rest of the chain nested inside it (None without a bastion)
+pub(crate) async fn jump_target(pool: &Pool<Sqlite>, machine: &machines::Machine) -> Result<Option<Box<SshTarget>>, String> {
+    // Walk outwards: machine -> its jump host -> that host's jump host ...
+    let mut chain: Vec<machines::Machine> = Vec::new();
+    let mut next = machine.jump_host_id;
+    while let Some(id) = next {
+        if id == machine.id || chain.iter().any(|m| m.id == id) || chain.len() >= machines::MAX_JUMP_DEPTH {
+            return Err(format!("Jump host chain of machine '{}' loops or is too long", machine.name));
+        }
+        let hop = machines::get_machine(pool, id).await?;
+        next = hop.jump_host_id;
+        chain.push(hop);
+    }
+
+    // Build from the outermost bastion inwards, so each host key probe can tunnel through the previous hop
+    let mut jump = None;
+    for hop in chain.iter().rev() {
+        jump = Some(Box::new(machine_target(pool, hop, jump).await?));
+    }
+    Ok(jump)
+}
+
+async fn machine_target(pool: &Pool<Sqlite>, machine: &machines::Machine, jump: Option<Box<SshTarget>>) -> Result<SshTarget, String> {
+    let auth = match (machine.auth_method.as_str(), machine.ssh_key_id) {
+        ("agent", _) => SshAuth::Agent,
+        (_, Some(key_id)) => {
+            let master_key = key_manager::get_or_create_master_key()?;
+            let creds = ssh_keys::get_ssh_key_credentials(pool, key_id, &master_key).await?;
+            // Keys added before encryption was tracked are detected from their content
+            let passphrase = if creds.is_encrypted || passphrase::is_key_encrypted(&creds.private_key) {
+                // A passphrase unlocked this session is the most recent one the user typed
+                let unlocked = passphrase::cached_passphrase(key_id).or(creds.passphrase);
+                Some(unlocked.ok_or(passphrase::KeyLockedError { key_id, name: creds.name })?)
+            } else {
+                None
+            };
+            SshAuth::PrivateKey { key: creds.private_key, passphrase }
+        }
+        (_, None) => return Err(format!("Machine '{}' has no SSH key configured", machine.name)),
+    };
     let port = machine.port.try_into().unwrap_or(22);
-    Ok((machine.host, port, machine.username, private_key, machine.platform))
+    let host_key = host_keys::trusted_host_key(pool, &machine.host, port, jump.as_deref()).await?;
+    Ok(SshTarget {
+        host: machine.host.clone(),
+        port,
+        username: machine.username.clone(),
+        auth,
+        host_key,
+        jump,
+    })
 }
 
 /// SECURITY: Use SFTP instead of shell commands to prevent injection vulnerabilities
 pub(crate) async fn read_remote_file(pool: &Pool<Sqlite>, ssh_pool: &SshPool, machine_id: i64, path: &str) -> Result<String, String> {
-    let (host, port, username, private_key, _) = get_connection_info(pool, machine_id).await?;
-    sftp::sftp_read_file_with_pool(ssh_pool.clone(), &host, port, &username, &private_key, path).await
+    let (target, _) = get_connection_info(pool, machine_id).await?;
+    sftp::sftp_read_file_with_pool(ssh_pool.clone(), &target, path).await
 }
 
-/// SECURITY: Use SFTP instead of shell commands to prevent injection vulnerabilities
-pub(crate) async fn write_remote_file(pool: &Pool<Sqlite>, ssh_pool: &SshPool, machine_id: i64, path: &str, content: &str) -> Result<(), String> {
-    let (host, port, username, private_key, _) = get_connection_info(pool, machine_id).await?;
-    sftp::sftp_write_file_with_pool(ssh_pool.clone(), &host, port, &username, &private_key, path, content).await
+/// Read a file for a read-modify-write; pass the returned version to `write_remote_file`.
+/// `None` content means the file doesn't exist yet.
+pub(crate) async fn read_remote_file_versioned(pool: &Pool<Sqlite>, ssh_pool: &SshPool, machine_id: i64, path: &str) -> Result<(Option<String>, FileVersion), String> {
+    let (target, _) = get_connection_info(pool, machine_id).await?;
+    sftp::sftp_read_file_versioned_with_pool(ssh_pool.clone(), &target, path).await
 }
 
 /// SECURITY: Use SFTP instead of shell commands to prevent injection vulnerabilities
-pub(crate) async fn backup_remote_file(pool: &Pool<Sqlite>, ssh_pool: &SshPool, machine_id: i64, path: &str) -> Result<(), String> {
-    let (host, port, username, private_key, _) = get_connection_info(pool, machine_id).await?;
-    
-    // Create timestamped backup
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    let backup_path = format!("{}.bak.{}", path, timestamp);
-    
-    sftp::sftp_copy_file_with_pool(ssh_pool.clone(), &host, port, &username, &private_key, path, &backup_path).await
+///
+/// Fails with a WRITE_CONFLICT error if the file no longer matches `expected`.
+pub(crate) async fn write_remote_file(pool: &Pool<Sqlite>, ssh_pool: &SshPool, machine_id: i64, path: &str, content: &str, expected: Option<&FileVersion>) -> Result<FileVersion, String> {
+    let (target, _) = get_connection_info(pool, machine_id).await?;
+    sftp::sftp_write_file_with_pool(ssh_pool.clone(), &target, path, content, expected.cloned()).await
 }
 
-async fn check_claude_installed_v2(pool: &Pool<Sqlite>, ssh_pool: &SshPool, machine_id: i64) -> bool {
-    let (_, _, _, _, platform) = match get_connection_info(pool, machine_id).await {
-        Ok(info) => info,
-        Err(_) => return false,
-    };
+/// Whether an SFTP read error just means the file isn't there (as opposed to a permission/transport problem)
+pub(crate) fn is_missing_file_error(err: &str) -> bool {
+    let e_lower = err.to_lowercase();
+    e_lower.contains("no such file") || e_lower.contains("code 2") || e_lower.contains("does not exist")
+}
 
-    let cmd = match platform.to_lowercase().as_str() {
-        "macos" | "darwin" => "test -d '/Applications/Claude.app' && echo INSTALLED",
-        "windows" => "powershell -Command \"if (Test-Path '$env:LOCALAPPDATA\\Anthropic\\Claude\\Claude.exe') { Write-Host 'INSTALLED' }\"",
-        // For Linux, check common paths or flatpak
-        "linux" => "which claude >/dev/null 2>&1 && echo INSTALLED || test -d /opt/Claude && echo INSTALLED || flatpak list | grep com.anthropic.Claude && echo INSTALLED",
-        _ => return false,
-    };
-    
-    check_app_installed_generic(pool, ssh_pool, machine_id, cmd).await
+/// Whether the source's client is installed on the machine
+pub(crate) async fn check_source_installed(pool: &Pool<Sqlite>, ssh_pool: &SshPool, machine_id: i64, source: &dyn ConfigSource) -> bool {
+    match source.install_check() {
+        InstallCheck::Cli(binary) => check_cli_installed(pool, ssh_pool, machine_id, binary).await,
+        InstallCheck::Shell(by_platform) => match platform_default(pool, machine_id, by_platform).await {
+            Ok(cmd) => check_app_installed_generic(pool, ssh_pool, machine_id, cmd).await,
+            Err(_) => false,
+        },
+    }
 }
 
 async fn check_app_installed_generic(pool: &Pool<Sqlite>, ssh_pool: &SshPool, machine_id: i64, check_cmd: &str) -> bool {
-    let (host, port, username, private_key, _) = match get_connection_info(pool, machine_id).await {
+    let (target, _) = match get_connection_info(pool, machine_id).await {
         Ok(info) => info,
         Err(_) => return false,
     };
     
-    match connection::execute_cmd_with_pool(ssh_pool.clone(), &host, port, &username, &private_key, check_cmd).await {
+    match connection::execute_cmd_with_pool(ssh_pool.clone(), &target, check_cmd).await {
         Ok(output) => output.trim().contains("INSTALLED"),
         Err(_) => false,
     }
 }
 
-async fn check_opencode_installed(pool: &Pool<Sqlite>, ssh_pool: &SshPool, machine_id: i64) -> bool {
-    let (_, _, _, _, platform) = match get_connection_info(pool, machine_id).await {
+async fn check_cli_installed(pool: &Pool<Sqlite>, ssh_pool: &SshPool, machine_id: i64, binary: &str) -> bool {
+    let (_, platform) = match get_connection_info(pool, machine_id).await {
         Ok(info) => info,
         Err(_) => return false,
     };
 
     let cmd = match platform.to_lowercase().as_str() {
-        "macos" | "darwin" | "linux" => "which opencode >/dev/null 2>&1 && echo INSTALLED",
-        "windows" => "powershell -Command \"if (Get-Command opencode -ErrorAction SilentlyContinue) { Write-Host 'INSTALLED' }\"",
+        "macos" | "darwin" | "linux" => format!("which {} >/dev/null 2>&1 && echo INSTALLED", binary),
+        "windows" => format!("powershell -Command \"if (Get-Command {} -ErrorAction SilentlyContinue) {{ Write-Host 'INSTALLED' }}\"", binary),
         _ => return false,
     };
 
-    check_app_installed_generic(pool, ssh_pool, machine_id, cmd).await
+    check_app_installed_generic(pool, ssh_pool, machine_id, &cmd).await
 }
 
 async fn check_remote_file_exists_via_shell(pool: &Pool<Sqlite>, ssh_pool: &SshPool, machine_id: i64, path: &str) -> Result<bool, String> {
-    let (host, port, username, private_key, platform) = get_connection_info(pool, machine_id).await?;
+    let (target, platform) = get_connection_info(pool, machine_id).await?;
     
     let cmd = if platform.to_lowercase() == "windows" {
         // PowerShell command for Windows
         format!("powershell -Command \"if (Test-Path '{}') {{ Write-Host 'MCP_FILE_EXISTS' }} else {{ Write-Host 'MCP_FILE_MISSING' }}\"", path)
     } else {
-        // Sh command for Linux/Unix
-        format!("test -f {} && echo MCP_FILE_EXISTS || echo MCP_FILE_MISSING", path)
+        // Sh command for Linux/Unix. Quoted for paths with spaces, leaving `~/` outside the quotes so it expands
+        let quoted = match path.strip_prefix("~/") {
+            Some(rest) => format!("~/'{}'", rest.replace('\'', "'\\''")),
+            None => format!("'{}'", path.replace('\'', "'\\''")),
+        };
+        format!("test -f {} && echo MCP_FILE_EXISTS || echo MCP_FILE_MISSING", quoted)
     };
     
-    match connection::execute_cmd_with_pool(ssh_pool.clone(), &host, port, &username, &private_key, &cmd).await {
+    match connection::execute_cmd_with_pool(ssh_pool.clone(), &target, &cmd).await {
         Ok(output) => {
             Ok(output.contains("MCP_FILE_EXISTS"))
         },
@@ -114,30 +168,51 @@ async fn check_remote_file_exists_via_shell(pool: &Pool<Sqlite>, ssh_pool: &SshP
     }
 }
 
-async fn find_claude_config(pool: &Pool<Sqlite>, ssh_pool: &SshPool, machine_id: i64) -> Result<(String, Option<String>), String> {
-    let candidates = vec![
-        "~/.claude.json", // Linux/Mac default
-        "~/AppData/Roaming/Claude/claude.json", // Windows default
-        "~/Library/Application Support/Claude/claude.json", // Mac alternative?
-        "~/.config/Claude/claude.json", // Linux XDG style?
-    ];
+/// The source's config on a machine as `(path, content)`: the first candidate it accepts, else (with no content)
+/// where to create one for the machine's platform
+pub(crate) async fn find_source_config(pool: &Pool<Sqlite>, ssh_pool: &SshPool, machine_id: i64, source: &dyn ConfigSource) -> Result<(String, Option<String>), String> {
+    let location = source.remote_location();
+    let candidates: Vec<&str> = location.candidates.iter().map(String::as_str).collect();
+    let accept = |path: &str, content: &str| source.accepts(path, content);
+    if let Some((path, content)) = probe_config_candidates(pool, ssh_pool, machine_id, &candidates, accept).await? {
+        return Ok((path, Some(content)));
+    }
+
+    let default_path = platform_default(pool, machine_id, location.default_by_platform.each_ref().map(String::as_str)).await?;
+    Ok((default_path.to_string(), None))
+}
+
+/// Pick the candidate for the machine's platform: `[macOS, Windows, Linux]`
+async fn platform_default<'a>(pool: &Pool<Sqlite>, machine_id: i64, by_platform: [&'a str; 3]) -> Result<&'a str, String> {
+    let (_, platform) = get_connection_info(pool, machine_id).await?;
+    Ok(match platform.to_lowercase().as_str() {
+        "macos" | "darwin" => by_platform[0],
+        "windows" => by_platform[1],
+        _ => by_platform[2],
+    })
+}
 
-    for path in &candidates {
+/// First candidate that can be read and passes `accept`, as `(path, content)`. A file that exists but can't be
+/// read is an error rather than a reason to fall through to a default path that would then be overwritten.
+async fn probe_config_candidates(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    machine_id: i64,
+    candidates: &[&str],
+    accept: impl Fn(&str, &str) -> bool,
+) -> Result<Option<(String, String)>, String> {
+    for path in candidates {
         match read_remote_file(pool, ssh_pool, machine_id, path).await {
             Ok(content) => {
-                // Verify content is valid JSON to ensure it's the right file
-                if serde_json::from_str::<serde_json::Value>(&content).is_ok() {
-                    return Ok((path.to_string(), Some(content)));
-                } else {
-                    println!("[Remote] Found file at {} but invalid JSON, skipping", path);
+                if accept(path, &content) {
+                    return Ok(Some((path.to_string(), content)));
                 }
+                println!("[Remote] Found file at {} but it is not a usable config, skipping", path);
             }
             Err(e) => {
-                let e_lower = e.to_lowercase();
-                if e_lower.contains("no such file") || e_lower.contains("code 2") || e_lower.contains("does not exist") {
-                    // Double check via shell
+                if is_missing_file_error(&e) {
                     if let Ok(true) = check_remote_file_exists_via_shell(pool, ssh_pool, machine_id, path).await {
-                         return Err(format!("File '{}' exists but cannot be read via SFTP. Check permissions. (Original error: {})", path, e));
+                        return Err(format!("File '{}' exists but cannot be read via SFTP. Check permissions. (Original error: {})", path, e));
                     }
                     continue;
                 }
@@ -145,60 +220,39 @@ async fn find_claude_config(pool: &Pool<Sqlite>, ssh_pool: &SshPool, machine_id:
             }
         }
     }
-
-    // Fallback default
-    Ok(("~/.claude.json".to_string(), None))
+    Ok(None)
 }
 
-async fn find_opencode_config(pool: &Pool<Sqlite>, ssh_pool: &SshPool, machine_id: i64) -> Result<(String, Option<String>), String> {
-    let candidates = vec![
-        "~/.config/opencode/opencode.json", // Linux/Mac default
-        "~/AppData/Roaming/opencode/opencode.json", // Windows guess
-        "~/.opencode.json",
-        "~/opencode.json",
-    ];
+/// Read a machine's config for a source as its typed model
+async fn read_remote_source_config<T: serde::de::DeserializeOwned + Default>(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    machine_id: i64,
+    source: &dyn ConfigSource,
+) -> Result<RemoteConfigResponse<T>, String> {
+    let (path, content_opt) = find_source_config(pool, ssh_pool, machine_id, source).await?;
+    let app_installed = check_source_installed(pool, ssh_pool, machine_id, source).await;
 
-    for path in &candidates {
-        match read_remote_file(pool, ssh_pool, machine_id, path).await {
-            Ok(content) => {
-                 if serde_json::from_str::<serde_json::Value>(&content).is_ok() {
-                    return Ok((path.to_string(), Some(content)));
-                 }
-            },
-            Err(e) => {
-                let e_lower = e.to_lowercase();
-                if e_lower.contains("no such file") || e_lower.contains("code 2") || e_lower.contains("does not exist") {
-                    if let Ok(true) = check_remote_file_exists_via_shell(pool, ssh_pool, machine_id, path).await {
-                        return Err(format!("File '{}' exists but cannot be read via SFTP. Check permissions. (Original error: {})", path, e));
-                    }
-                    continue;
-                }
-            }
-        }
+    if let Some(content) = content_opt {
+        println!("[Remote] Successfully read {} config from {}", source.display_name(), path);
+        let config: T = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse JSON in {}: {}", path, e))?;
+        Ok(RemoteConfigResponse { config, path, exists: true, app_installed })
+    } else {
+        println!("[Remote] {} config not found, returning default", source.display_name());
+        Ok(RemoteConfigResponse { config: T::default(), path, exists: false, app_installed })
     }
-    
-    // Default fallback if none found
-    Ok(("~/.config/opencode/opencode.json".to_string(), None))
 }
 
+// The Claude/OpenCode commands below predate `read_source_config` and friends and are kept for the current UI
+
 #[tauri::command]
 pub async fn read_remote_claude_config(
     pool: State<'_, Pool<Sqlite>>,
     ssh_pool: State<'_, SshPool>,
     machine_id: i64,
 ) -> Result<RemoteConfigResponse<ClaudeConfig>, String> {
-    let (path, content_opt) = find_claude_config(&pool, &ssh_pool, machine_id).await?;
-    let app_installed = check_claude_installed_v2(&pool, &ssh_pool, machine_id).await;
-
-    if let Some(content) = content_opt {
-        println!("[Remote] Successfully read Claude config from {}", path);
-        let config: ClaudeConfig = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse JSON in {}: {}", path, e))?;
-        Ok(RemoteConfigResponse { config, path, exists: true, app_installed })
-    } else {
-        println!("[Remote] Claude config not found, returning default");
-        Ok(RemoteConfigResponse { config: ClaudeConfig::default(), path, exists: false, app_installed })
-    }
+    read_remote_source_config(&pool, &ssh_pool, machine_id, SourceKind::Claude.source()).await
 }
 
 #[tauri::command]
@@ -207,16 +261,7 @@ pub async fn read_remote_opencode_config(
     ssh_pool: State<'_, SshPool>,
     machine_id: i64,
 ) -> Result<RemoteConfigResponse<OpenCodeConfig>, String> {
-    let (path, content_opt) = find_opencode_config(&pool, &ssh_pool, machine_id).await?;
-    let app_installed = check_opencode_installed(&pool, &ssh_pool, machine_id).await;
-    
-    if let Some(content) = content_opt {
-        let config: OpenCodeConfig = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-        Ok(RemoteConfigResponse { config, path, exists: true, app_installed })
-    } else {
-        Ok(RemoteConfigResponse { config: OpenCodeConfig::default(), path, exists: false, app_installed })
-    }
+    read_remote_source_config(&pool, &ssh_pool, machine_id, SourceKind::OpenCode.source()).await
 }
 
 #[tauri::command]
@@ -225,51 +270,11 @@ pub async fn update_remote_claude_server(
     ssh_pool: State<'_, SshPool>,
     machine_id: i64,
     server_name: String,
-    mut server_config: ClaudeMCPServer,
+    server_config: ClaudeMCPServer,
     path: Option<String>,
 ) -> Result<(), String> {
-    let path_str = path.as_deref().unwrap_or("~/.claude.json");
-    let content = match read_remote_file(&pool, &ssh_pool, machine_id, path_str).await {
-        Ok(c) => c,
-        Err(_) => "{}".to_string(), // Handle missing file by starting fresh
-    };
-    
-    // Backup using SFTP
-    if content != "{}" {
-        let _ = backup_remote_file(&pool, &ssh_pool, machine_id, path_str).await;
-    }
-
-    // Get platform for normalization
-    let (_, _, _, _, platform) = get_connection_info(&pool, machine_id).await?;
-
-    // Normalize command
-    if let Some(ref cmd) = server_config.command {
-        let mut full_cmd = vec![cmd.clone()];
-        if let Some(args) = &server_config.args {
-            full_cmd.extend(args.clone());
-        }
-        
-        let normalized = platform_adapter::normalize_command_for_platform(full_cmd, &platform);
-        
-        if !normalized.is_empty() {
-            server_config.command = Some(normalized[0].clone());
-            if normalized.len() > 1 {
-                server_config.args = Some(normalized[1..].to_vec());
-            } else {
-                server_config.args = Some(vec![]);
-            }
-        }
-    }
-
-    let mut config: ClaudeConfig = serde_json::from_str(&content).unwrap_or_default();
-
-    config.mcp_servers.insert(server_name, server_config);
-
-    let output = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
-
-    write_remote_file(&pool, &ssh_pool, machine_id, path_str, &output).await?;
-    Ok(())
+    let server = to_json(&server_config)?;
+    sources::put_source_server(&pool, &ssh_pool, SourceKind::Claude.source(), Some(machine_id), path, server_name, server).await
 }
 
 #[tauri::command]
@@ -280,25 +285,8 @@ pub async fn disable_remote_claude_server(
     server_name: String,
     path: Option<String>,
 ) -> Result<(), String> {
-    let path_str = path.as_deref().unwrap_or("~/.claude.json");
-    let content = read_remote_file(&pool, &ssh_pool, machine_id, path_str).await?;
-    
-    let _ = backup_remote_file(&pool, &ssh_pool, machine_id, path_str).await;
-
-    let mut config: ClaudeConfig = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-
-    if let Some(mut server) = config.mcp_servers.remove(&server_name) {
-        server.is_active = Some(false);
-        let disabled_name = format!("_disabled_{}", server_name);
-        config.mcp_servers.insert(disabled_name, server);
-    }
-
-    let output = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
-
-    write_remote_file(&pool, &ssh_pool, machine_id, path_str, &output).await?;
-    Ok(())
+    let items = vec![BatchToggleItem { name: server_name, enabled: false }];
+    sources::toggle_servers(&pool, &ssh_pool, SourceKind::Claude.source(), Some(machine_id), path, items).await
 }
 
 #[tauri::command]
@@ -309,25 +297,8 @@ pub async fn enable_remote_claude_server(
     server_name: String,
     path: Option<String>,
 ) -> Result<(), String> {
-    let path_str = path.as_deref().unwrap_or("~/.claude.json");
-    let content = read_remote_file(&pool, &ssh_pool, machine_id, path_str).await?;
-    
-    let _ = backup_remote_file(&pool, &ssh_pool, machine_id, path_str).await;
-
-    let mut config: ClaudeConfig = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-
-    let disabled_name = format!("_disabled_{}", server_name);
-    if let Some(mut server) = config.mcp_servers.remove(&disabled_name) {
-        server.is_active = Some(true);
-        config.mcp_servers.insert(server_name, server);
-    }
-
-    let output = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
-
-    write_remote_file(&pool, &ssh_pool, machine_id, path_str, &output).await?;
-    Ok(())
+    let items = vec![BatchToggleItem { name: server_name, enabled: true }];
+    sources::toggle_servers(&pool, &ssh_pool, SourceKind::Claude.source(), Some(machine_id), path, items).await
 }
 
 #[tauri::command]
@@ -338,23 +309,7 @@ pub async fn delete_remote_claude_server(
     server_name: String,
     path: Option<String>,
 ) -> Result<(), String> {
-    let path_str = path.as_deref().unwrap_or("~/.claude.json");
-    let content = read_remote_file(&pool, &ssh_pool, machine_id, path_str).await?;
-    
-    let _ = backup_remote_file(&pool, &ssh_pool, machine_id, path_str).await;
-
-    let mut config: ClaudeConfig = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-
-    config.mcp_servers.remove(&server_name);
-    let disabled_name = format!("_disabled_{}", server_name);
-    config.mcp_servers.remove(&disabled_name);
-
-    let output = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
-
-    write_remote_file(&pool, &ssh_pool, machine_id, path_str, &output).await?;
-    Ok(())
+    sources::remove_source_server(&pool, &ssh_pool, SourceKind::Claude.source(), Some(machine_id), path, server_name).await
 }
 
 #[tauri::command]
@@ -363,39 +318,11 @@ pub async fn update_remote_opencode_server(
     ssh_pool: State<'_, SshPool>,
     machine_id: i64,
     server_name: String,
-    mut server_config: OpenCodeMCPServer,
+    server_config: OpenCodeMCPServer,
     path: Option<String>,
 ) -> Result<(), String> {
-    // If path is not provided, we should probably try to find it again or fail?
-    // But frontend should pass it. If not, fallback to default.
-    let path_str = path.as_deref().unwrap_or("~/.config/opencode/opencode.json");
-    
-    let content = match read_remote_file(&pool, &ssh_pool, machine_id, path_str).await {
-        Ok(c) => c,
-        Err(_) => "{}".to_string(),
-    };
-    
-    if content != "{}" {
-        let _ = backup_remote_file(&pool, &ssh_pool, machine_id, path_str).await;
-    }
-
-    // Get platform for normalization
-    let (_, _, _, _, platform) = get_connection_info(&pool, machine_id).await?;
-
-    // Normalize command
-    if let Some(cmd) = server_config.command {
-        server_config.command = Some(platform_adapter::normalize_command_for_platform(cmd, &platform));
-    }
-
-    let mut config: OpenCodeConfig = serde_json::from_str(&content).unwrap_or_default();
-
-    config.mcp.insert(server_name, server_config);
-
-    let output = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
-
-    write_remote_file(&pool, &ssh_pool, machine_id, path_str, &output).await?;
-    Ok(())
+    let server = to_json(&server_config)?;
+    sources::put_source_server(&pool, &ssh_pool, SourceKind::OpenCode.source(), Some(machine_id), path, server_name, server).await
 }
 
 #[tauri::command]
@@ -406,21 +333,7 @@ pub async fn delete_remote_opencode_server(
     server_name: String,
     path: Option<String>,
 ) -> Result<(), String> {
-    let path_str = path.as_deref().unwrap_or("~/.config/opencode/opencode.json");
-    let content = read_remote_file(&pool, &ssh_pool, machine_id, path_str).await?;
-    
-    let _ = backup_remote_file(&pool, &ssh_pool, machine_id, path_str).await;
-
-    let mut config: OpenCodeConfig = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-
-    config.mcp.remove(&server_name);
-
-    let output = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
-
-    write_remote_file(&pool, &ssh_pool, machine_id, path_str, &output).await?;
-    Ok(())
+    sources::remove_source_server(&pool, &ssh_pool, SourceKind::OpenCode.source(), Some(machine_id), path, server_name).await
 }
 
 pub async fn execute_remote_command_helper(
@@ -429,8 +342,8 @@ pub async fn execute_remote_command_helper(
     machine_id: i64,
     command: String,
 ) -> Result<String, String> {
-    let (host, port, username, private_key, _) = get_connection_info(pool, machine_id).await?;
-    connection::execute_cmd_with_pool(ssh_pool.clone(), &host, port, &username, &private_key, &command).await
+    let (target, _) = get_connection_info(pool, machine_id).await?;
+    connection::execute_cmd_with_pool(ssh_pool.clone(), &target, &command).await
 }
 
 #[tauri::command]
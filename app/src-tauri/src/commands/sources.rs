@@ -0,0 +1,257 @@
+use crate::commands::config::{self, BatchToggleItem};
+use crate::commands::remote;
+use crate::config::platform_adapter;
+use crate::config::schema::{self, SchemaViolation};
+use crate::config::{ConfigSource, SourceDocument, SourceKind};
+use crate::db::machines;
+use crate::paths::get_app_paths;
+use crate::ssh::pool::SshPool;
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use tauri::State;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceInfo {
+    pub id: SourceKind,
+    pub name: &'static str,
+    pub supports_enabled: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceServerEntry {
+    pub name: String,
+    pub enabled: bool,
+    /// The server in the source's own format
+    pub config: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceConfigResponse {
+    pub source: SourceKind,
+    pub path: String,
+    pub exists: bool,
+    /// Only checked on machines
+    pub app_installed: Option<bool>,
+    pub servers: Vec<SourceServerEntry>,
+}
+
+/// Explicit path, else the source's detected config (locally or on the machine)
+pub(crate) async fn resolve_source_path(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    source: &dyn ConfigSource,
+    machine_id: Option<i64>,
+    path: Option<String>,
+) -> Result<String, String> {
+    match (path, machine_id) {
+        (Some(path), _) => Ok(path),
+        (None, Some(id)) => Ok(remote::find_source_config(pool, ssh_pool, id, source).await?.0),
+        (None, None) => get_app_paths()
+            .map(|p| source.local_path(&p).to_string_lossy().to_string())
+            .ok_or_else(|| "Could not determine home directory".to_string()),
+    }
+}
+
+/// Read-modify-write a source's config, creating it if needed
+pub(crate) async fn modify_source_config(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    source: &dyn ConfigSource,
+    machine_id: Option<i64>,
+    path: Option<String>,
+    reason: &str,
+    modify: impl FnOnce(&mut dyn SourceDocument) -> Result<(), String>,
+) -> Result<(), String> {
+    let path = resolve_source_path(pool, ssh_pool, source, machine_id, path).await?;
+    config::update_config_file(pool, ssh_pool, machine_id, &path, reason, |content| {
+        let mut doc = source.parse(&path, content)?;
+        modify(doc.as_mut())?;
+        let output = doc.serialize()?;
+        schema::check_write(source, &path, content, &output)?;
+        Ok(output)
+    })
+    .await
+}
+
+/// The source's config (detected when `path` is omitted) and its content, `None` if it doesn't exist
+pub(crate) async fn read_source_file(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    source: &dyn ConfigSource,
+    machine_id: Option<i64>,
+    path: Option<String>,
+) -> Result<(String, Option<String>), String> {
+    match (path, machine_id) {
+        (None, Some(id)) => remote::find_source_config(pool, ssh_pool, id, source).await,
+        (path, _) => {
+            let path = resolve_source_path(pool, ssh_pool, source, machine_id, path).await?;
+            let content = config::read_config_file(pool, ssh_pool, machine_id, &path).await?;
+            Ok((path, content))
+        }
+    }
+}
+
+/// `server` with an npx/uvx/npm launcher wrapped in `cmd /c` for Windows, or a `cmd /c` wrapper dropped for
+/// other platforms. Other commands are written as given.
+fn normalize_for_platform(source: &dyn ConfigSource, server: &serde_json::Value, platform: &str) -> Result<serde_json::Value, String> {
+    let mut canonical = source.parse_server(server)?;
+    match canonical.command_line() {
+        Some(command_line) => {
+            canonical.set_command_line(platform_adapter::normalize_command_for_platform(command_line, platform));
+            source.format_server(&canonical)
+        }
+        None => Ok(server.clone()),
+    }
+}
+
+/// Add or replace a server. On a machine the command is normalized for its platform first.
+pub(crate) async fn put_source_server(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    source: &dyn ConfigSource,
+    machine_id: Option<i64>,
+    path: Option<String>,
+    server_name: String,
+    server: serde_json::Value,
+) -> Result<(), String> {
+    let server = match machine_id {
+        Some(id) => normalize_for_platform(source, &server, &machines::get_machine(pool, id).await?.platform)?,
+        None => server,
+    };
+    modify_source_config(pool, ssh_pool, source, machine_id, path, "update_server", |doc| {
+        doc.put_server(&server_name, server)
+    })
+    .await
+}
+
+pub(crate) async fn remove_source_server(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    source: &dyn ConfigSource,
+    machine_id: Option<i64>,
+    path: Option<String>,
+    server_name: String,
+) -> Result<(), String> {
+    modify_source_config(pool, ssh_pool, source, machine_id, path, "delete_server", |doc| {
+        if !doc.remove_server(&server_name) {
+            return Err(format!("Server '{}' not found in {} config", server_name, source.display_name()));
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Enable/disable servers in a single write, through whatever enabled setting the source has
+pub(crate) async fn toggle_servers(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    source: &dyn ConfigSource,
+    machine_id: Option<i64>,
+    path: Option<String>,
+    items: Vec<BatchToggleItem>,
+) -> Result<(), String> {
+    if items.is_empty() {
+        return Ok(());
+    }
+    if !source.supports_enabled() {
+        return Err(format!("{} has no per-server enabled setting; remove the server instead", source.display_name()));
+    }
+    modify_source_config(pool, ssh_pool, source, machine_id, path, "toggle_servers", |doc| {
+        for item in items {
+            doc.set_enabled(&item.name, item.enabled)?;
+        }
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn list_sources() -> Result<Vec<SourceInfo>, String> {
+    Ok(SourceKind::ALL
+        .iter()
+        .map(|kind| kind.source())
+        .map(|source| SourceInfo { id: source.kind(), name: source.display_name(), supports_enabled: source.supports_enabled() })
+        .collect())
+}
+
+/// Servers of any source, locally (`machine_id: None`) or on a machine. The config is detected when `path` is omitted.
+#[tauri::command]
+pub async fn read_source_config(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    source: SourceKind,
+    machine_id: Option<i64>,
+    path: Option<String>,
+) -> Result<SourceConfigResponse, String> {
+    let config_source = source.source();
+    let (path, content) = read_source_file(&pool, &ssh_pool, config_source, machine_id, path).await?;
+    let app_installed = match machine_id {
+        Some(id) => Some(remote::check_source_installed(&pool, &ssh_pool, id, config_source).await),
+        None => None,
+    };
+
+    let doc = config_source.parse(&path, content.as_deref()).map_err(|e| format!("{} ({})", e, path))?;
+    let mut servers: Vec<SourceServerEntry> = doc
+        .servers()?
+        .into_iter()
+        .map(|(name, config)| SourceServerEntry { enabled: doc.is_enabled(&name), name, config })
+        .collect();
+    servers.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(SourceConfigResponse { source, path, exists: content.is_some(), app_installed, servers })
+}
+
+/// Check every server of a source's config against the client's schema, e.g. to show why one won't load
+#[tauri::command]
+pub async fn validate_source_config(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    source: SourceKind,
+    machine_id: Option<i64>,
+    path: Option<String>,
+) -> Result<Vec<SchemaViolation>, String> {
+    let config_source = source.source();
+    let (path, content) = read_source_file(&pool, &ssh_pool, config_source, machine_id, path).await?;
+    let doc = config_source.parse(&path, content.as_deref()).map_err(|e| format!("{} ({})", e, path))?;
+    schema::validate_servers(config_source, doc.as_ref(), None)
+}
+
+#[tauri::command]
+pub async fn update_source_server(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    source: SourceKind,
+    machine_id: Option<i64>,
+    path: Option<String>,
+    server_name: String,
+    server_config: serde_json::Value,
+) -> Result<(), String> {
+    put_source_server(&pool, &ssh_pool, source.source(), machine_id, path, server_name, server_config).await
+}
+
+#[tauri::command]
+pub async fn delete_source_server(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    source: SourceKind,
+    machine_id: Option<i64>,
+    path: Option<String>,
+    server_name: String,
+) -> Result<(), String> {
+    remove_source_server(&pool, &ssh_pool, source.source(), machine_id, path, server_name).await
+}
+
+#[tauri::command]
+pub async fn toggle_source_servers(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    source: SourceKind,
+    machine_id: Option<i64>,
+    path: Option<String>,
+    items: Vec<BatchToggleItem>,
+) -> Result<(), String> {
+    toggle_servers(&pool, &ssh_pool, source.source(), machine_id, path, items).await
+}
@@ -1,13 +1,115 @@
-use crate::ssh::{connection, pool::SshPool};
+use crate::commands::remote::{get_connection_info, jump_target};
+use crate::db::known_hosts::{self, KnownHost};
+use crate::ssh::{connection, host_keys, pool::SshPool};
+use crate::ssh::connection::{SshAuth, SshTarget};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
 use tauri::State;
 
+/// Connection details to try before saving a machine
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionTest {
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    pub username: String,
+    pub private_key: Option<String>, // None to authenticate through ssh-agent
+    pub passphrase: Option<String>,
+    pub jump_host_id: Option<i64>, // Machine to tunnel through, for hosts only reachable via a bastion
+}
+
+fn default_port() -> u16 {
+    22
+}
+
 #[tauri::command]
 pub async fn test_ssh_connection(
+    db: State<'_, Pool<Sqlite>>,
     pool: State<'_, SshPool>,
-    host: String,
-    port: u16,
-    username: String,
-    private_key: String
+    target: ConnectionTest,
 ) -> Result<String, String> {
-    connection::execute_cmd_with_pool(pool.inner().clone(), &host, port, &username, &private_key, "echo 'SSH connection successful'").await
+    let ConnectionTest { host, port, username, private_key, passphrase, jump_host_id } = target;
+    let jump = match jump_host_id {
+        Some(id) => Some(Box::new(get_connection_info(&db, id).await?.0)),
+        None => None,
+    };
+    let host_key = host_keys::trusted_host_key(&db, &host, port, jump.as_deref()).await?;
+    let auth = match private_key {
+        Some(key) => SshAuth::PrivateKey { key, passphrase },
+        None => SshAuth::Agent,
+    };
+    let target = SshTarget { host, port, username, auth, host_key, jump };
+    connection::execute_cmd_with_pool(pool.inner().clone(), &target, "echo 'SSH connection successful'").await
+}
+
+/// Trusted vs currently presented host key for a machine
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostKeyReview {
+    pub host: String,
+    pub port: u16,
+    pub trusted: Option<KnownHost>,
+    pub presented: host_keys::HostKeyInfo,
+    pub matches: bool,
+}
+
+/// Address of a machine plus the jump chain needed to reach it
+async fn machine_address(pool: &Pool<Sqlite>, machine_id: i64) -> Result<(String, u16, Option<Box<SshTarget>>), String> {
+    let machine = crate::db::machines::get_machine(pool, machine_id).await?;
+    let jump = jump_target(pool, &machine).await?;
+    let port = machine.port.try_into().unwrap_or(22);
+    Ok((machine.host, port, jump))
+}
+
+async fn fetch_presented_key(host: &str, port: u16, jump: Option<Box<SshTarget>>) -> Result<host_keys::HostKeyInfo, String> {
+    let (h, p) = (host.to_string(), port);
+    tokio::task::spawn_blocking(move || host_keys::fetch_host_key(&h, p, jump.as_deref()))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+pub async fn review_host_key(
+    pool: State<'_, Pool<Sqlite>>,
+    machine_id: i64,
+) -> Result<HostKeyReview, String> {
+    let (host, port, jump) = machine_address(&pool, machine_id).await?;
+    let trusted = known_hosts::get_known_host(&pool, &host, port, &host_keys::jump_route(jump.as_deref())).await?;
+    let presented = fetch_presented_key(&host, port, jump).await?;
+    let matches = trusted.as_ref().map(|k| k.fingerprint == presented.fingerprint).unwrap_or(false);
+
+    Ok(HostKeyReview { host, port, trusted, presented, matches })
+}
+
+/// Re-accept a changed host key. The fingerprint the user reviewed must still be the one the server presents.
+#[tauri::command]
+pub async fn accept_host_key(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    machine_id: i64,
+    fingerprint: String,
+) -> Result<(), String> {
+    let (host, port, jump) = machine_address(&pool, machine_id).await?;
+    let via = host_keys::jump_route(jump.as_deref());
+    let presented = fetch_presented_key(&host, port, jump).await?;
+
+    if presented.fingerprint != fingerprint {
+        return Err(format!(
+            "Host key for {}:{} changed again during review (now {}). Review it again before accepting.",
+            host, port, presented.fingerprint
+        ));
+    }
+
+    known_hosts::replace_known_host(&pool, &host, port, &via, &presented.key_type, &presented.fingerprint).await?;
+    ssh_pool.remove_host(&host, port);
+
+    println!("[SSH] Accepted new host key for {}:{} ({})", host, port, fingerprint);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_known_hosts(pool: State<'_, Pool<Sqlite>>) -> Result<Vec<KnownHost>, String> {
+    known_hosts::list_known_hosts(&pool).await
 }
+
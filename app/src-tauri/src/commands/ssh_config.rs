@@ -0,0 +1,236 @@
+use crate::db::{machines, ssh_keys};
+use crate::ssh::ssh_config::{self, SshConfigHost};
+use crate::ssh::{connection, key_manager, passphrase};
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// A `Host` entry as shown in the import dialog
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshConfigHostPreview {
+    #[serde(flatten)]
+    pub host: SshConfigHost,
+    pub username: Option<String>,         // User, or the local user like ssh falls back to
+    pub identity_file: Option<String>,    // First IdentityFile that exists on disk
+    pub existing_machine_id: Option<i64>, // Same host/port/user is already a machine
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum SshConfigImportOutcome {
+    #[serde(rename_all = "camelCase")]
+    Imported { machine_id: i64, ssh_key_id: Option<i64> },
+    Skipped { reason: String },
+    Failed { error: String },
+}
+
+#[derive(Debug, Serialize)]
+pub struct SshConfigImportResult {
+    pub alias: String,
+    #[serde(flatten)]
+    pub outcome: SshConfigImportOutcome,
+}
+
+fn resolve_config_path(path: Option<String>) -> Result<PathBuf, String> {
+    match path {
+        Some(p) => Ok(PathBuf::from(p)),
+        None => ssh_config::default_config_path().ok_or_else(|| "Could not determine home directory".to_string()),
+    }
+}
+
+fn local_username() -> Option<String> {
+    std::env::var("USER").or_else(|_| std::env::var("USERNAME")).ok()
+}
+
+fn first_existing_identity(host: &SshConfigHost) -> Option<String> {
+    host.identity_files.iter().find(|f| Path::new(f).is_file()).cloned()
+}
+
+/// Key name shown in the SSH keys list, e.g. "~/.ssh/id_ed25519"
+fn identity_key_name(identity_file: &str) -> String {
+    let home = dirs::home_dir().map(|h| h.to_string_lossy().to_string());
+    match home.as_deref().and_then(|h| identity_file.strip_prefix(h)) {
+        Some(rest) => format!("~{}", rest.replace('\\', "/")),
+        None => identity_file.to_string(),
+    }
+}
+
+/// Host part of the last ProxyJump hop ("user@bastion:2222,inner" -> "inner"); that hop is our direct jump host,
+/// and any earlier hops are expected to be its own ProxyJump.
+fn last_jump_hop(proxy_jump: &str) -> &str {
+    let hop = proxy_jump.rsplit(',').next().unwrap_or(proxy_jump).trim();
+    let hop = hop.rsplit_once('@').map(|(_, h)| h).unwrap_or(hop);
+    hop.split(':').next().unwrap_or(hop)
+}
+
+/// Put each selected alias after the selected alias it jumps through, so bastions exist before their hosts
+fn order_by_jump(aliases: Vec<String>, hosts: &HashMap<String, SshConfigHost>) -> Vec<String> {
+    fn visit(alias: &str, selected: &[String], hosts: &HashMap<String, SshConfigHost>, out: &mut Vec<String>, depth: usize) {
+        if out.iter().any(|a| a == alias) || depth > machines::MAX_JUMP_DEPTH {
+            return;
+        }
+        if let Some(jump) = hosts.get(alias).and_then(|h| h.proxy_jump.as_deref()).map(last_jump_hop) {
+            if selected.iter().any(|a| a == jump) {
+                visit(jump, selected, hosts, out, depth + 1);
+            }
+        }
+        if !out.iter().any(|a| a == alias) {
+            out.push(alias.to_string());
+        }
+    }
+
+    let mut ordered = Vec::new();
+    for alias in &aliases {
+        visit(alias, &aliases, hosts, &mut ordered, 0);
+    }
+    ordered
+}
+
+fn find_existing_machine(existing: &[machines::Machine], host: &SshConfigHost, username: &str) -> Option<i64> {
+    let port = host.port.unwrap_or(22) as i64;
+    existing
+        .iter()
+        .find(|m| m.host == host.address() && m.port == port && m.username == username)
+        .map(|m| m.id)
+}
+
+/// Import an IdentityFile through the normal encrypted storage, reusing it if already imported
+async fn import_identity(pool: &Pool<Sqlite>, identity_file: &str) -> Result<i64, String> {
+    let name = identity_key_name(identity_file);
+    if let Some(id) = ssh_keys::find_ssh_key_id_by_name(pool, &name).await? {
+        return Ok(id);
+    }
+
+    let private_key = std::fs::read_to_string(identity_file)
+        .map_err(|e| format!("Failed to read IdentityFile '{}': {}", identity_file, e))?;
+    connection::validate_key_format(&private_key)?;
+
+    let master_key = key_manager::get_or_create_master_key()?;
+    let is_encrypted = passphrase::is_key_encrypted(&private_key);
+    // Encrypted keys are unlocked on first connection, like keys added by hand without "remember"
+    ssh_keys::add_ssh_key(pool, &name, &private_key, is_encrypted, None, &master_key).await
+}
+
+#[tauri::command]
+pub async fn list_ssh_config_hosts(
+    pool: State<'_, Pool<Sqlite>>,
+    path: Option<String>,
+) -> Result<Vec<SshConfigHostPreview>, String> {
+    let config_path = resolve_config_path(path)?;
+    let hosts = ssh_config::load_ssh_config(&config_path)?;
+    let existing = machines::list_machines(&pool).await?;
+
+    Ok(hosts
+        .into_iter()
+        .map(|host| {
+            let username = host.user.clone().or_else(local_username);
+            let existing_machine_id = username
+                .as_deref()
+                .and_then(|u| find_existing_machine(&existing, &host, u));
+            SshConfigHostPreview {
+                identity_file: first_existing_identity(&host),
+                username,
+                existing_machine_id,
+                host,
+            }
+        })
+        .collect())
+}
+
+/// Create machines for the selected `Host` aliases. IdentityFiles become SSH keys; hosts without one use ssh-agent.
+/// A ProxyJump becomes the machine's jump host when that host is (or is being) imported too.
+#[tauri::command]
+pub async fn import_ssh_config_hosts(
+    pool: State<'_, Pool<Sqlite>>,
+    aliases: Vec<String>,
+    path: Option<String>,
+    platform: Option<String>,
+) -> Result<Vec<SshConfigImportResult>, String> {
+    let config_path = resolve_config_path(path)?;
+    let hosts: HashMap<String, SshConfigHost> = ssh_config::load_ssh_config(&config_path)?
+        .into_iter()
+        .map(|h| (h.alias.clone(), h))
+        .collect();
+    let platform_val = platform.unwrap_or_else(|| "linux".to_string());
+    let mut existing = machines::list_machines(&pool).await?;
+
+    let mut results = Vec::new();
+    for alias in order_by_jump(aliases, &hosts) {
+        let outcome = match hosts.get(&alias) {
+            Some(host) => import_host(&pool, host, &existing, &platform_val).await,
+            None => SshConfigImportOutcome::Failed { error: format!("Host '{}' not found in {}", alias, config_path.display()) },
+        };
+
+        if let SshConfigImportOutcome::Imported { machine_id, .. } = &outcome {
+            println!("[SSH Config] Imported '{}' as machine {}", alias, machine_id);
+            // Later aliases pointing at the same host/user are reported as duplicates
+            existing.push(machines::get_machine(&pool, *machine_id).await?);
+        }
+        results.push(SshConfigImportResult { alias, outcome });
+    }
+
+    Ok(results)
+}
+
+async fn import_host(
+    pool: &Pool<Sqlite>,
+    host: &SshConfigHost,
+    existing: &[machines::Machine],
+    platform: &str,
+) -> SshConfigImportOutcome {
+    let username = match host.user.clone().or_else(local_username) {
+        Some(u) => u,
+        None => return SshConfigImportOutcome::Failed { error: "No User set and local username is unknown".to_string() },
+    };
+
+    if let Some(id) = find_existing_machine(existing, host, &username) {
+        return SshConfigImportOutcome::Skipped { reason: format!("Already added as machine {}", id) };
+    }
+
+    let (ssh_key_id, auth_method) = if host.identity_files.is_empty() {
+        (None, "agent")
+    } else {
+        let identity = match first_existing_identity(host) {
+            Some(file) => file,
+            None => {
+                return SshConfigImportOutcome::Failed {
+                    error: format!("IdentityFile not found: {}", host.identity_files.join(", ")),
+                }
+            }
+        };
+        match import_identity(pool, &identity).await {
+            Ok(id) => (Some(id), "key"),
+            Err(e) => return SshConfigImportOutcome::Failed { error: e },
+        }
+    };
+
+    let jump_host_id = match host.proxy_jump.as_deref().map(last_jump_hop) {
+        Some(hop) => match existing.iter().find(|m| m.name == hop || m.host == hop) {
+            Some(jump) => Some(jump.id),
+            None => {
+                return SshConfigImportOutcome::Failed {
+                    error: format!("Jump host '{}' is not a machine yet; import it as well", hop),
+                }
+            }
+        },
+        None => None,
+    };
+
+    let machine = machines::NewMachine {
+        name: host.alias.clone(),
+        host: host.address().to_string(),
+        username,
+        ssh_key_id,
+        port: host.port.unwrap_or(22) as i64,
+        platform: platform.to_string(),
+        auth_method: auth_method.to_string(),
+        jump_host_id,
+    };
+    match machines::add_machine(pool, &machine).await {
+        Ok(machine_id) => SshConfigImportOutcome::Imported { machine_id, ssh_key_id },
+        Err(e) => SshConfigImportOutcome::Failed { error: e },
+    }
+}
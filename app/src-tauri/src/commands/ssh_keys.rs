@@ -1,5 +1,5 @@
 use crate::db::ssh_keys;
-use crate::ssh::key_manager;
+use crate::ssh::{key_manager, passphrase};
 use sqlx::{Pool, Sqlite};
 use tauri::State;
 
@@ -8,15 +8,67 @@ pub async fn add_ssh_key(
     pool: State<'_, Pool<Sqlite>>,
     name: String,
     private_key: String,
+    passphrase: Option<String>,
+    remember_passphrase: Option<bool>,
 ) -> Result<i64, String> {
     // Get or create master key for encryption
     let master_key = key_manager::get_or_create_master_key()?;
     
     // Validate the key format before storing
     crate::ssh::connection::validate_key_format(&private_key)?;
+
+    let is_encrypted = passphrase::is_key_encrypted(&private_key);
+    if let (true, Some(p)) = (is_encrypted, passphrase.as_deref()) {
+        passphrase::verify_passphrase(&private_key, p)?;
+    }
+    let stored_passphrase = if is_encrypted && remember_passphrase.unwrap_or(false) {
+        passphrase.as_deref()
+    } else {
+        None
+    };
     
     // Add the key with encryption
-    ssh_keys::add_ssh_key(&pool, &name, &private_key, &master_key).await
+    let key_id = ssh_keys::add_ssh_key(&pool, &name, &private_key, is_encrypted, stored_passphrase, &master_key).await?;
+
+    // Not remembered: keep it for this session only, so the user isn't asked again right away
+    if let (true, None, Some(p)) = (is_encrypted, stored_passphrase, passphrase.as_deref()) {
+        passphrase::cache_passphrase(key_id, p);
+    }
+
+    Ok(key_id)
+}
+
+/// Provide the passphrase of an encrypted key, either remembered (stored encrypted) or for this session only
+#[tauri::command]
+pub async fn unlock_ssh_key(
+    pool: State<'_, Pool<Sqlite>>,
+    key_id: i64,
+    passphrase: String,
+    remember: bool,
+) -> Result<(), String> {
+    let master_key = key_manager::get_or_create_master_key()?;
+    let private_key = ssh_keys::get_ssh_key(&pool, key_id, &master_key).await?;
+    passphrase::verify_passphrase(&private_key, &passphrase)?;
+
+    if remember {
+        ssh_keys::set_ssh_key_passphrase(&pool, key_id, Some(&passphrase), &master_key).await?;
+        passphrase::forget_passphrase(key_id);
+    } else {
+        passphrase::cache_passphrase(key_id, &passphrase);
+    }
+    Ok(())
+}
+
+/// Drop both the stored and the session passphrase; the next connection will prompt again
+#[tauri::command]
+pub async fn forget_ssh_key_passphrase(
+    pool: State<'_, Pool<Sqlite>>,
+    key_id: i64,
+) -> Result<(), String> {
+    let master_key = key_manager::get_or_create_master_key()?;
+    ssh_keys::set_ssh_key_passphrase(&pool, key_id, None, &master_key).await?;
+    passphrase::forget_passphrase(key_id);
+    Ok(())
 }
 
 #[tauri::command]
@@ -1,13 +1,19 @@
-use crate::config::{ClaudeConfig, OpenCodeConfig};
+use crate::config::{ConfigSource, McpServer, SourceDocument, SourceKind};
 use crate::config::converter::{self, Platform};
+use crate::config::schema;
 use crate::sync::engine::{SyncEngine, SyncItem, SyncStatus};
 use crate::sync::conflict_detector::ConflictDetector;
 use crate::sync::diff_generator;
-use crate::db::sync_history;
-use crate::commands::remote;
+use crate::sync::merge::{self, MergeSide};
+use crate::db::{carried_fields, machines, sync_history};
+use crate::commands::{backups, remote};
+use crate::file_version::FileVersion;
+use crate::local_fs;
 use crate::ssh::pool::SshPool;
-use std::fs;
-use std::collections::HashMap;
+use crate::paths::get_app_paths;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use tauri::{command, State};
 use sqlx::{Pool, Sqlite};
 
@@ -17,6 +23,33 @@ pub struct SyncPlan {
     pub items: Vec<SyncItem>,
 }
 
+/// The legacy Claude/OpenCode sides: Claude Code is side A, OpenCode side B
+fn claude_opencode_sources(claude_path: String, opencode_path: String) -> (SourceRef, SourceRef) {
+    (
+        SourceRef { source: SourceKind::Claude, path: Some(claude_path) },
+        SourceRef { source: SourceKind::OpenCode, path: Some(opencode_path) },
+    )
+}
+
+/// Each side's own JSON under the legacy field names, and a conflicting server converted to the other format
+fn fill_claude_opencode_fields(item: &mut SyncItem, platform: Platform) {
+    item.claude_json = item.source_json.clone();
+    item.opencode_json = item.destination_json.clone();
+    if item.status != SyncStatus::Conflict {
+        return;
+    }
+
+    // Left empty for a server the other side can't represent
+    let convert = |json: &Option<String>, from: SourceKind, to: SourceKind| -> Option<String> {
+        let native: serde_json::Value = serde_json::from_str(json.as_deref()?).ok()?;
+        let converted: serde_json::Value = converter::convert(from, &native, to, platform).ok()?;
+        serde_json::to_string_pretty(&converted).ok()
+    };
+    item.claude_as_opencode_json = convert(&item.claude_json, SourceKind::Claude, SourceKind::OpenCode);
+    item.opencode_as_claude_json = convert(&item.opencode_json, SourceKind::OpenCode, SourceKind::Claude);
+}
+
+/// `generate_source_sync_plan` for Claude Code and OpenCode, with the fields the Claude/OpenCode view reads
 #[command]
 pub async fn generate_sync_plan(
     pool: State<'_, Pool<Sqlite>>,
@@ -25,221 +58,1200 @@ pub async fn generate_sync_plan(
     opencode_path: String,
     machine_id: Option<i64>,
 ) -> Result<SyncPlan, String> {
-    let claude_content = if let Some(id) = machine_id {
-        remote::read_remote_file(&pool, &ssh_pool, id, &claude_path).await?
-    } else {
-        fs::read_to_string(&claude_path)
-            .map_err(|e| format!("Failed to read Claude config: {}", e))?
-    };
+    let (claude, opencode) = claude_opencode_sources(claude_path, opencode_path);
+    let mut plan = source_sync_plan(&pool, &ssh_pool, machine_id, claude, opencode).await?;
 
-    let opencode_content = if let Some(id) = machine_id {
-        remote::read_remote_file(&pool, &ssh_pool, id, &opencode_path).await?
-    } else {
-        fs::read_to_string(&opencode_path)
-            .map_err(|e| format!("Failed to read OpenCode config: {}", e))?
-    };
+    let platform = resolve_platform(&pool, machine_id).await?;
+    for item in plan.items.iter_mut() {
+        fill_claude_opencode_fields(item, platform);
+    }
+    Ok(plan)
+}
 
-    let claude_config: ClaudeConfig = serde_json::from_str(&claude_content)
-        .map_err(|e| format!("Failed to parse Claude config: {}", e))?;
-    let opencode_config: OpenCodeConfig = serde_json::from_str(&opencode_content)
-        .map_err(|e| format!("Failed to parse OpenCode config: {}", e))?;
+/// Copy the named servers from OpenCode into Claude Code
+#[command]
+pub async fn apply_sync_opencode_to_claude(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    claude_path: String,
+    opencode_path: String,
+    server_names: Vec<String>,
+    machine_id: Option<i64>,
+) -> Result<(), String> {
+    let (claude, opencode) = claude_opencode_sources(claude_path, opencode_path);
+    let actions = server_names.into_iter().map(|name| SyncAction { name, action: SyncActionKind::CopyBToA }).collect();
+    source_actions(&pool, &ssh_pool, machine_id, claude, opencode, actions).await?;
+    Ok(())
+}
 
-    let claude_map: HashMap<String, _> = claude_config.mcp_servers
-        .into_iter()
-        .filter(|(k, _)| !k.starts_with("_disabled_"))
-        .collect();
+/// Copy the named servers from Claude Code into OpenCode
+#[command]
+pub async fn apply_sync_claude_to_opencode(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    claude_path: String,
+    opencode_path: String,
+    server_names: Vec<String>,
+    machine_id: Option<i64>,
+) -> Result<(), String> {
+    let (claude, opencode) = claude_opencode_sources(claude_path, opencode_path);
+    let actions = server_names.into_iter().map(|name| SyncAction { name, action: SyncActionKind::CopyAToB }).collect();
+    source_actions(&pool, &ssh_pool, machine_id, claude, opencode, actions).await?;
+    Ok(())
+}
 
-    let opencode_map = opencode_config.mcp.clone();
+/// One side of a cross-machine sync: the local host (`machine_id: None`) or a managed machine.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncEndpoint {
+    pub machine_id: Option<i64>,
+    /// Config path on that side; the source's default location is detected when omitted
+    pub path: Option<String>,
+}
+
+impl SyncEndpoint {
+    fn key(&self) -> String {
+        self.machine_id.map(|id| format!("machine_{}", id)).unwrap_or_else(|| "local".to_string())
+    }
+}
 
-    let platform = if let Some(id) = machine_id {
-        let (_, _, _, _, p) = remote::get_connection_info(&pool, id).await?;
-        p.parse().unwrap_or(Platform::Linux)
+/// History key for a pair of endpoints. Sorted so A->B and B->A share the same rows.
+fn cross_machine_target_id(source: SourceKind, a: &SyncEndpoint, b: &SyncEndpoint) -> String {
+    let mut keys = [a.key(), b.key()];
+    keys.sort();
+    format!("{}:{}:{}", source.as_str(), keys[0], keys[1])
+}
+
+fn ensure_distinct_endpoints(a: &SyncEndpoint, b: &SyncEndpoint) -> Result<(), String> {
+    if a.key() == b.key() {
+        return Err("Cross-machine sync needs two different machines".to_string());
+    }
+    Ok(())
+}
+
+/// Fail naming every requested server the source config doesn't have, so nothing is copied on a partial match
+fn ensure_servers_exist(servers: &HashMap<String, serde_json::Value>, names: &[String], location: &str) -> Result<(), String> {
+    let missing: Vec<&str> = names.iter().filter(|n| !servers.contains_key(*n)).map(String::as_str).collect();
+    if !missing.is_empty() {
+        return Err(format!("Servers not found in {}: {}", location, missing.join(", ")));
+    }
+    Ok(())
+}
+
+pub(crate) async fn resolve_platform(pool: &Pool<Sqlite>, machine_id: Option<i64>) -> Result<Platform, String> {
+    match machine_id {
+        Some(id) => {
+            let machine = machines::get_machine(pool, id).await?;
+            Ok(machine.platform.parse().unwrap_or(Platform::Linux))
+        }
+        None => Ok(Platform::current()),
+    }
+}
+
+/// Read the source's config on one endpoint. Returns the path used, the content (`None` if the file is missing)
+/// and the version to check when writing it back.
+async fn read_endpoint_config(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    source: SourceKind,
+    endpoint: &SyncEndpoint,
+) -> Result<(String, Option<String>, FileVersion), String> {
+    match (endpoint.machine_id, &endpoint.path) {
+        (Some(id), Some(path)) => {
+            let (content, version) = remote::read_remote_file_versioned(pool, ssh_pool, id, path).await?;
+            Ok((path.clone(), content, version))
+        }
+        (Some(id), None) => {
+            let (path, content) = remote::find_source_config(pool, ssh_pool, id, source.source()).await?;
+            // Detection reads don't report an mtime; the content hash alone guards the write
+            let version = match &content {
+                Some(c) => FileVersion::of(c, None),
+                None => FileVersion::missing(),
+            };
+            Ok((path, content, version))
+        }
+        (None, path) => {
+            let path = match path {
+                Some(p) => p.clone(),
+                None => {
+                    let paths = get_app_paths()
+                        .ok_or_else(|| "Could not determine home directory".to_string())?;
+                    source.source().local_path(&paths).to_string_lossy().to_string()
+                }
+            };
+
+            let (content, version) = local_fs::read_versioned(&path)?;
+            Ok((path, content, version))
+        }
+    }
+}
+
+/// Back up (when the file existed) and write the new config on one endpoint
+async fn write_endpoint_config(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    endpoint: &SyncEndpoint,
+    path: &str,
+    original: Option<&str>,
+    expected: &FileVersion,
+    output: &str,
+) -> Result<(), String> {
+    if let Some(id) = endpoint.machine_id {
+        if let Some(content) = original {
+            backups::backup_remote_file(pool, ssh_pool, id, path, content, "sync").await?;
+        }
+        remote::write_remote_file(pool, ssh_pool, id, path, output, Some(expected)).await?;
+        Ok(())
     } else {
-        Platform::current()
-    };
+        if let Some(content) = original {
+            backups::backup_local_file(pool, path, content, "sync").await?;
+        }
+        local_fs::write_atomic(path, output, Some(expected))?;
+        Ok(())
+    }
+}
 
-    let target_id = machine_id.map(|id| format!("machine_{}", id)).unwrap_or_else(|| "local".to_string());
-    let engine = SyncEngine::new(&pool, "cross-source", &target_id);
-    
-    let mut items = engine.plan(
-        &claude_map,
-        &opencode_map,
-        |v| ConflictDetector::fingerprint_claude(v),
-        |v| ConflictDetector::fingerprint_opencode(v),
-        |v| ConflictDetector::canonical_fingerprint_claude(v),
-        |v| ConflictDetector::canonical_fingerprint_opencode(v),
-    ).await?;
+/// `merge::snapshot` as stored in the sync history
+fn snapshot_json(server: &McpServer, enabled: bool) -> String {
+    serde_json::to_string(&merge::snapshot(server, enabled)).unwrap_or_default()
+}
+
+/// A document's servers as `merge::snapshot`s. Servers that can't be converted are left out and never merged.
+fn merge_snapshots(
+    source: &dyn ConfigSource,
+    doc: &dyn SourceDocument,
+    servers: &HashMap<String, serde_json::Value>,
+) -> HashMap<String, McpServer> {
+    servers
+        .iter()
+        .filter_map(|(name, native)| {
+            let server = source.to_canonical(native, Platform::Linux).ok()?;
+            Some((name.clone(), merge::snapshot(&server, doc.is_enabled(name))))
+        })
+        .collect()
+}
 
-    // Enrich items with diff data and JSON
+/// Attach both sides' JSON (and a diff when they disagree) to cross-machine plan items
+fn enrich_cross_machine_items(
+    items: &mut [SyncItem],
+    map_a: &HashMap<String, serde_json::Value>,
+    map_b: &HashMap<String, serde_json::Value>,
+    label_a: &str,
+    label_b: &str,
+) {
     for item in items.iter_mut() {
-        let claude_server = claude_map.get(&item.name);
-        let opencode_server = opencode_map.get(&item.name);
-
-        // Always populate JSON fields for UI details
-        if let Some(cs) = claude_server {
-            item.claude_json = Some(serde_json::to_string_pretty(cs).unwrap_or_default());
-        }
-        if let Some(os) = opencode_server {
-            item.opencode_json = Some(serde_json::to_string_pretty(os).unwrap_or_default());
-        }
-
-        if item.status == SyncStatus::Conflict {
-            if let (Some(cs), Some(os)) = (claude_server, opencode_server) {
-                let claude_json = item.claude_json.clone().unwrap_or_default();
-                let opencode_json = item.opencode_json.clone().unwrap_or_default();
-                
-                // Convert for normalization
-                let claude_as_opencode = converter::claude_to_opencode(cs, platform);
-                let opencode_as_claude = converter::opencode_to_claude(os, platform);
-                
-                let claude_as_opencode_json = serde_json::to_string_pretty(&claude_as_opencode).unwrap_or_default();
-                let opencode_as_claude_json = serde_json::to_string_pretty(&opencode_as_claude).unwrap_or_default();
-
-                let diff = diff_generator::generate_unified_diff(
-                    &claude_json, 
-                    &opencode_json, 
-                    "Claude", 
-                    "OpenCode"
-                );
-                let diff_result = diff_generator::generate_diff_lines(&claude_json, &opencode_json);
-                
-                item.diff = Some(diff);
-                item.diff_lines = Some(diff_result.lines);
-                item.claude_as_opencode_json = Some(claude_as_opencode_json);
-                item.opencode_as_claude_json = Some(opencode_as_claude_json);
-            }
+        item.source_json = map_a
+            .get(&item.name)
+            .map(|s| serde_json::to_string_pretty(s).unwrap_or_default());
+        item.destination_json = map_b
+            .get(&item.name)
+            .map(|s| serde_json::to_string_pretty(s).unwrap_or_default());
+
+        if let (Some(a_json), Some(b_json)) = (&item.source_json, &item.destination_json) {
+            item.diff = Some(diff_generator::generate_unified_diff(a_json, b_json, label_a, label_b));
+            item.diff_lines = Some(diff_generator::generate_diff_lines(a_json, b_json).lines);
         }
     }
+}
+
+#[command]
+pub async fn generate_cross_machine_sync_plan(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    source: SourceKind,
+    endpoint_a: SyncEndpoint,
+    endpoint_b: SyncEndpoint,
+) -> Result<SyncPlan, String> {
+    ensure_distinct_endpoints(&endpoint_a, &endpoint_b)?;
+
+    let (path_a, content_a, _) = read_endpoint_config(&pool, &ssh_pool, source, &endpoint_a).await?;
+    let (path_b, content_b, _) = read_endpoint_config(&pool, &ssh_pool, source, &endpoint_b).await?;
+
+    let target_id = cross_machine_target_id(source, &endpoint_a, &endpoint_b);
+    let engine = SyncEngine::new(&pool, "cross-machine", &target_id);
+    let label_a = endpoint_a.key();
+    let label_b = endpoint_b.key();
+
+    let config_source = source.source();
+    let doc_a = config_source.parse(&path_a, content_a.as_deref())?;
+    let doc_b = config_source.parse(&path_b, content_b.as_deref())?;
+    let map_a = doc_a.servers()?;
+    let map_b = doc_b.servers()?;
+
+    let fingerprint = |server: &serde_json::Value| ConflictDetector::portable_fingerprint(config_source, server);
+    let mut items = engine.plan(&map_a, &map_b, fingerprint, fingerprint, fingerprint, fingerprint).await?;
+    engine.resolve_merges(
+        &mut items,
+        &merge_snapshots(config_source, doc_a.as_ref(), &map_a),
+        &merge_snapshots(config_source, doc_b.as_ref(), &map_b),
+    ).await?;
+    enrich_cross_machine_items(&mut items, &map_a, &map_b, &label_a, &label_b);
 
     Ok(SyncPlan { items })
 }
 
+/// Copy the named servers from one endpoint to another (same source on both),
+/// adapting commands when the two machines run different platforms.
 #[command]
-pub async fn apply_sync_opencode_to_claude(
+pub async fn apply_cross_machine_sync(
     pool: State<'_, Pool<Sqlite>>,
     ssh_pool: State<'_, SshPool>,
-    claude_path: String,
-    opencode_path: String,
+    source: SourceKind,
+    from: SyncEndpoint,
+    to: SyncEndpoint,
     server_names: Vec<String>,
+) -> Result<(), String> {
+    ensure_distinct_endpoints(&from, &to)?;
+
+    let (from_path, from_content, _) = read_endpoint_config(&pool, &ssh_pool, source, &from).await?;
+    let from_content = from_content
+        .ok_or_else(|| format!("No {} config found at {} on {}", source.as_str(), from_path, from.key()))?;
+    let (to_path, to_content, to_version) = read_endpoint_config(&pool, &ssh_pool, source, &to).await?;
+
+    let from_platform = resolve_platform(&pool, from.machine_id).await?;
+    let to_platform = resolve_platform(&pool, to.machine_id).await?;
+    let target_id = cross_machine_target_id(source, &from, &to);
+
+    // The two sides may use different layouts (e.g. VS Code's mcp.json vs settings.json)
+    let config_source = source.source();
+    let from_doc = config_source.parse(&from_path, Some(&from_content))?;
+    let from_servers = from_doc.servers()?;
+    ensure_servers_exist(&from_servers, &server_names, &format!("{} on {}", from_path, from.key()))?;
+    let mut to_doc = config_source.parse(&to_path, to_content.as_deref())?;
+
+    let mut hashes = Vec::new();
+    for name in &server_names {
+        let server = &from_servers[name];
+        let adapted = if from_platform == to_platform {
+            server.clone()
+        } else {
+            config_source.adapt_for_platform(server, to_platform)?
+        };
+        let snapshot = config_source
+            .to_canonical(&adapted, Platform::Linux)
+            .ok()
+            .map(|server| snapshot_json(&server, from_doc.is_enabled(name)));
+        hashes.push((name.clone(), ConflictDetector::portable_fingerprint(config_source, &adapted), snapshot));
+        to_doc.put_server(name, adapted)?;
+        if config_source.supports_enabled() {
+            to_doc.set_enabled(name, from_doc.is_enabled(name))?;
+        }
+    }
+    let output = to_doc.serialize()?;
+    schema::check_write(config_source, &to_path, to_content.as_deref(), &output)?;
+
+    write_endpoint_config(&pool, &ssh_pool, &to, &to_path, to_content.as_deref(), &to_version, &output).await?;
+
+    for (name, hash, snapshot) in hashes {
+        sync_history::upsert_sync_history(&pool, "cross-machine", &target_id, &name, &hash, snapshot.as_deref()).await?;
+    }
+
+    Ok(())
+}
+
+/// One config source on a machine, for syncing two different sources with each other
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceRef {
+    pub source: SourceKind,
+    /// Config path; the source's default location is detected when omitted
+    pub path: Option<String>,
+}
+
+/// A source's servers as native JSON (for display) and in the canonical model, which every source converts
+/// to so any two of them can be planned against each other. Disabled servers are left out.
+struct SourceServers {
+    native: HashMap<String, serde_json::Value>,
+    canonical: HashMap<String, McpServer>,
+}
+
+/// Fields kept for a source's file, by server (see `db::carried_fields`)
+type CarriedFields = HashMap<String, Map<String, Value>>;
+
+async fn load_carried(pool: &Pool<Sqlite>, machine_id: Option<i64>, source: SourceKind, path: &str) -> Result<CarriedFields, String> {
+    carried_fields::get_carried_fields(pool, machine_id, source.as_str(), path).await
+}
+
+/// Keep what was left out of each written server, or forget it when nothing was (`None`)
+async fn save_carried(
+    pool: &Pool<Sqlite>,
     machine_id: Option<i64>,
+    source: SourceKind,
+    path: &str,
+    updates: Vec<(String, Option<Map<String, Value>>)>,
 ) -> Result<(), String> {
-    let claude_content = if let Some(id) = machine_id {
-        remote::read_remote_file(&pool, &ssh_pool, id, &claude_path).await?
-    } else {
-        fs::read_to_string(&claude_path)
-            .map_err(|e| format!("Failed to read Claude config: {}", e))?
-    };
+    for (name, fields) in updates {
+        carried_fields::set_carried_fields(pool, machine_id, source.as_str(), path, &name, fields.as_ref()).await?;
+    }
+    Ok(())
+}
 
-    let opencode_content = if let Some(id) = machine_id {
-        remote::read_remote_file(&pool, &ssh_pool, id, &opencode_path).await?
-    } else {
-        fs::read_to_string(&opencode_path)
-            .map_err(|e| format!("Failed to read OpenCode config: {}", e))?
-    };
+fn parse_source_servers(
+    source: SourceKind,
+    path: &str,
+    content: Option<&str>,
+    carried: &CarriedFields,
+    platform: Platform,
+) -> Result<SourceServers, String> {
+    let source = source.source();
+    let doc = source.parse(path, content)?;
+
+    let mut servers = SourceServers { native: HashMap::new(), canonical: HashMap::new() };
+    for (name, native) in doc.servers()? {
+        if !doc.is_enabled(&name) {
+            continue;
+        }
+        servers.canonical.insert(name.clone(), source.to_canonical_carried(&native, carried.get(&name), platform)?);
+        servers.native.insert(name, native);
+    }
+    Ok(servers)
+}
 
-    let mut claude_config: ClaudeConfig = serde_json::from_str(&claude_content)
-        .map_err(|e| format!("Failed to parse Claude config: {}", e))?;
-    let opencode_config: OpenCodeConfig = serde_json::from_str(&opencode_content)
-        .map_err(|e| format!("Failed to parse OpenCode config: {}", e))?;
+/// Fields of `name` left out of `to`'s file when it is copied from `from` (see `converter::lost_fields`). A server
+/// that can't be converted at all (e.g. a remote server into Claude Desktop) fails on apply instead, so nothing is
+/// reported for it here.
+fn source_lost_fields(from: SourceKind, servers: &SourceServers, to: SourceKind, name: &str, platform: Platform) -> Vec<String> {
+    servers
+        .native
+        .get(name)
+        .and_then(|native| converter::lost_fields(from, native, to, platform).ok())
+        .unwrap_or_default()
+}
 
-    let platform = if let Some(id) = machine_id {
-        let (_, _, _, _, p) = remote::get_connection_info(&pool, id).await?;
-        p.parse().unwrap_or(Platform::Linux)
-    } else {
-        Platform::current()
+/// History key for a pair of sources on one machine. Sorted so either direction shares the same rows.
+fn cross_source_target_id(a: SourceKind, b: SourceKind, machine_id: Option<i64>) -> String {
+    let mut sources = [a.as_str(), b.as_str()];
+    sources.sort();
+    let machine = machine_id.map(|id| format!("machine_{}", id)).unwrap_or_else(|| "local".to_string());
+    format!("{}:{}:{}", sources[0], sources[1], machine)
+}
+
+fn ensure_distinct_sources(a: &SourceRef, b: &SourceRef) -> Result<(), String> {
+    if a.source == b.source {
+        return Err("Pick two different sources (use cross-machine sync for the same source)".to_string());
+    }
+    Ok(())
+}
+
+/// Compare any two sources on one machine (e.g. Claude Desktop and Claude Code).
+/// Servers are compared in the canonical model; `sourceJson`/`destinationJson` hold each side's own format.
+#[command]
+pub async fn generate_source_sync_plan(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    machine_id: Option<i64>,
+    source_a: SourceRef,
+    source_b: SourceRef,
+) -> Result<SyncPlan, String> {
+    source_sync_plan(&pool, &ssh_pool, machine_id, source_a, source_b).await
+}
+
+async fn source_sync_plan(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    machine_id: Option<i64>,
+    source_a: SourceRef,
+    source_b: SourceRef,
+) -> Result<SyncPlan, String> {
+    ensure_distinct_sources(&source_a, &source_b)?;
+
+    let endpoint_a = SyncEndpoint { machine_id, path: source_a.path.clone() };
+    let endpoint_b = SyncEndpoint { machine_id, path: source_b.path.clone() };
+    let (path_a, content_a, _) = read_endpoint_config(pool, ssh_pool, source_a.source, &endpoint_a).await?;
+    let (path_b, content_b, _) = read_endpoint_config(pool, ssh_pool, source_b.source, &endpoint_b).await?;
+
+    let platform = resolve_platform(pool, machine_id).await?;
+    let carried_a = load_carried(pool, machine_id, source_a.source, &path_a).await?;
+    let carried_b = load_carried(pool, machine_id, source_b.source, &path_b).await?;
+    let servers_a = parse_source_servers(source_a.source, &path_a, content_a.as_deref(), &carried_a, platform)?;
+    let servers_b = parse_source_servers(source_b.source, &path_b, content_b.as_deref(), &carried_b, platform)?;
+
+    let target_id = cross_source_target_id(source_a.source, source_b.source, machine_id);
+    let engine = SyncEngine::new(pool, "cross-source", &target_id);
+    let mut items = engine.plan(
+        &servers_a.canonical,
+        &servers_b.canonical,
+        ConflictDetector::portable_fingerprint_canonical,
+        ConflictDetector::portable_fingerprint_canonical,
+        ConflictDetector::portable_fingerprint_canonical,
+        ConflictDetector::portable_fingerprint_canonical,
+    ).await?;
+    // Disabled servers aren't compared, so whatever is left is enabled
+    let snapshots = |servers: &SourceServers| -> HashMap<String, McpServer> {
+        servers.canonical.iter().map(|(name, server)| (name.clone(), merge::snapshot(server, true))).collect()
     };
+    engine.resolve_merges(&mut items, &snapshots(&servers_a), &snapshots(&servers_b)).await?;
+
+    for item in items.iter_mut() {
+        item.source_json = servers_a
+            .native
+            .get(&item.name)
+            .map(|s| serde_json::to_string_pretty(s).unwrap_or_default());
+        item.destination_json = servers_b
+            .native
+            .get(&item.name)
+            .map(|s| serde_json::to_string_pretty(s).unwrap_or_default());
 
-    let target_id = machine_id.map(|id| format!("machine_{}", id)).unwrap_or_else(|| "local".to_string());
+        // Items on side A are described as copied to B, items only on B as copied to A
+        item.lost_fields = if servers_a.native.contains_key(&item.name) {
+            source_lost_fields(source_a.source, &servers_a, source_b.source, &item.name, platform)
+        } else {
+            source_lost_fields(source_b.source, &servers_b, source_a.source, &item.name, platform)
+        };
 
-    for name in server_names {
-        if let Some(server) = opencode_config.mcp.get(&name) {
-            let converted = converter::opencode_to_claude(server, platform.clone());
-            claude_config.mcp_servers.insert(name.clone(), converted.clone());
-            
-            // Update history
-            let hash = ConflictDetector::fingerprint_claude(&converted);
-            sync_history::upsert_sync_history(&pool, "cross-source", &target_id, &name, &hash).await?;
+        // Diff the common format, otherwise every field rename would show up as a change
+        if let (Some(a), Some(b)) = (servers_a.canonical.get(&item.name), servers_b.canonical.get(&item.name)) {
+            let a_json = serde_json::to_string_pretty(a).unwrap_or_default();
+            let b_json = serde_json::to_string_pretty(b).unwrap_or_default();
+            item.diff = Some(diff_generator::generate_unified_diff(
+                &a_json,
+                &b_json,
+                source_a.source.as_str(),
+                source_b.source.as_str(),
+            ));
+            item.diff_lines = Some(diff_generator::generate_diff_lines(&a_json, &b_json).lines);
         }
     }
 
-    let output = serde_json::to_string_pretty(&claude_config)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+    Ok(SyncPlan { items })
+}
 
-    if let Some(id) = machine_id {
-        let _ = remote::backup_remote_file(&pool, &ssh_pool, id, &claude_path).await;
-        remote::write_remote_file(&pool, &ssh_pool, id, &claude_path, &output).await?;
-    } else {
-        let backup_path = format!("{}.bak", claude_path);
-        fs::write(&backup_path, &claude_content)
-            .map_err(|e| format!("Failed to create backup: {}", e))?;
-        fs::write(&claude_path, output)
-            .map_err(|e| format!("Failed to write file: {}", e))?;
+/// Outcome of `apply_source_sync`
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceSyncReport {
+    /// Servers written to the target
+    pub copied: Vec<String>,
+    /// Per server, the fields left out of the target's file because it has no place for them. The app keeps them,
+    /// so copying the server back restores them.
+    pub lost_fields: HashMap<String, Vec<String>>,
+}
+
+/// Copy the named servers from one source to another on the same machine, converting between their formats.
+/// Fields the target can't represent are reported rather than dropped silently.
+#[command]
+pub async fn apply_source_sync(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    machine_id: Option<i64>,
+    from: SourceRef,
+    to: SourceRef,
+    server_names: Vec<String>,
+) -> Result<SourceSyncReport, String> {
+    ensure_distinct_sources(&from, &to)?;
+
+    let from_endpoint = SyncEndpoint { machine_id, path: from.path.clone() };
+    let to_endpoint = SyncEndpoint { machine_id, path: to.path.clone() };
+    let (from_path, from_content, _) = read_endpoint_config(&pool, &ssh_pool, from.source, &from_endpoint).await?;
+    let from_content = from_content
+        .ok_or_else(|| format!("No {} config found at {}", from.source.as_str(), from_path))?;
+    let (to_path, to_content, to_version) = read_endpoint_config(&pool, &ssh_pool, to.source, &to_endpoint).await?;
+
+    let platform = resolve_platform(&pool, machine_id).await?;
+    let from_carried = load_carried(&pool, machine_id, from.source, &from_path).await?;
+    let source = parse_source_servers(from.source, &from_path, Some(&from_content), &from_carried, platform)?;
+    let selected: Vec<(&String, &McpServer)> = server_names
+        .iter()
+        .filter_map(|name| source.canonical.get(name).map(|s| (name, s)))
+        .collect();
+
+    let to_source = to.source.source();
+    let mut to_doc = to_source.parse(&to_path, to_content.as_deref())?;
+    let mut to_carried = Vec::new();
+    for (name, server) in &selected {
+        let (converted, carried) = to_source
+            .to_native_carried(server, platform)
+            .map_err(|e| format!("Cannot copy '{}' to {}: {}", name, to_source.display_name(), e))?;
+        to_doc.put_server(name, converted)?;
+        to_carried.push((name.to_string(), Some(carried)));
+    }
+    let output = to_doc.serialize()?;
+    schema::check_write(to_source, &to_path, to_content.as_deref(), &output)?;
+
+    write_endpoint_config(&pool, &ssh_pool, &to_endpoint, &to_path, to_content.as_deref(), &to_version, &output).await?;
+    save_carried(&pool, machine_id, to.source, &to_path, to_carried).await?;
+
+    let target_id = cross_source_target_id(from.source, to.source, machine_id);
+    let mut report = SourceSyncReport::default();
+    for (name, server) in selected {
+        let hash = ConflictDetector::portable_fingerprint_canonical(server);
+        let snapshot = snapshot_json(server, true);
+        sync_history::upsert_sync_history(&pool, "cross-source", &target_id, name, &hash, Some(&snapshot)).await?;
+
+        let lost = source_lost_fields(from.source, &source, to.source, name, platform);
+        if !lost.is_empty() {
+            println!("[Sync] '{}' copied to {}, left out of its file: {}", name, to.source.as_str(), lost.join(", "));
+            report.lost_fields.insert(name.clone(), lost);
+        }
+        report.copied.push(name.clone());
     }
 
+    Ok(report)
+}
+
+/// A server both sides changed, with the side to take each conflicting field from
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeResolution {
+    pub name: String,
+    #[serde(default)]
+    pub choices: HashMap<String, MergeSide>,
+}
+
+/// A source's config on one endpoint, read to be written back
+struct SyncSide {
+    source: SourceKind,
+    endpoint: SyncEndpoint,
+    path: String,
+    content: Option<String>,
+    version: FileVersion,
+    platform: Platform,
+    doc: Box<dyn SourceDocument>,
+    carried: CarriedFields,
+    /// Servers whose carried fields changed since loading
+    recarried: BTreeSet<String>,
+}
+
+async fn load_sync_side(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    source: SourceKind,
+    endpoint: SyncEndpoint,
+) -> Result<SyncSide, String> {
+    let (path, content, version) = read_endpoint_config(pool, ssh_pool, source, &endpoint).await?;
+    let doc = source.source().parse(&path, content.as_deref())?;
+    let platform = resolve_platform(pool, endpoint.machine_id).await?;
+    let carried = load_carried(pool, endpoint.machine_id, source, &path).await?;
+    Ok(SyncSide { source, endpoint, path, content, version, platform, doc, carried, recarried: BTreeSet::new() })
+}
+
+impl SyncSide {
+    /// `native`, an entry of this side's file, in the canonical model with its carried fields restored
+    fn canonical(&self, name: &str, native: &Value, platform: Platform) -> Result<McpServer, String> {
+        self.source.source().to_canonical_carried(native, self.carried.get(name), platform)
+    }
+
+    /// The server as a `merge::snapshot`
+    fn snapshot(&self, name: &str) -> Result<McpServer, String> {
+        let native = self
+            .doc
+            .servers()?
+            .remove(name)
+            .ok_or_else(|| format!("No server '{}' in {} on {}", name, self.path, self.endpoint.key()))?;
+        Ok(merge::snapshot(&self.canonical(name, &native, Platform::Linux)?, self.doc.is_enabled(name)))
+    }
+
+    fn set_carried(&mut self, name: &str, fields: Map<String, Value>) {
+        if fields.is_empty() {
+            self.carried.remove(name);
+        } else {
+            self.carried.insert(name.to_string(), fields);
+        }
+        self.recarried.insert(name.to_string());
+    }
+
+    /// Convert `server` to this side's format and put it in the file, keeping what the format can't hold
+    fn put_converted(&mut self, name: &str, server: &McpServer) -> Result<(), String> {
+        let config_source = self.source.source();
+        let (native, carried) = config_source
+            .to_native_carried(server, self.platform)
+            .map_err(|e| format!("Cannot write '{}' to {}: {}", name, config_source.display_name(), e))?;
+        self.doc.put_server(name, native)?;
+        self.set_carried(name, carried);
+        Ok(())
+    }
+
+    /// Carried fields to save once the file is written
+    fn carried_updates(&self) -> Vec<(String, Option<Map<String, Value>>)> {
+        self.recarried.iter().map(|name| (name.clone(), self.carried.get(name).cloned())).collect()
+    }
+
+    /// Replace the server with `merged`, enabled state included where the file has one
+    fn put_merged(&mut self, name: &str, merged: &McpServer) -> Result<(), String> {
+        let mut server = merged.clone();
+        let enabled = server.enabled.take().unwrap_or(true);
+        self.put_converted(name, &server)?;
+        if self.source.source().supports_enabled() {
+            self.doc.set_enabled(name, enabled)?;
+        }
+        Ok(())
+    }
+
+    fn output(&self) -> Result<String, String> {
+        let output = self.doc.serialize()?;
+        schema::check_write(self.source.source(), &self.path, self.content.as_deref(), &output)?;
+        Ok(output)
+    }
+}
+
+/// Merge each server in `merges` against its last synced snapshot and write the result to both sides.
+/// The merge is recomputed from the files as they are now, so every conflict left must have a choice.
+async fn apply_merges(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    engine: &SyncEngine<'_>,
+    mut a: SyncSide,
+    mut b: SyncSide,
+    merges: &[MergeResolution],
+) -> Result<(), String> {
+    let mut merged_servers = Vec::new();
+    for resolution in merges {
+        let name = &resolution.name;
+        let base = engine.last_snapshot(name).await?;
+        let merge = merge::merge_servers(base.as_ref(), &a.snapshot(name)?, &b.snapshot(name)?, &resolution.choices)?;
+        let Some(merged) = merge.merged else {
+            let fields: Vec<&str> = merge.conflicts.iter().map(|c| c.field.as_str()).collect();
+            return Err(format!("'{}' still has conflicts in: {}", name, fields.join(", ")));
+        };
+        a.put_merged(name, &merged)?;
+        b.put_merged(name, &merged)?;
+        merged_servers.push((name, merged));
+    }
+
+    // Both files are checked before either is written
+    let output_a = a.output()?;
+    let output_b = b.output()?;
+    let (carried_a, carried_b) = (a.carried_updates(), b.carried_updates());
+    write_endpoint_config(pool, ssh_pool, &a.endpoint, &a.path, a.content.as_deref(), &a.version, &output_a).await?;
+    save_carried(pool, a.endpoint.machine_id, a.source, &a.path, carried_a).await?;
+    write_endpoint_config(pool, ssh_pool, &b.endpoint, &b.path, b.content.as_deref(), &b.version, &output_b).await?;
+    save_carried(pool, b.endpoint.machine_id, b.source, &b.path, carried_b).await?;
+
+    for (name, merged) in merged_servers {
+        let hash = ConflictDetector::fingerprint_canonical(&merged);
+        let snapshot = serde_json::to_string(&merged).unwrap_or_default();
+        engine.record_synced(name, &hash, Some(&snapshot)).await?;
+        println!("[Sync] Merged '{}' into {} and {}", name, a.endpoint.key(), b.endpoint.key());
+    }
     Ok(())
 }
 
+/// Write the merge of servers changed on both endpoints (`Merged` or resolved `Conflict` plan items) to both
 #[command]
-pub async fn apply_sync_claude_to_opencode(
+pub async fn apply_cross_machine_merge(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    source: SourceKind,
+    endpoint_a: SyncEndpoint,
+    endpoint_b: SyncEndpoint,
+    merges: Vec<MergeResolution>,
+) -> Result<(), String> {
+    ensure_distinct_endpoints(&endpoint_a, &endpoint_b)?;
+
+    let target_id = cross_machine_target_id(source, &endpoint_a, &endpoint_b);
+    let engine = SyncEngine::new(&pool, "cross-machine", &target_id);
+    let a = load_sync_side(&pool, &ssh_pool, source, endpoint_a).await?;
+    let b = load_sync_side(&pool, &ssh_pool, source, endpoint_b).await?;
+    apply_merges(&pool, &ssh_pool, &engine, a, b, &merges).await
+}
+
+/// Write the merge of servers changed in both sources on one machine to both
+#[command]
+pub async fn apply_source_merge(
     pool: State<'_, Pool<Sqlite>>,
     ssh_pool: State<'_, SshPool>,
-    claude_path: String,
-    opencode_path: String,
-    server_names: Vec<String>,
     machine_id: Option<i64>,
+    source_a: SourceRef,
+    source_b: SourceRef,
+    merges: Vec<MergeResolution>,
 ) -> Result<(), String> {
-    let claude_content = if let Some(id) = machine_id {
-        remote::read_remote_file(&pool, &ssh_pool, id, &claude_path).await?
-    } else {
-        fs::read_to_string(&claude_path)
-            .map_err(|e| format!("Failed to read Claude config: {}", e))?
-    };
+    ensure_distinct_sources(&source_a, &source_b)?;
 
-    let opencode_content = if let Some(id) = machine_id {
-        remote::read_remote_file(&pool, &ssh_pool, id, &opencode_path).await?
-    } else {
-        fs::read_to_string(&opencode_path)
-            .map_err(|e| format!("Failed to read OpenCode config: {}", e))?
-    };
+    let target_id = cross_source_target_id(source_a.source, source_b.source, machine_id);
+    let engine = SyncEngine::new(&pool, "cross-source", &target_id);
+    let a = load_sync_side(&pool, &ssh_pool, source_a.source, SyncEndpoint { machine_id, path: source_a.path }).await?;
+    let b = load_sync_side(&pool, &ssh_pool, source_b.source, SyncEndpoint { machine_id, path: source_b.path }).await?;
+    apply_merges(&pool, &ssh_pool, &engine, a, b, &merges).await
+}
+
+/// What to do with one server of a sync plan. A and B are the plan's two sides, in the order it was made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncActionKind {
+    CopyAToB,
+    CopyBToA,
+    DeleteInA,
+    DeleteInB,
+    Skip,
+}
 
-    let claude_config: ClaudeConfig = serde_json::from_str(&claude_content)
-        .map_err(|e| format!("Failed to parse Claude config: {}", e))?;
-    let mut opencode_config: OpenCodeConfig = serde_json::from_str(&opencode_content)
-        .map_err(|e| format!("Failed to parse OpenCode config: {}", e))?;
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncAction {
+    pub name: String,
+    pub action: SyncActionKind,
+}
+
+/// Outcome of applying sync actions
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncActionReport {
+    pub copied: Vec<String>,
+    pub deleted: Vec<String>,
+    /// Skipped servers, and deletions of servers that were already gone
+    pub skipped: Vec<String>,
+}
 
-    let platform = if let Some(id) = machine_id {
-        let (_, _, _, _, p) = remote::get_connection_info(&pool, id).await?;
-        p.parse().unwrap_or(Platform::Linux)
+/// Copy a server to the other side: as-is within a source (re-targeted when the platforms differ), through the
+/// canonical model between sources. Its enabled state and carried fields come along.
+fn copy_server(from: &SyncSide, to: &mut SyncSide, name: &str) -> Result<(), String> {
+    let native = from
+        .doc
+        .servers()?
+        .remove(name)
+        .ok_or_else(|| format!("No server '{}' in {} on {}", name, from.path, from.endpoint.key()))?;
+    let to_source = to.source.source();
+    if from.source != to.source {
+        let canonical = from.canonical(name, &native, to.platform)?;
+        to.put_converted(name, &canonical)?;
     } else {
-        Platform::current()
-    };
+        let converted = if from.platform != to.platform {
+            to_source.adapt_for_platform(&native, to.platform)?
+        } else {
+            native
+        };
+        to.doc.put_server(name, converted)?;
+        to.set_carried(name, from.carried.get(name).cloned().unwrap_or_default());
+    }
+    if to_source.supports_enabled() {
+        to.doc.set_enabled(name, from.doc.is_enabled(name))?;
+    }
+    Ok(())
+}
 
-    let target_id = machine_id.map(|id| format!("machine_{}", id)).unwrap_or_else(|| "local".to_string());
+fn portable_fingerprint(source: SourceKind, native: &serde_json::Value) -> String {
+    ConflictDetector::portable_fingerprint(source.source(), native)
+}
 
-    for name in server_names {
-        if let Some(server) = claude_config.mcp_servers.get(&name) {
-            let converted = converter::claude_to_opencode(server, platform.clone());
-            opencode_config.mcp.insert(name.clone(), converted.clone());
+/// Side of a sync plan an action writes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlanSide {
+    A,
+    B,
+}
 
-            // Update history
-            let hash = ConflictDetector::fingerprint_opencode(&converted);
-            sync_history::upsert_sync_history(&pool, "cross-source", &target_id, &name, &hash).await?;
+impl SyncActionKind {
+    fn target(self) -> Option<PlanSide> {
+        match self {
+            SyncActionKind::CopyBToA | SyncActionKind::DeleteInA => Some(PlanSide::A),
+            SyncActionKind::CopyAToB | SyncActionKind::DeleteInB => Some(PlanSide::B),
+            SyncActionKind::Skip => None,
         }
     }
+}
 
-    let output = serde_json::to_string_pretty(&opencode_config)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+/// How an applied action changes the sync history
+enum HistoryUpdate {
+    /// A copied server, as it now is on the side it was written to
+    Synced { name: String, hash: String, snapshot: Option<String> },
+    /// A deleted server, so the next plan doesn't report the deletion again
+    Forget(String),
+}
 
-    if let Some(id) = machine_id {
-        let _ = remote::backup_remote_file(&pool, &ssh_pool, id, &opencode_path).await;
-        remote::write_remote_file(&pool, &ssh_pool, id, &opencode_path, &output).await?;
-    } else {
-        let backup_path = format!("{}.bak", opencode_path);
-        fs::write(&backup_path, &opencode_content)
-            .map_err(|e| format!("Failed to create backup: {}", e))?;
-        fs::write(&opencode_path, output)
-            .map_err(|e| format!("Failed to write file: {}", e))?;
+/// History updates for the actions that wrote to `side`, whose document `target` holds the result
+fn history_updates<H>(
+    target: &SyncSide,
+    side: PlanSide,
+    actions: &[SyncAction],
+    deleted: &[String],
+    fingerprint: &H,
+) -> Result<Vec<HistoryUpdate>, String>
+where
+    H: Fn(SourceKind, &serde_json::Value) -> String,
+{
+    let mut servers = target.doc.servers()?;
+    let mut updates = Vec::new();
+    for SyncAction { name, action } in actions.iter().filter(|a| a.action.target() == Some(side)) {
+        if matches!(action, SyncActionKind::DeleteInA | SyncActionKind::DeleteInB) {
+            if deleted.contains(name) {
+                updates.push(HistoryUpdate::Forget(name.clone()));
+            }
+            continue;
+        }
+        let Some(native) = servers.remove(name) else {
+            continue;
+        };
+        let snapshot = target
+            .canonical(name, &native, Platform::Linux)
+            .ok()
+            .map(|server| snapshot_json(&server, target.doc.is_enabled(name)));
+        updates.push(HistoryUpdate::Synced { name: name.clone(), hash: fingerprint(target.source, &native), snapshot });
     }
+    Ok(updates)
+}
 
+async fn record_history(engine: &SyncEngine<'_>, updates: Vec<HistoryUpdate>) -> Result<(), String> {
+    for update in updates {
+        match update {
+            HistoryUpdate::Synced { name, hash, snapshot } => engine.record_synced(&name, &hash, snapshot.as_deref()).await?,
+            HistoryUpdate::Forget(name) => engine.forget(&name).await?,
+        }
+    }
     Ok(())
 }
+
+/// Run per-server actions on two sides in one pass: each file is changed in memory, both are checked, then
+/// each one that changed is written once. History is recorded per side as it is written, so when B fails to
+/// write, what already landed on A is still remembered.
+async fn apply_actions<H>(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    engine: &SyncEngine<'_>,
+    mut a: SyncSide,
+    mut b: SyncSide,
+    actions: &[SyncAction],
+    fingerprint: H,
+) -> Result<SyncActionReport, String>
+where
+    H: Fn(SourceKind, &serde_json::Value) -> String,
+{
+    let mut names = HashSet::new();
+    if let Some(duplicate) = actions.iter().find(|action| !names.insert(action.name.as_str())) {
+        return Err(format!("'{}' has more than one action", duplicate.name));
+    }
+
+    let mut report = SyncActionReport::default();
+    let (mut a_changed, mut b_changed) = (false, false);
+    for SyncAction { name, action } in actions {
+        match action {
+            SyncActionKind::CopyAToB => {
+                copy_server(&a, &mut b, name)?;
+                b_changed = true;
+                report.copied.push(name.clone());
+            }
+            SyncActionKind::CopyBToA => {
+                copy_server(&b, &mut a, name)?;
+                a_changed = true;
+                report.copied.push(name.clone());
+            }
+            SyncActionKind::DeleteInA | SyncActionKind::DeleteInB => {
+                let (side, changed) = match action {
+                    SyncActionKind::DeleteInA => (&mut a, &mut a_changed),
+                    _ => (&mut b, &mut b_changed),
+                };
+                // Deleting a server that is already gone is reported as skipped
+                if side.doc.remove_server(name) {
+                    side.set_carried(name, Map::new());
+                    *changed = true;
+                    report.deleted.push(name.clone());
+                } else {
+                    report.skipped.push(name.clone());
+                }
+            }
+            SyncActionKind::Skip => report.skipped.push(name.clone()),
+        }
+    }
+
+    let output_a = if a_changed { Some(a.output()?) } else { None };
+    let output_b = if b_changed { Some(b.output()?) } else { None };
+    let history_a = history_updates(&a, PlanSide::A, actions, &report.deleted, &fingerprint)?;
+    let history_b = history_updates(&b, PlanSide::B, actions, &report.deleted, &fingerprint)?;
+    let (carried_a, carried_b) = (a.carried_updates(), b.carried_updates());
+
+    if let Some(output) = &output_a {
+        write_endpoint_config(pool, ssh_pool, &a.endpoint, &a.path, a.content.as_deref(), &a.version, output).await?;
+        save_carried(pool, a.endpoint.machine_id, a.source, &a.path, carried_a).await?;
+    }
+    record_history(engine, history_a).await?;
+    if let Some(output) = &output_b {
+        write_endpoint_config(pool, ssh_pool, &b.endpoint, &b.path, b.content.as_deref(), &b.version, output)
+            .await
+            .map_err(|e| if output_a.is_some() { format!("{} ({} was already updated)", e, a.path) } else { e })?;
+        save_carried(pool, b.endpoint.machine_id, b.source, &b.path, carried_b).await?;
+    }
+    record_history(engine, history_b).await?;
+
+    println!(
+        "[Sync] Applied actions: {} copied, {} deleted, {} skipped",
+        report.copied.len(),
+        report.deleted.len(),
+        report.skipped.len()
+    );
+    Ok(report)
+}
+
+/// Apply a `generate_sync_plan` item by item (A is Claude, B is OpenCode), deletions included
+#[command]
+pub async fn apply_sync_actions(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    claude_path: String,
+    opencode_path: String,
+    machine_id: Option<i64>,
+    actions: Vec<SyncAction>,
+) -> Result<SyncActionReport, String> {
+    let (claude, opencode) = claude_opencode_sources(claude_path, opencode_path);
+    source_actions(&pool, &ssh_pool, machine_id, claude, opencode, actions).await
+}
+
+/// Apply a `generate_cross_machine_sync_plan` item by item, deletions included
+#[command]
+pub async fn apply_cross_machine_actions(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    source: SourceKind,
+    endpoint_a: SyncEndpoint,
+    endpoint_b: SyncEndpoint,
+    actions: Vec<SyncAction>,
+) -> Result<SyncActionReport, String> {
+    ensure_distinct_endpoints(&endpoint_a, &endpoint_b)?;
+
+    let target_id = cross_machine_target_id(source, &endpoint_a, &endpoint_b);
+    let engine = SyncEngine::new(&pool, "cross-machine", &target_id);
+    let a = load_sync_side(&pool, &ssh_pool, source, endpoint_a).await?;
+    let b = load_sync_side(&pool, &ssh_pool, source, endpoint_b).await?;
+    apply_actions(&pool, &ssh_pool, &engine, a, b, &actions, portable_fingerprint).await
+}
+
+/// Apply a `generate_source_sync_plan` item by item, deletions included
+#[command]
+pub async fn apply_source_actions(
+    pool: State<'_, Pool<Sqlite>>,
+    ssh_pool: State<'_, SshPool>,
+    machine_id: Option<i64>,
+    source_a: SourceRef,
+    source_b: SourceRef,
+    actions: Vec<SyncAction>,
+) -> Result<SyncActionReport, String> {
+    source_actions(&pool, &ssh_pool, machine_id, source_a, source_b, actions).await
+}
+
+async fn source_actions(
+    pool: &Pool<Sqlite>,
+    ssh_pool: &SshPool,
+    machine_id: Option<i64>,
+    source_a: SourceRef,
+    source_b: SourceRef,
+    actions: Vec<SyncAction>,
+) -> Result<SyncActionReport, String> {
+    ensure_distinct_sources(&source_a, &source_b)?;
+
+    let target_id = cross_source_target_id(source_a.source, source_b.source, machine_id);
+    let engine = SyncEngine::new(pool, "cross-source", &target_id);
+    let a = load_sync_side(pool, ssh_pool, source_a.source, SyncEndpoint { machine_id, path: source_a.path }).await?;
+    let b = load_sync_side(pool, ssh_pool, source_b.source, SyncEndpoint { machine_id, path: source_b.path }).await?;
+    apply_actions(pool, ssh_pool, &engine, a, b, &actions, portable_fingerprint).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use std::fs;
+
+    async fn local_side(pool: &Pool<Sqlite>, dir: &std::path::Path, source: SourceKind, file: &str, content: &str) -> SyncSide {
+        let path = dir.join(file).to_string_lossy().to_string();
+        fs::write(&path, content).unwrap();
+        let endpoint = SyncEndpoint { machine_id: None, path: Some(path) };
+        load_sync_side(pool, &SshPool::new(), source, endpoint).await.unwrap()
+    }
+
+    /// A Cursor config as side A and a Claude Code one as side B, both local
+    async fn local_sides(pool: &Pool<Sqlite>, dir: &std::path::Path, a: &str, b: &str) -> (SyncSide, SyncSide) {
+        (
+            local_side(pool, dir, SourceKind::Cursor, "mcp.json", a).await,
+            local_side(pool, dir, SourceKind::Claude, ".claude.json", b).await,
+        )
+    }
+
+    fn action(name: &str, action: SyncActionKind) -> SyncAction {
+        SyncAction { name: name.to_string(), action }
+    }
+
+    fn servers_in(side: &SyncSide) -> Vec<String> {
+        let doc = side.source.source().parse(&side.path, fs::read_to_string(&side.path).ok().as_deref()).unwrap();
+        let mut names: Vec<String> = doc.servers().unwrap().into_keys().collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn test_apply_actions_copies_deletes_and_records_history() {
+        let pool = test_pool().await;
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = local_sides(
+            &pool,
+            dir.path(),
+            r#"{"mcpServers": {"fs": {"command": "npx", "args": ["-y", "server-fs"]}, "old": {"command": "old-mcp"}}}"#,
+            r#"{"mcpServers": {"git": {"type": "stdio", "command": "uvx", "args": ["mcp-server-git"]}}}"#,
+        )
+        .await;
+        let engine = SyncEngine::new(&pool, "cross-source", "cursor:claude:local");
+        engine.record_synced("old", "old-hash", None).await.unwrap();
+        engine.record_synced("gone", "gone-hash", None).await.unwrap();
+
+        let actions = [
+            action("fs", SyncActionKind::CopyAToB),
+            action("old", SyncActionKind::DeleteInA),
+            action("gone", SyncActionKind::DeleteInB),
+            action("git", SyncActionKind::Skip),
+        ];
+        let (a_path, b_path) = (a.path.clone(), b.path.clone());
+        let report = apply_actions(&pool, &SshPool::new(), &engine, a, b, &actions, portable_fingerprint).await.unwrap();
+
+        assert_eq!(report.copied, vec!["fs"]);
+        assert_eq!(report.deleted, vec!["old"]);
+        assert_eq!(report.skipped, vec!["gone", "git"]);
+
+        let (a, b) = local_sides(&pool, dir.path(), &fs::read_to_string(&a_path).unwrap(), &fs::read_to_string(&b_path).unwrap()).await;
+        assert_eq!(servers_in(&a), vec!["fs"]);
+        assert_eq!(servers_in(&b), vec!["fs", "git"]);
+
+        let history = |name: &'static str| sync_history::get_sync_history(&pool, "cross-source", "cursor:claude:local", name);
+        let fs_native = b.doc.servers().unwrap().remove("fs").unwrap();
+        assert_eq!(history("fs").await.unwrap().unwrap().last_hash, portable_fingerprint(SourceKind::Claude, &fs_native));
+        assert!(history("fs").await.unwrap().unwrap().snapshot.is_some());
+        assert!(history("old").await.unwrap().is_none());
+        // Nothing was deleted, so its history stays
+        assert!(history("gone").await.unwrap().is_some());
+        assert!(history("git").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_apply_actions_keeps_fields_the_target_cannot_hold() {
+        let pool = test_pool().await;
+        let dir = tempfile::tempdir().unwrap();
+        let engine = SyncEngine::new(&pool, "cross-source", "claude:opencode:local");
+        let claude = local_side(
+            &pool,
+            dir.path(),
+            SourceKind::Claude,
+            ".claude.json",
+            r#"{"mcpServers": {"docs": {"type": "sse", "url": "https://mcp.example.com/sse"}}}"#,
+        )
+        .await;
+        let opencode = local_side(&pool, dir.path(), SourceKind::OpenCode, "opencode.json", r#"{"mcp": {}}"#).await;
+        let opencode_path = opencode.path.clone();
+        apply_actions(&pool, &SshPool::new(), &engine, claude, opencode, &[action("docs", SyncActionKind::CopyAToB)], portable_fingerprint)
+            .await
+            .unwrap();
+
+        // OpenCode's file only gets its own keys, the transport is kept by the app
+        let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&opencode_path).unwrap()).unwrap();
+        assert_eq!(written["mcp"]["docs"], serde_json::json!({"type": "remote", "enabled": true, "url": "https://mcp.example.com/sse"}));
+        let carried = carried_fields::get_carried_fields(&pool, None, "opencode", &opencode_path).await.unwrap();
+        assert_eq!(carried["docs"]["transport"], "sse");
+
+        // Copying it back into an empty Claude config restores the transport
+        let claude = local_side(&pool, dir.path(), SourceKind::Claude, ".claude.json", r#"{"mcpServers": {}}"#).await;
+        let opencode = local_side(&pool, dir.path(), SourceKind::OpenCode, "opencode.json", &written.to_string()).await;
+        let claude_path = claude.path.clone();
+        apply_actions(&pool, &SshPool::new(), &engine, claude, opencode, &[action("docs", SyncActionKind::CopyBToA)], portable_fingerprint)
+            .await
+            .unwrap();
+        let restored: serde_json::Value = serde_json::from_str(&fs::read_to_string(&claude_path).unwrap()).unwrap();
+        assert_eq!(restored["mcpServers"]["docs"]["type"], "sse");
+    }
+
+    #[tokio::test]
+    async fn test_apply_actions_records_only_the_written_side() {
+        let pool = test_pool().await;
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = local_sides(
+            &pool,
+            dir.path(),
+            r#"{"mcpServers": {"fs": {"command": "npx", "args": ["-y", "server-fs"]}}}"#,
+            r#"{"mcpServers": {"git": {"type": "stdio", "command": "uvx", "args": ["mcp-server-git"]}}}"#,
+        )
+        .await;
+        // B changes after it was read, so its write is refused
+        fs::write(&b.path, r#"{"mcpServers": {}, "numStartups": 2}"#).unwrap();
+
+        let engine = SyncEngine::new(&pool, "cross-source", "cursor:claude:local");
+        let actions = [action("fs", SyncActionKind::CopyAToB), action("git", SyncActionKind::CopyBToA)];
+        let a_path = a.path.clone();
+        let error = apply_actions(&pool, &SshPool::new(), &engine, a, b, &actions, portable_fingerprint).await.unwrap_err();
+        assert!(error.contains("was already updated"), "{}", error);
+
+        assert!(fs::read_to_string(&a_path).unwrap().contains("mcp-server-git"));
+        assert!(engine.last_snapshot("git").await.unwrap().is_some());
+        assert!(sync_history::get_sync_history(&pool, "cross-source", "cursor:claude:local", "fs").await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_cross_machine_target_id_is_direction_independent() {
+        let local = SyncEndpoint { machine_id: None, path: None };
+        let remote = SyncEndpoint { machine_id: Some(3), path: Some("~/.claude.json".to_string()) };
+
+        let forward = cross_machine_target_id(SourceKind::Claude, &local, &remote);
+        let backward = cross_machine_target_id(SourceKind::Claude, &remote, &local);
+
+        assert_eq!(forward, backward);
+        assert_eq!(forward, "claude:local:machine_3");
+        assert_ne!(forward, cross_machine_target_id(SourceKind::OpenCode, &local, &remote));
+    }
+
+    #[test]
+    fn test_claude_desktop_and_claude_code_compare_equal() {
+        let desktop = r#"{"mcpServers": {"fs": {"command": "npx", "args": ["-y", "server-fs"]}}, "globalShortcut": ""}"#;
+        let claude = r#"{"mcpServers": {
+            "fs": {"type": "stdio", "command": "npx", "args": ["-y", "server-fs"], "isActive": true},
+            "_disabled_db": {"command": "db-mcp"}
+        }}"#;
+
+        let a = parse_source_servers(SourceKind::ClaudeDesktop, "claude_desktop_config.json", Some(desktop), &CarriedFields::new(), Platform::Linux).unwrap();
+        let b = parse_source_servers(SourceKind::Claude, ".claude.json", Some(claude), &CarriedFields::new(), Platform::Linux).unwrap();
+
+        assert_eq!(b.canonical.len(), 1);
+        assert_eq!(
+            ConflictDetector::portable_fingerprint_canonical(&a.canonical["fs"]),
+            ConflictDetector::portable_fingerprint_canonical(&b.canonical["fs"])
+        );
+        assert!(a.native["fs"].get("type").is_none());
+        assert_eq!(
+            cross_source_target_id(SourceKind::ClaudeDesktop, SourceKind::Claude, Some(2)),
+            cross_source_target_id(SourceKind::Claude, SourceKind::ClaudeDesktop, Some(2))
+        );
+    }
+
+    #[test]
+    fn test_cursor_and_vscode_settings_compare_equal() {
+        let cursor = r#"{"mcpServers": {"git": {"command": "uvx", "args": ["mcp-server-git"]}}}"#;
+        let settings = r#"{
+            "files.autoSave": "afterDelay", // comment
+            "mcp": {"servers": {"git": {"type": "stdio", "command": "uvx", "args": ["mcp-server-git"]}}}
+        }"#;
+
+        let a = parse_source_servers(SourceKind::Cursor, "~/.cursor/mcp.json", Some(cursor), &CarriedFields::new(), Platform::Linux).unwrap();
+        let b = parse_source_servers(SourceKind::VsCode, "~/.config/Code/User/settings.json", Some(settings), &CarriedFields::new(), Platform::Linux).unwrap();
+
+        assert_eq!(
+            ConflictDetector::portable_fingerprint_canonical(&a.canonical["git"]),
+            ConflictDetector::portable_fingerprint_canonical(&b.canonical["git"])
+        );
+        assert_eq!(b.native["git"]["type"], "stdio");
+    }
+
+    #[test]
+    fn test_codex_compares_with_claude() {
+        let codex = "model = \"o4-mini\"\n\n[mcp_servers.fs]\ncommand = \"npx\"\nargs = [\"-y\", \"server-fs\"]\n";
+        let claude = r#"{"mcpServers": {"fs": {"type": "stdio", "command": "npx", "args": ["-y", "server-fs"]}}}"#;
+
+        let a = parse_source_servers(SourceKind::Codex, "~/.codex/config.toml", Some(codex), &CarriedFields::new(), Platform::Linux).unwrap();
+        let b = parse_source_servers(SourceKind::Claude, ".claude.json", Some(claude), &CarriedFields::new(), Platform::Linux).unwrap();
+
+        assert_eq!(
+            ConflictDetector::portable_fingerprint_canonical(&a.canonical["fs"]),
+            ConflictDetector::portable_fingerprint_canonical(&b.canonical["fs"])
+        );
+    }
+
+    #[test]
+    fn test_ensure_distinct_endpoints() {
+        let a = SyncEndpoint { machine_id: Some(1), path: None };
+        let b = SyncEndpoint { machine_id: Some(1), path: Some("/other".to_string()) };
+        let c = SyncEndpoint { machine_id: Some(2), path: None };
+
+        assert!(ensure_distinct_endpoints(&a, &b).is_err());
+        assert!(ensure_distinct_endpoints(&a, &c).is_ok());
+    }
+
+    #[test]
+    fn test_ensure_servers_exist_lists_missing_names() {
+        let servers = HashMap::from([("fs".to_string(), serde_json::json!({"command": "fs-mcp"}))]);
+
+        assert!(ensure_servers_exist(&servers, &["fs".to_string()], "/a.json").is_ok());
+        let err = ensure_servers_exist(&servers, &["fs".to_string(), "db".to_string(), "gh".to_string()], "/a.json").unwrap_err();
+        assert_eq!(err, "Servers not found in /a.json: db, gh");
+    }
+}
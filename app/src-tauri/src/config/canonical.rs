@@ -0,0 +1,241 @@
+use super::converter::{adapt_command_for_platform, Platform};
+use super::source::ConfigSource;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// How the client talks to the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    #[default]
+    Stdio,
+    Sse,
+    /// Streamable HTTP
+    Http,
+}
+
+impl Transport {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Transport::Stdio => "stdio",
+            Transport::Sse => "sse",
+            Transport::Http => "http",
+        }
+    }
+
+    /// A `type` value as clients write it
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "stdio" => Some(Transport::Stdio),
+            "sse" => Some(Transport::Sse),
+            "http" | "streamable-http" | "streamableHttp" => Some(Transport::Http),
+            _ => None,
+        }
+    }
+
+    /// For clients that leave `type` optional: remote when there is a URL, stdio otherwise
+    pub fn infer(url: Option<&String>) -> Self {
+        match url {
+            Some(_) => Transport::Http,
+            None => Transport::Stdio,
+        }
+    }
+
+    pub fn is_remote(self) -> bool {
+        self != Transport::Stdio
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthConfig {
+    /// `Some(false)` turns the client's automatic OAuth off
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<String>>,
+
+    /// Endpoints, audiences, redirect URI...
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, Value>,
+}
+
+/// A server in a client-neutral form, the hub every source converts through.
+///
+/// Optional fields stay `None` when the client didn't set them, so converting back writes the same entry.
+/// Fields only one client understands live in `extensions` under that client's id and are only written back
+/// to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServer {
+    pub transport: Transport,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<HashMap<String, String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headers: Option<HashMap<String, String>>,
+
+    /// `None` when the client doesn't say, which every client reads as enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    /// How long to wait for the server to start / list its tools
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub startup_timeout_ms: Option<u64>,
+
+    /// How long to wait for a tool call
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_timeout_ms: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oauth: Option<OAuthConfig>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extensions: BTreeMap<String, Map<String, Value>>,
+}
+
+impl McpServer {
+    /// The fields only `client` understands (empty if none)
+    pub fn extension(&self, client: &str) -> Map<String, Value> {
+        self.extensions.get(client).cloned().unwrap_or_default()
+    }
+
+    /// Store `fields` as `client`'s extension, dropping it when empty
+    pub fn set_extension(&mut self, client: &str, fields: Map<String, Value>) {
+        if fields.is_empty() {
+            self.extensions.remove(client);
+        } else {
+            self.extensions.insert(client.to_string(), fields);
+        }
+    }
+
+    /// Command and args as one list, as OpenCode writes it
+    pub fn command_line(&self) -> Option<Vec<String>> {
+        let command = self.command.clone()?;
+        let mut full = vec![command];
+        full.extend(self.args.clone().unwrap_or_default());
+        Some(full)
+    }
+
+    /// Split a command line into command + args. A lone command keeps `args` as it was (absent or empty).
+    pub fn set_command_line(&mut self, command_line: Vec<String>) {
+        let mut parts = command_line.into_iter();
+        self.command = parts.next();
+        let args: Vec<String> = parts.collect();
+        if !args.is_empty() {
+            self.args = Some(args);
+        } else if self.args.is_some() {
+            self.args = Some(Vec::new());
+        }
+    }
+
+    /// The same server with its command re-targeted for `platform` (`cmd /c` wrapping on Windows)
+    pub fn adapted_for(&self, platform: Platform) -> McpServer {
+        let mut adapted = self.clone();
+        if let Some(command_line) = self.command_line().and_then(|c| adapt_command_for_platform(c, platform)) {
+            adapted.set_command_line(command_line);
+        }
+        adapted
+    }
+}
+
+fn to_object(server: &McpServer) -> Result<Map<String, Value>, String> {
+    match serde_json::to_value(server).map_err(|e| format!("Failed to serialize server: {}", e))? {
+        Value::Object(fields) => Ok(fields),
+        _ => Err("Server must serialize to an object".to_string()),
+    }
+}
+
+fn from_object(fields: Map<String, Value>) -> Result<McpServer, String> {
+    serde_json::from_value(Value::Object(fields)).map_err(|e| format!("Invalid carried fields: {}", e))
+}
+
+/// Canonical fields of `server` that `source`'s format can't hold: what is lost by formatting it and parsing
+/// the result back. Extensions are compared per client.
+pub(crate) fn unrepresented_fields<S: ConfigSource + ?Sized>(
+    source: &S,
+    server: &McpServer,
+    native: &Value,
+) -> Result<Map<String, Value>, String> {
+    let before = to_object(server)?;
+    let after = to_object(&source.parse_server(native)?)?;
+
+    let mut missing = Map::new();
+    for (field, value) in before {
+        if field == "extensions" {
+            let after_extensions = after.get("extensions").and_then(Value::as_object);
+            let lost: Map<String, Value> = value
+                .as_object()
+                .into_iter()
+                .flatten()
+                .filter(|(client, ext)| after_extensions.and_then(|a| a.get(client.as_str())) != Some(ext))
+                .map(|(client, ext)| (client.clone(), ext.clone()))
+                .collect();
+            if !lost.is_empty() {
+                missing.insert(field, Value::Object(lost));
+            }
+        } else if after.get(&field) != Some(&value) {
+            missing.insert(field, value);
+        }
+    }
+    Ok(missing)
+}
+
+/// Put back fields a client has no place for (e.g. `sse` in OpenCode, which only knows "remote"), as kept by
+/// the app when it last wrote the entry (see `unrepresented_fields`). A carried field is only used while it is
+/// still invisible to the client, so an entry edited by hand (say, remote turned local) wins.
+pub(crate) fn restore_carried<S: ConfigSource + ?Sized>(
+    source: &S,
+    server: McpServer,
+    carried: &Map<String, Value>,
+) -> Result<McpServer, String> {
+    if carried.is_empty() {
+        return Ok(server);
+    }
+
+    let formatted = source.format_server(&server)?;
+    let mut restored = to_object(&server)?;
+    for (field, value) in carried.clone() {
+        let mut candidate = restored.clone();
+        if field == "extensions" {
+            // Other clients' extensions are never written to this one, so they can always come back
+            let extensions = candidate.entry("extensions").or_insert_with(|| Value::Object(Map::new()));
+            if let (Some(extensions), Value::Object(carried)) = (extensions.as_object_mut(), value) {
+                for (client, ext) in carried {
+                    extensions.entry(client).or_insert(ext);
+                }
+            }
+        } else {
+            candidate.insert(field, value);
+        }
+
+        let Ok(candidate_server) = from_object(candidate.clone()) else {
+            continue;
+        };
+        if source.format_server(&candidate_server).ok().as_ref() == Some(&formatted) {
+            restored = candidate;
+        }
+    }
+    from_object(restored)
+}
@@ -1,5 +1,15 @@
+use super::converter;
+use super::json_edit::PreservingDocument;
+use super::source::{from_json, json_pointer, servers_to_json, to_json, to_pretty_json, ConfigSource, InstallCheck, RemoteLocation, SourceDocument};
+use super::{McpServer, SourceKind};
+use crate::paths::AppPaths;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Key prefix this app uses to park a disabled server where Claude Code won't load it
+pub const DISABLED_PREFIX: &str = "_disabled_";
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ClaudeMCPServer {
@@ -26,6 +36,28 @@ pub struct ClaudeMCPServer {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub headers: Option<HashMap<String, String>>,
+
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, serde_json::Value>,
+}
+
+/// Per-project state Claude Code keeps in `~/.claude.json` under `projects.<absolute project path>`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ClaudeProjectConfig {
+    /// Servers only available in this project ("local" scope)
+    #[serde(rename = "mcpServers", default, skip_serializing_if = "HashMap::is_empty")]
+    pub mcp_servers: HashMap<String, ClaudeMCPServer>,
+
+    /// Servers from the project's `.mcp.json` the user approved
+    #[serde(rename = "enabledMcpjsonServers", default, skip_serializing_if = "Vec::is_empty")]
+    pub enabled_mcpjson_servers: Vec<String>,
+
+    /// Servers from the project's `.mcp.json` the user rejected
+    #[serde(rename = "disabledMcpjsonServers", default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_mcpjson_servers: Vec<String>,
+
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +65,9 @@ pub struct ClaudeConfig {
     #[serde(rename = "mcpServers", default)]
     pub mcp_servers: HashMap<String, ClaudeMCPServer>,
 
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub projects: HashMap<String, ClaudeProjectConfig>,
+
     #[serde(flatten)]
     pub other_fields: HashMap<String, serde_json::Value>,
 }
@@ -41,6 +76,7 @@ impl Default for ClaudeConfig {
     fn default() -> Self {
         Self {
             mcp_servers: HashMap::new(),
+            projects: HashMap::new(),
             other_fields: HashMap::new(),
         }
     }
@@ -67,3 +103,132 @@ impl ClaudeConfig {
             .collect()
     }
 }
+
+/// Enable/disable one server by moving it between `<name>` and `_disabled_<name>` (global or project
+/// `mcpServers`). Returns `false` if the server is in neither slot.
+pub fn set_claude_server_enabled(servers: &mut HashMap<String, ClaudeMCPServer>, name: &str, enabled: bool) -> bool {
+    let disabled_name = format!("{}{}", DISABLED_PREFIX, name);
+    let (from, to) = if enabled { (disabled_name, name.to_string()) } else { (name.to_string(), disabled_name) };
+
+    match servers.remove(&from) {
+        Some(mut server) => {
+            server.is_active = Some(enabled);
+            servers.insert(to, server);
+            true
+        }
+        None => servers.contains_key(&to),
+    }
+}
+
+/// Add or replace one server (global or project `mcpServers`). A disabled server stays disabled: it is written
+/// to `_disabled_<name>` when only that slot exists.
+pub fn put_claude_server(servers: &mut HashMap<String, ClaudeMCPServer>, name: &str, server: ClaudeMCPServer) {
+    let disabled_name = format!("{}{}", DISABLED_PREFIX, name);
+    let key = if !servers.contains_key(name) && servers.contains_key(&disabled_name) {
+        disabled_name
+    } else {
+        name.to_string()
+    };
+    servers.insert(key, server);
+}
+
+/// Claude Code's `~/.claude.json`
+pub struct ClaudeSource;
+
+impl ConfigSource for ClaudeSource {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Claude
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Claude Code"
+    }
+
+    fn local_path(&self, paths: &AppPaths) -> PathBuf {
+        paths.claude_path.clone()
+    }
+
+    fn remote_location(&self) -> RemoteLocation {
+        // In the home directory on every platform. The app-data `Claude` folders belong to Claude Desktop.
+        RemoteLocation::single("~/.claude.json")
+    }
+
+    fn install_check(&self) -> InstallCheck {
+        InstallCheck::Cli("claude")
+    }
+
+    fn parse(&self, _path: &str, content: Option<&str>) -> Result<Box<dyn SourceDocument>, String> {
+        let config = match content {
+            Some(c) => serde_json::from_str(c).map_err(|e| format!("Failed to parse Claude config: {}", e))?,
+            None => ClaudeConfig::default(),
+        };
+        Ok(Box::new(PreservingDocument::new(config, content)))
+    }
+
+    fn supports_enabled(&self) -> bool {
+        true
+    }
+
+    fn parse_server(&self, server: &Value) -> Result<McpServer, String> {
+        converter::claude_to_canonical(&from_json(server)?)
+    }
+
+    fn format_server(&self, server: &McpServer) -> Result<Value, String> {
+        to_json(&converter::canonical_to_claude(server))
+    }
+}
+
+/// Disabled servers are listed under their plain name
+impl SourceDocument for ClaudeConfig {
+    fn servers(&self) -> Result<HashMap<String, Value>, String> {
+        let mut servers = servers_to_json(&self.mcp_servers)?;
+        let disabled: Vec<String> = servers.keys().filter(|k| k.starts_with(DISABLED_PREFIX)).cloned().collect();
+        for key in disabled {
+            if let Some(server) = servers.remove(&key) {
+                servers.entry(key[DISABLED_PREFIX.len()..].to_string()).or_insert(server);
+            }
+        }
+        Ok(servers)
+    }
+
+    /// A disabled server is at its `_disabled_` key
+    fn server_pointer(&self, name: &str) -> String {
+        let disabled_name = format!("{}{}", DISABLED_PREFIX, name);
+        let key = if self.mcp_servers.contains_key(name) || !self.mcp_servers.contains_key(&disabled_name) {
+            name
+        } else {
+            &disabled_name
+        };
+        json_pointer(&["mcpServers", key])
+    }
+
+    fn is_enabled(&self, name: &str) -> bool {
+        match self.mcp_servers.get(name) {
+            Some(server) => server.is_active.unwrap_or(true),
+            None => !self.mcp_servers.contains_key(&format!("{}{}", DISABLED_PREFIX, name)),
+        }
+    }
+
+    /// A disabled server stays disabled when it is replaced
+    fn put_server(&mut self, name: &str, server: Value) -> Result<(), String> {
+        put_claude_server(&mut self.mcp_servers, name, from_json(&server)?);
+        Ok(())
+    }
+
+    fn remove_server(&mut self, name: &str) -> bool {
+        let removed = self.mcp_servers.remove(name).is_some();
+        let removed_disabled = self.mcp_servers.remove(&format!("{}{}", DISABLED_PREFIX, name)).is_some();
+        removed || removed_disabled
+    }
+
+    fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), String> {
+        if !set_claude_server_enabled(&mut self.mcp_servers, name, enabled) {
+            return Err(format!("Server '{}' not found in Claude config", name));
+        }
+        Ok(())
+    }
+
+    fn serialize(&self) -> Result<String, String> {
+        to_pretty_json(self)
+    }
+}
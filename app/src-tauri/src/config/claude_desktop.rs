@@ -0,0 +1,141 @@
+use super::converter;
+use super::json_edit::PreservingDocument;
+use super::source::{from_json, json_pointer, servers_to_json, to_json, to_pretty_json, ConfigSource, InstallCheck, RemoteLocation, SourceDocument};
+use super::{McpServer, SourceKind};
+use crate::paths::AppPaths;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// File name Claude Desktop reads from its app-data folder (not to be confused with Claude Code's `~/.claude.json`)
+pub const CLAUDE_DESKTOP_FILE_NAME: &str = "claude_desktop_config.json";
+
+/// A server in `claude_desktop_config.json`. Desktop only launches local stdio servers from this file and has
+/// no `type` or `isActive`: a server is enabled by being listed.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ClaudeDesktopMCPServer {
+    pub command: String,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<HashMap<String, String>>,
+
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ClaudeDesktopConfig {
+    #[serde(rename = "mcpServers", default)]
+    pub mcp_servers: HashMap<String, ClaudeDesktopMCPServer>,
+
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, serde_json::Value>,
+}
+
+/// Claude Desktop's `claude_desktop_config.json`. Desktop has no enabled flag: a server is removed to disable it.
+pub struct ClaudeDesktopSource;
+
+impl ConfigSource for ClaudeDesktopSource {
+    fn kind(&self) -> SourceKind {
+        SourceKind::ClaudeDesktop
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Claude Desktop"
+    }
+
+    fn local_path(&self, paths: &AppPaths) -> PathBuf {
+        paths.claude_desktop_path.clone()
+    }
+
+    fn remote_location(&self) -> RemoteLocation {
+        let by_platform = [
+            format!("~/Library/Application Support/Claude/{}", CLAUDE_DESKTOP_FILE_NAME), // macOS
+            format!("~/AppData/Roaming/Claude/{}", CLAUDE_DESKTOP_FILE_NAME), // Windows (%APPDATA%)
+            format!("~/.config/Claude/{}", CLAUDE_DESKTOP_FILE_NAME), // Linux community builds
+        ];
+        RemoteLocation { candidates: by_platform.to_vec(), default_by_platform: by_platform }
+    }
+
+    fn install_check(&self) -> InstallCheck {
+        InstallCheck::Shell([
+            "test -d '/Applications/Claude.app' && echo INSTALLED",
+            "powershell -Command \"if (Test-Path '$env:LOCALAPPDATA\\AnthropicClaude\\claude.exe') { Write-Host 'INSTALLED' }\"",
+            // No official Linux build; community packages install to /opt/Claude or as a flatpak
+            "test -d /opt/Claude && echo INSTALLED || flatpak list 2>/dev/null | grep -q com.anthropic.Claude && echo INSTALLED",
+        ])
+    }
+
+    fn parse(&self, _path: &str, content: Option<&str>) -> Result<Box<dyn SourceDocument>, String> {
+        let config = match content {
+            Some(c) => serde_json::from_str(c).map_err(|e| format!("Failed to parse Claude Desktop config: {}", e))?,
+            None => ClaudeDesktopConfig::default(),
+        };
+        Ok(Box::new(PreservingDocument::new(config, content)))
+    }
+
+    fn parse_server(&self, server: &Value) -> Result<McpServer, String> {
+        Ok(converter::claude_desktop_to_canonical(&from_json(server)?))
+    }
+
+    fn format_server(&self, server: &McpServer) -> Result<Value, String> {
+        to_json(&converter::canonical_to_claude_desktop(server)?)
+    }
+}
+
+impl SourceDocument for ClaudeDesktopConfig {
+    fn servers(&self) -> Result<HashMap<String, Value>, String> {
+        servers_to_json(&self.mcp_servers)
+    }
+
+    fn server_pointer(&self, name: &str) -> String {
+        json_pointer(&["mcpServers", name])
+    }
+
+    fn put_server(&mut self, name: &str, server: Value) -> Result<(), String> {
+        let server: ClaudeDesktopMCPServer = from_json(&server)?;
+        if server.command.trim().is_empty() {
+            return Err("Claude Desktop servers need a command".to_string());
+        }
+        self.mcp_servers.insert(name.to_string(), server);
+        Ok(())
+    }
+
+    fn remove_server(&mut self, name: &str) -> bool {
+        self.mcp_servers.remove(name).is_some()
+    }
+
+    fn serialize(&self) -> Result<String, String> {
+        to_pretty_json(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claude_desktop_config_keeps_other_fields() {
+        let json = r#"{
+            "mcpServers": {
+                "fs": {"command": "npx", "args": ["-y", "@modelcontextprotocol/server-filesystem", "/tmp"]}
+            },
+            "globalShortcut": "Ctrl+Space"
+        }"#;
+        let mut doc = ClaudeDesktopSource.parse(CLAUDE_DESKTOP_FILE_NAME, Some(json)).unwrap();
+        assert_eq!(doc.servers().unwrap()["fs"]["command"], "npx");
+
+        let out: Value = serde_json::from_str(&doc.serialize().unwrap()).unwrap();
+        assert_eq!(out["globalShortcut"], "Ctrl+Space");
+        // Desktop's schema has no type/isActive, and none is added on write
+        assert!(out["mcpServers"]["fs"].get("type").is_none());
+        assert!(out["mcpServers"]["fs"].get("isActive").is_none());
+
+        assert!(doc.put_server("empty", serde_json::json!({"command": " "})).is_err());
+        assert!(ClaudeDesktopSource.parse(CLAUDE_DESKTOP_FILE_NAME, None).unwrap().servers().unwrap().is_empty());
+    }
+}
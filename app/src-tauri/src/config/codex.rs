@@ -0,0 +1,351 @@
+use super::converter;
+use super::source::{from_json, json_pointer, to_json, ConfigSource, InstallCheck, RemoteLocation, SourceDocument};
+use super::{McpServer, SourceKind};
+use crate::paths::AppPaths;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use toml_edit::{DocumentMut, InlineTable, Item, Table, Value};
+
+/// Top-level table Codex CLI reads servers from: `[mcp_servers.<name>]`
+const SERVERS_KEY: &str = "mcp_servers";
+
+/// Keys written first, in this order, when a server table is created
+const KEY_ORDER: [&str; 6] = ["command", "args", "env", "url", "http_headers", "enabled"];
+
+/// A server in Codex CLI's `~/.codex/config.toml`. Local servers have `command`/`args`/`env`,
+/// streamable HTTP ones a `url`. Other keys (`cwd`, `startup_timeout_sec`, `enabled_tools`...) are kept as-is.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct CodexMCPServer {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<HashMap<String, String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_headers: Option<HashMap<String, String>>,
+
+    /// Codex skips servers with `enabled = false`; missing means enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, serde_json::Value>,
+}
+
+/// An editable `config.toml`. Edits touch only the affected server tables, so comments, key order and
+/// every other setting in the file survive a round trip.
+#[derive(Debug, Clone, Default)]
+pub struct CodexDocument {
+    doc: DocumentMut,
+}
+
+impl CodexDocument {
+    /// Parse `content` (`None` for a file that doesn't exist yet)
+    pub fn parse(content: Option<&str>) -> Result<Self, String> {
+        let doc = match content {
+            Some(c) => c
+                .parse::<DocumentMut>()
+                .map_err(|e| format!("Failed to parse Codex config: {}", e))?,
+            None => DocumentMut::new(),
+        };
+        Ok(Self { doc })
+    }
+
+    pub fn servers(&self) -> Result<HashMap<String, CodexMCPServer>, String> {
+        let Some(servers) = self.doc.get(SERVERS_KEY).and_then(Item::as_table_like) else {
+            return Ok(HashMap::new());
+        };
+
+        servers
+            .iter()
+            .map(|(name, item)| {
+                let server = serde_json::from_value(item_to_json(item))
+                    .map_err(|e| format!("Invalid Codex server '{}': {}", name, e))?;
+                Ok((name.to_string(), server))
+            })
+            .collect()
+    }
+
+    /// Add or replace a server. An existing table is updated key by key, leaving unchanged keys
+    /// (and their comments/formatting) untouched.
+    pub fn set_server(&mut self, name: &str, server: &CodexMCPServer) -> Result<(), String> {
+        let fields = match serde_json::to_value(server).map_err(|e| format!("Failed to serialize server: {}", e))? {
+            serde_json::Value::Object(fields) => fields,
+            _ => return Err("Server must serialize to a table".to_string()),
+        };
+
+        let servers = self.servers_table_mut()?;
+        match servers.get_mut(name).and_then(Item::as_table_like_mut) {
+            Some(table) => {
+                let stale: Vec<String> = table
+                    .iter()
+                    .map(|(k, _)| k.to_string())
+                    .filter(|k| !fields.contains_key(k))
+                    .collect();
+                for key in stale {
+                    table.remove(&key);
+                }
+                for (key, value) in &fields {
+                    if table.get(key).map(item_to_json).as_ref() != Some(value) {
+                        if let Some(v) = json_to_toml(value) {
+                            table.insert(key, Item::Value(v));
+                        }
+                    }
+                }
+            }
+            None => {
+                let mut table = Table::new();
+                let ordered = KEY_ORDER
+                    .iter()
+                    .filter_map(|k| fields.get_key_value(*k))
+                    .chain(fields.iter().filter(|(k, _)| !KEY_ORDER.contains(&k.as_str())));
+                for (key, value) in ordered {
+                    if let Some(v) = json_to_toml(value) {
+                        table.insert(key, Item::Value(v));
+                    }
+                }
+                servers.insert(name, Item::Table(table));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn remove_server(&mut self, name: &str) -> bool {
+        self.doc
+            .get_mut(SERVERS_KEY)
+            .and_then(Item::as_table_like_mut)
+            .map(|servers| servers.remove(name).is_some())
+            .unwrap_or(false)
+    }
+
+    /// Set `enabled`, dropping the key when enabling since that's Codex's default
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), String> {
+        let table = self
+            .doc
+            .get_mut(SERVERS_KEY)
+            .and_then(Item::as_table_like_mut)
+            .and_then(|servers| servers.get_mut(name))
+            .and_then(Item::as_table_like_mut)
+            .ok_or_else(|| format!("Server '{}' not found in Codex config", name))?;
+
+        if enabled {
+            table.remove("enabled");
+        } else {
+            table.insert("enabled", Item::Value(false.into()));
+        }
+        Ok(())
+    }
+
+    pub fn to_toml_string(&self) -> String {
+        self.doc.to_string()
+    }
+
+    fn servers_table_mut(&mut self) -> Result<&mut dyn toml_edit::TableLike, String> {
+        let servers = self.doc.entry(SERVERS_KEY).or_insert_with(|| {
+            // Only the `[mcp_servers.<name>]` headers get written, not an empty `[mcp_servers]`
+            let mut table = Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        });
+        servers
+            .as_table_like_mut()
+            .ok_or_else(|| format!("`{}` in Codex config is not a table", SERVERS_KEY))
+    }
+}
+
+pub struct CodexSource;
+
+impl ConfigSource for CodexSource {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Codex
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Codex"
+    }
+
+    fn local_path(&self, paths: &AppPaths) -> PathBuf {
+        paths.codex_path.clone()
+    }
+
+    fn remote_location(&self) -> RemoteLocation {
+        // %USERPROFILE%\.codex on Windows too. CODEX_HOME overrides are only picked up with an explicit path.
+        RemoteLocation::single("~/.codex/config.toml")
+    }
+
+    fn install_check(&self) -> InstallCheck {
+        InstallCheck::Cli("codex")
+    }
+
+    fn parse(&self, _path: &str, content: Option<&str>) -> Result<Box<dyn SourceDocument>, String> {
+        Ok(Box::new(CodexDocument::parse(content)?))
+    }
+
+    fn supports_enabled(&self) -> bool {
+        true
+    }
+
+    fn parse_server(&self, server: &serde_json::Value) -> Result<McpServer, String> {
+        Ok(converter::codex_to_canonical(&from_json(server)?))
+    }
+
+    fn format_server(&self, server: &McpServer) -> Result<serde_json::Value, String> {
+        to_json(&converter::canonical_to_codex(server))
+    }
+}
+
+impl SourceDocument for CodexDocument {
+    fn servers(&self) -> Result<HashMap<String, serde_json::Value>, String> {
+        CodexDocument::servers(self)?
+            .iter()
+            .map(|(name, server)| Ok((name.clone(), to_json(server)?)))
+            .collect()
+    }
+
+    /// The server's table, addressed as if the TOML were JSON
+    fn server_pointer(&self, name: &str) -> String {
+        json_pointer(&[SERVERS_KEY, name])
+    }
+
+    fn is_enabled(&self, name: &str) -> bool {
+        self.doc
+            .get(SERVERS_KEY)
+            .and_then(|servers| servers.get(name))
+            .and_then(|server| server.get("enabled"))
+            .and_then(Item::as_bool)
+            .unwrap_or(true)
+    }
+
+    fn put_server(&mut self, name: &str, server: serde_json::Value) -> Result<(), String> {
+        self.set_server(name, &from_json(&server)?)
+    }
+
+    fn remove_server(&mut self, name: &str) -> bool {
+        CodexDocument::remove_server(self, name)
+    }
+
+    fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), String> {
+        CodexDocument::set_enabled(self, name, enabled)
+    }
+
+    fn serialize(&self) -> Result<String, String> {
+        Ok(self.to_toml_string())
+    }
+}
+
+fn item_to_json(item: &Item) -> serde_json::Value {
+    match item {
+        Item::None => serde_json::Value::Null,
+        Item::Value(v) => value_to_json(v),
+        Item::Table(t) => t.iter().map(|(k, v)| (k.to_string(), item_to_json(v))).collect(),
+        Item::ArrayOfTables(a) => a
+            .iter()
+            .map(|t| t.iter().map(|(k, v)| (k.to_string(), item_to_json(v))).collect::<serde_json::Value>())
+            .collect(),
+    }
+}
+
+fn value_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::String(s) => s.value().clone().into(),
+        Value::Integer(i) => (*i.value()).into(),
+        Value::Float(f) => serde_json::Number::from_f64(*f.value())
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        Value::Boolean(b) => (*b.value()).into(),
+        Value::Datetime(d) => d.value().to_string().into(),
+        Value::Array(a) => a.iter().map(value_to_json).collect(),
+        Value::InlineTable(t) => t.iter().map(|(k, v)| (k.to_string(), value_to_json(v))).collect(),
+    }
+}
+
+/// TOML has no null, so null values (and nulls inside arrays/tables) are left out
+fn json_to_toml(value: &serde_json::Value) -> Option<Value> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::Bool(b) => Some((*b).into()),
+        serde_json::Value::Number(n) => n
+            .as_i64()
+            .map(Value::from)
+            .or_else(|| n.as_f64().map(Value::from)),
+        serde_json::Value::String(s) => Some(s.as_str().into()),
+        serde_json::Value::Array(items) => Some(Value::Array(items.iter().filter_map(json_to_toml).collect())),
+        serde_json::Value::Object(fields) => {
+            let mut table = InlineTable::new();
+            for (k, v) in fields {
+                if let Some(v) = json_to_toml(v) {
+                    table.insert(k, v);
+                }
+            }
+            Some(Value::InlineTable(table))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"# Codex settings
+model = "o4-mini"
+approval_policy = "on-request"
+
+[mcp_servers.docs]
+# keep this one pinned
+command = "npx"
+args = ["-y", "docs-mcp@1.2.0"]
+env = { "DOCS_TOKEN" = "abc" }
+startup_timeout_sec = 20
+
+[profiles.fast]
+model = "gpt-4.1-mini"
+"#;
+
+    #[test]
+    fn test_codex_round_trip_preserves_rest_of_file() {
+        let mut doc = CodexDocument::parse(Some(CONFIG)).unwrap();
+        let servers = doc.servers().unwrap();
+        let docs = &servers["docs"];
+        assert_eq!(docs.command.as_deref(), Some("npx"));
+        assert_eq!(docs.env.as_ref().unwrap()["DOCS_TOKEN"], "abc");
+        assert_eq!(docs.other_fields["startup_timeout_sec"], 20);
+
+        // Untouched document prints back byte for byte
+        assert_eq!(doc.to_toml_string(), CONFIG);
+
+        let mut updated = docs.clone();
+        updated.args = Some(vec!["-y".to_string(), "docs-mcp@1.3.0".to_string()]);
+        doc.set_server("docs", &updated).unwrap();
+        doc.set_server("git", &CodexMCPServer {
+            command: Some("uvx".to_string()),
+            args: Some(vec!["mcp-server-git".to_string()]),
+            ..Default::default()
+        })
+        .unwrap();
+        doc.set_enabled("git", false).unwrap();
+
+        let out = doc.to_toml_string();
+        assert!(out.starts_with("# Codex settings\nmodel = \"o4-mini\""));
+        assert!(out.contains("# keep this one pinned\ncommand = \"npx\""));
+        assert!(out.contains("docs-mcp@1.3.0"));
+        assert!(out.contains("[profiles.fast]"));
+        assert!(out.contains("[mcp_servers.git]\ncommand = \"uvx\""));
+        assert!(!out.contains("[mcp_servers]\n"));
+
+        let reparsed = CodexDocument::parse(Some(&out)).unwrap().servers().unwrap();
+        assert_eq!(reparsed["git"].enabled, Some(false));
+        assert_eq!(reparsed["docs"].other_fields["startup_timeout_sec"], 20);
+
+        assert!(doc.remove_server("git"));
+        assert!(!doc.remove_server("git"));
+        assert!(CodexDocument::parse(Some("mcp_servers = [")).is_err());
+    }
+}
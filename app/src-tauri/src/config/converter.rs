@@ -1,4 +1,13 @@
-use super::{ClaudeMCPServer, OpenCodeMCPServer};
+use super::canonical::{McpServer, OAuthConfig, Transport};
+use super::source::{from_json, to_json};
+use super::{
+    ClaudeDesktopMCPServer, ClaudeMCPServer, CodexMCPServer, CursorMCPServer, GeminiMCPServer, OpenCodeMCPServer,
+    SourceKind, VsCodeMCPServer,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
 use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -34,85 +43,454 @@ impl Platform {
     }
 }
 
-#[allow(dead_code)]
-pub fn claude_to_opencode(
-    server: &ClaudeMCPServer,
-    target_platform: Platform,
-) -> OpenCodeMCPServer {
-    let server_type = match server.server_type.as_deref() {
-        Some("stdio") | None => "local".to_string(),
-        Some("sse") | Some("http") => "remote".to_string(),
-        Some(other) => other.to_string(),
-    };
+fn to_map(fields: &HashMap<String, Value>) -> Map<String, Value> {
+    fields.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+}
 
-    let command = if server_type == "local" {
-        match (&server.command, &server.args) {
-            (Some(cmd), Some(args)) => {
-                let mut full_cmd = vec![cmd.clone()];
-                full_cmd.extend(args.clone());
-                adapt_command_for_platform(full_cmd, target_platform)
-            }
-            (Some(cmd), None) => Some(vec![cmd.clone()]),
-            _ => None,
-        }
+fn to_fields(map: Map<String, Value>) -> HashMap<String, Value> {
+    map.into_iter().collect()
+}
+
+/// Remember `type` as the client wrote it when that isn't how it would be written by default (left out, or
+/// spelled "streamable-http"), so formatting gives the same entry back
+fn remember_type(ext: &mut Map<String, Value>, written: Option<&str>, default: Option<&str>) {
+    if written != default {
+        ext.insert("type".to_string(), written.map(Value::from).unwrap_or(Value::Null));
+    }
+}
+
+/// The `type` to write: as remembered while it still names the same transport, else `default`
+fn written_type(ext: &mut Map<String, Value>, transport: Transport, url: Option<&String>, default: Option<&str>) -> Option<String> {
+    match ext.remove("type") {
+        Some(Value::Null) if Transport::infer(url) == transport => None,
+        Some(Value::String(t)) if Transport::parse(&t) == Some(transport) => Some(t),
+        _ => default.map(str::to_string),
+    }
+}
+
+fn parse_type(client: &str, written: Option<&str>, url: Option<&String>) -> Result<Transport, String> {
+    match written {
+        Some(t) => Transport::parse(t).ok_or_else(|| format!("Unsupported {} server type '{}'", client, t)),
+        None => Ok(Transport::infer(url)),
+    }
+}
+
+fn take<T: DeserializeOwned>(ext: &mut Map<String, Value>, key: &str) -> Option<T> {
+    let value = serde_json::from_value(ext.get(key)?.clone()).ok()?;
+    ext.remove(key);
+    Some(value)
+}
+
+fn seconds_value(ms: u64) -> Value {
+    if ms.is_multiple_of(1000) {
+        Value::from(ms / 1000)
     } else {
-        None
+        Value::from(ms as f64 / 1000.0)
+    }
+}
+
+fn as_ms(seconds: &Value) -> Option<u64> {
+    seconds.as_f64().map(|s| (s * 1000.0).round() as u64)
+}
+
+/// Read a seconds field as milliseconds. The original stays in `ext` when writing the milliseconds back
+/// would spell it differently (`10.0` vs `10`), and `put_seconds` then keeps it.
+fn take_seconds(ext: &mut Map<String, Value>, key: &str) -> Option<u64> {
+    let ms = as_ms(ext.get(key)?)?;
+    if ext.get(key) == Some(&seconds_value(ms)) {
+        ext.remove(key);
+    }
+    Some(ms)
+}
+
+fn put_seconds(fields: &mut Map<String, Value>, key: &str, ms: Option<u64>) {
+    match ms {
+        Some(ms) if fields.get(key).and_then(as_ms) != Some(ms) => {
+            fields.insert(key.to_string(), seconds_value(ms));
+        }
+        Some(_) => {}
+        None => {
+            fields.remove(key);
+        }
+    }
+}
+
+/// Claude's `name` and any field it doesn't model go to its extension
+pub fn claude_to_canonical(server: &ClaudeMCPServer) -> Result<McpServer, String> {
+    let transport = parse_type("Claude", server.server_type.as_deref(), server.url.as_ref())?;
+    let mut ext = to_map(&server.other_fields);
+    remember_type(&mut ext, server.server_type.as_deref(), Some(transport.as_str()));
+    if let Some(name) = &server.name {
+        ext.insert("name".to_string(), Value::from(name.clone()));
+    }
+
+    let mut canonical = McpServer {
+        transport,
+        command: server.command.clone(),
+        args: server.args.clone(),
+        env: server.env.clone(),
+        url: server.url.clone(),
+        headers: server.headers.clone(),
+        enabled: server.is_active,
+        ..Default::default()
     };
+    canonical.set_extension(SourceKind::Claude.as_str(), ext);
+    Ok(canonical)
+}
 
-    OpenCodeMCPServer {
+pub fn canonical_to_claude(server: &McpServer) -> ClaudeMCPServer {
+    let mut ext = server.extension(SourceKind::Claude.as_str());
+    let server_type = written_type(&mut ext, server.transport, server.url.as_ref(), Some(server.transport.as_str()));
+    let name = take(&mut ext, "name");
+
+    ClaudeMCPServer {
+        is_active: server.enabled,
+        name,
         server_type,
-        command,
-        environment: server.env.clone(),
-        enabled: Some(server.is_active.unwrap_or(true)),
+        command: server.command.clone(),
+        args: server.args.clone(),
+        env: server.env.clone(),
         url: server.url.clone(),
         headers: server.headers.clone(),
+        other_fields: to_fields(ext),
     }
 }
 
-#[allow(dead_code)]
-pub fn opencode_to_claude(
-    server: &OpenCodeMCPServer,
-    target_platform: Platform,
-) -> ClaudeMCPServer {
-    let server_type = match server.server_type.as_str() {
-        "local" => Some("stdio".to_string()),
-        "remote" => Some("http".to_string()),
-        other => Some(other.to_string()),
+/// OpenCode's `remote` covers both SSE and streamable HTTP (it tries one, then the other); it is read as HTTP
+pub fn opencode_to_canonical(server: &OpenCodeMCPServer) -> Result<McpServer, String> {
+    let transport = match server.server_type.as_str() {
+        "local" => Transport::Stdio,
+        "remote" => Transport::Http,
+        other => return Err(format!("Unsupported OpenCode server type '{}'", other)),
     };
 
-    let (command, args) = if let Some(cmd_array) = &server.command {
-        if cmd_array.is_empty() {
-            (None, None)
-        } else {
-            let adapted = adapt_command_for_platform(cmd_array.clone(), target_platform);
-            if let Some(full_cmd) = adapted {
-                if full_cmd.len() > 1 {
-                    (Some(full_cmd[0].clone()), Some(full_cmd[1..].to_vec()))
-                } else {
-                    (Some(full_cmd[0].clone()), None)
-                }
-            } else {
-                (None, None)
+    let mut canonical = McpServer {
+        transport,
+        env: server.environment.clone(),
+        url: server.url.clone(),
+        headers: server.headers.clone(),
+        enabled: server.enabled,
+        startup_timeout_ms: server.timeout,
+        oauth: server.oauth.as_ref().map(opencode_oauth_to_canonical).transpose()?,
+        ..Default::default()
+    };
+    if let Some(command_line) = server.command.clone().filter(|c| !c.is_empty()) {
+        canonical.set_command_line(command_line);
+    }
+    canonical.set_extension(SourceKind::OpenCode.as_str(), to_map(&server.other_fields));
+    Ok(canonical)
+}
+
+pub fn canonical_to_opencode(server: &McpServer) -> OpenCodeMCPServer {
+    let remote = server.transport.is_remote();
+    OpenCodeMCPServer {
+        server_type: if remote { "remote" } else { "local" }.to_string(),
+        command: if remote { None } else { server.command_line() },
+        environment: server.env.clone(),
+        enabled: server.enabled,
+        url: server.url.clone(),
+        headers: server.headers.clone(),
+        timeout: server.startup_timeout_ms,
+        oauth: server.oauth.as_ref().map(canonical_oauth_to_opencode),
+        other_fields: to_fields(server.extension(SourceKind::OpenCode.as_str())),
+    }
+}
+
+/// OpenCode writes `false` to turn OAuth off, and scopes as one space-separated `scope`
+fn opencode_oauth_to_canonical(oauth: &Value) -> Result<OAuthConfig, String> {
+    match oauth {
+        Value::Bool(enabled) => Ok(OAuthConfig { enabled: Some(*enabled), ..Default::default() }),
+        Value::Object(fields) => {
+            let mut fields = fields.clone();
+            let scope = fields.remove("scope");
+            let mut config: OAuthConfig = from_json(&Value::Object(fields))?;
+            if let Some(scope) = scope.as_ref().and_then(Value::as_str) {
+                config.scopes = Some(scope.split(' ').map(str::to_string).collect());
             }
+            Ok(config)
         }
-    } else {
-        (None, None)
+        other => Err(format!("Invalid OpenCode oauth setting: {}", other)),
+    }
+}
+
+fn canonical_oauth_to_opencode(oauth: &OAuthConfig) -> Value {
+    let only_switch = oauth.client_id.is_none()
+        && oauth.client_secret.is_none()
+        && oauth.scopes.is_none()
+        && oauth.other_fields.is_empty();
+    if let (true, Some(enabled)) = (only_switch, oauth.enabled) {
+        return Value::Bool(enabled);
+    }
+
+    let mut fields = to_map(&oauth.other_fields);
+    if let Some(client_id) = &oauth.client_id {
+        fields.insert("clientId".to_string(), Value::from(client_id.clone()));
+    }
+    if let Some(client_secret) = &oauth.client_secret {
+        fields.insert("clientSecret".to_string(), Value::from(client_secret.clone()));
+    }
+    if let Some(scopes) = &oauth.scopes {
+        fields.insert("scope".to_string(), Value::from(scopes.join(" ")));
+    }
+    Value::Object(fields)
+}
+
+pub fn claude_desktop_to_canonical(server: &ClaudeDesktopMCPServer) -> McpServer {
+    let mut canonical = McpServer {
+        transport: Transport::Stdio,
+        command: Some(server.command.clone()),
+        args: Some(server.args.clone()).filter(|args| !args.is_empty()),
+        env: server.env.clone(),
+        ..Default::default()
     };
+    canonical.set_extension(SourceKind::ClaudeDesktop.as_str(), to_map(&server.other_fields));
+    canonical
+}
 
-    ClaudeMCPServer {
-        is_active: server.enabled,
-        name: None,
-        server_type,
+/// Claude Desktop's config file only launches stdio servers, so remote servers can't be converted.
+pub fn canonical_to_claude_desktop(server: &McpServer) -> Result<ClaudeDesktopMCPServer, String> {
+    if server.transport.is_remote() {
+        return Err(format!("Claude Desktop does not support {} servers in its config file", server.transport.as_str()));
+    }
+    let command = server
+        .command
+        .clone()
+        .ok_or_else(|| "Server has no command to run".to_string())?;
+
+    Ok(ClaudeDesktopMCPServer {
         command,
-        args,
-        env: server.environment.clone(),
+        args: server.args.clone().unwrap_or_default(),
+        env: server.env.clone(),
+        other_fields: to_fields(server.extension(SourceKind::ClaudeDesktop.as_str())),
+    })
+}
+
+/// `type` is only written when the URL alone would suggest another transport
+fn cursor_default_type(transport: Transport, url: Option<&String>) -> Option<&'static str> {
+    (Transport::infer(url) != transport).then(|| transport.as_str())
+}
+
+pub fn cursor_to_canonical(server: &CursorMCPServer) -> Result<McpServer, String> {
+    let transport = parse_type("Cursor", server.server_type.as_deref(), server.url.as_ref())?;
+    let mut ext = to_map(&server.other_fields);
+    remember_type(&mut ext, server.server_type.as_deref(), cursor_default_type(transport, server.url.as_ref()));
+
+    let mut canonical = McpServer {
+        transport,
+        command: server.command.clone(),
+        args: server.args.clone(),
+        env: server.env.clone(),
+        url: server.url.clone(),
+        headers: server.headers.clone(),
+        ..Default::default()
+    };
+    canonical.set_extension(SourceKind::Cursor.as_str(), ext);
+    Ok(canonical)
+}
+
+pub fn canonical_to_cursor(server: &McpServer) -> CursorMCPServer {
+    let mut ext = server.extension(SourceKind::Cursor.as_str());
+    let default = cursor_default_type(server.transport, server.url.as_ref());
+    CursorMCPServer {
+        server_type: written_type(&mut ext, server.transport, server.url.as_ref(), default),
+        command: server.command.clone(),
+        args: server.args.clone(),
+        env: server.env.clone(),
         url: server.url.clone(),
         headers: server.headers.clone(),
+        other_fields: to_fields(ext),
     }
 }
 
-#[allow(dead_code)]
-fn adapt_command_for_platform(mut command: Vec<String>, target: Platform) -> Option<Vec<String>> {
+/// `envFile` has no equivalent elsewhere and goes to VS Code's extension
+pub fn vscode_to_canonical(server: &VsCodeMCPServer) -> Result<McpServer, String> {
+    let transport = parse_type("VS Code", server.server_type.as_deref(), server.url.as_ref())?;
+    let mut ext = to_map(&server.other_fields);
+    remember_type(&mut ext, server.server_type.as_deref(), Some(transport.as_str()));
+    if let Some(env_file) = &server.env_file {
+        ext.insert("envFile".to_string(), Value::from(env_file.clone()));
+    }
+
+    let mut canonical = McpServer {
+        transport,
+        command: server.command.clone(),
+        args: server.args.clone(),
+        env: server.env.clone(),
+        url: server.url.clone(),
+        headers: server.headers.clone(),
+        ..Default::default()
+    };
+    canonical.set_extension(SourceKind::VsCode.as_str(), ext);
+    Ok(canonical)
+}
+
+pub fn canonical_to_vscode(server: &McpServer) -> VsCodeMCPServer {
+    let mut ext = server.extension(SourceKind::VsCode.as_str());
+    VsCodeMCPServer {
+        server_type: written_type(&mut ext, server.transport, server.url.as_ref(), Some(server.transport.as_str())),
+        command: server.command.clone(),
+        args: server.args.clone(),
+        env: server.env.clone(),
+        env_file: take(&mut ext, "envFile"),
+        url: server.url.clone(),
+        headers: server.headers.clone(),
+        other_fields: to_fields(ext),
+    }
+}
+
+/// Codex only speaks streamable HTTP to remote servers. `cwd` and the `*_timeout_sec` keys map to the
+/// canonical fields; tool filters, `bearer_token_env_var` and the rest go to Codex's extension.
+pub fn codex_to_canonical(server: &CodexMCPServer) -> McpServer {
+    let mut ext = to_map(&server.other_fields);
+    let mut canonical = McpServer {
+        transport: Transport::infer(server.url.as_ref()),
+        command: server.command.clone(),
+        args: server.args.clone(),
+        env: server.env.clone(),
+        cwd: take(&mut ext, "cwd"),
+        url: server.url.clone(),
+        headers: server.http_headers.clone(),
+        enabled: server.enabled,
+        startup_timeout_ms: take_seconds(&mut ext, "startup_timeout_sec"),
+        tool_timeout_ms: take_seconds(&mut ext, "tool_timeout_sec"),
+        ..Default::default()
+    };
+    canonical.set_extension(SourceKind::Codex.as_str(), ext);
+    canonical
+}
+
+pub fn canonical_to_codex(server: &McpServer) -> CodexMCPServer {
+    let mut other = server.extension(SourceKind::Codex.as_str());
+    if let Some(cwd) = &server.cwd {
+        other.insert("cwd".to_string(), Value::from(cwd.clone()));
+    }
+    put_seconds(&mut other, "startup_timeout_sec", server.startup_timeout_ms);
+    put_seconds(&mut other, "tool_timeout_sec", server.tool_timeout_ms);
+
+    CodexMCPServer {
+        command: server.command.clone(),
+        args: server.args.clone(),
+        env: server.env.clone(),
+        url: server.url.clone(),
+        http_headers: server.headers.clone(),
+        enabled: server.enabled,
+        other_fields: to_fields(other),
+    }
+}
+
+/// Gemini picks the transport by field name: `url` is SSE, `httpUrl` streamable HTTP. Its `timeout` is per
+/// request (a tool call); `trust` and the tool filters go to Gemini's extension.
+pub fn gemini_to_canonical(server: &GeminiMCPServer) -> McpServer {
+    let mut ext = to_map(&server.other_fields);
+    let (transport, url) = match (&server.http_url, &server.url) {
+        (Some(http_url), url) => {
+            if let Some(url) = url {
+                ext.insert("url".to_string(), Value::from(url.clone()));
+            }
+            (Transport::Http, Some(http_url.clone()))
+        }
+        (None, Some(url)) => (Transport::Sse, Some(url.clone())),
+        (None, None) => (Transport::Stdio, None),
+    };
+    if let Some(trust) = server.trust {
+        ext.insert("trust".to_string(), Value::from(trust));
+    }
+    if let Some(tools) = &server.include_tools {
+        ext.insert("includeTools".to_string(), Value::from(tools.clone()));
+    }
+    if let Some(tools) = &server.exclude_tools {
+        ext.insert("excludeTools".to_string(), Value::from(tools.clone()));
+    }
+
+    let mut canonical = McpServer {
+        transport,
+        command: server.command.clone(),
+        args: server.args.clone(),
+        env: server.env.clone(),
+        cwd: server.cwd.clone(),
+        url,
+        headers: server.headers.clone(),
+        tool_timeout_ms: server.timeout,
+        oauth: take(&mut ext, "oauth"),
+        ..Default::default()
+    };
+    canonical.set_extension(SourceKind::Gemini.as_str(), ext);
+    canonical
+}
+
+pub fn canonical_to_gemini(server: &McpServer) -> GeminiMCPServer {
+    let mut ext = server.extension(SourceKind::Gemini.as_str());
+    let (url, http_url) = match server.transport {
+        Transport::Sse => (server.url.clone(), None),
+        Transport::Http => (take(&mut ext, "url"), server.url.clone()),
+        Transport::Stdio => (None, None),
+    };
+    if let Some(oauth) = &server.oauth {
+        ext.insert("oauth".to_string(), serde_json::to_value(oauth).unwrap_or_default());
+    }
+
+    GeminiMCPServer {
+        command: server.command.clone(),
+        args: server.args.clone(),
+        env: server.env.clone(),
+        cwd: server.cwd.clone(),
+        url,
+        http_url,
+        headers: server.headers.clone(),
+        timeout: server.tool_timeout_ms,
+        trust: take(&mut ext, "trust"),
+        include_tools: take(&mut ext, "includeTools"),
+        exclude_tools: take(&mut ext, "excludeTools"),
+        other_fields: to_fields(ext),
+    }
+}
+
+/// Convert a server between two sources' own models through the canonical model
+pub fn convert<F: Serialize, T: DeserializeOwned>(
+    from: SourceKind,
+    server: &F,
+    to: SourceKind,
+    target_platform: Platform,
+) -> Result<T, String> {
+    let canonical = from.source().to_canonical(&to_json(server)?, target_platform)?;
+    from_json(&to.source().to_native(&canonical, target_platform)?)
+}
+
+/// Fields of a `source` server the `target` client won't see, so callers can report them instead of dropping
+/// them silently. They are left out of the target's file; the app keeps them (see `db::carried_fields`) so
+/// copying the server back restores them. Found by converting to the target's format and back, so new fields
+/// in any model are covered without a list to maintain; named as the source writes them. Commands aren't
+/// compared: they are re-targeted for the platform, never dropped.
+pub fn lost_fields(
+    source: SourceKind,
+    native: &Value,
+    target: SourceKind,
+    target_platform: Platform,
+) -> Result<Vec<String>, String> {
+    let (source, target) = (source.source(), target.source());
+    let canonical = source.to_canonical(native, target_platform)?;
+    let converted = target.format_server(&canonical)?;
+    let back = source.format_server(&target.parse_server(&converted)?)?;
+
+    let mut lost = changed_fields(native, &back);
+    lost.sort();
+    Ok(lost)
+}
+
+/// Top-level fields set in `before` that are missing or different in `after`
+fn changed_fields(before: &Value, after: &Value) -> Vec<String> {
+    let Some(fields) = before.as_object() else {
+        return Vec::new();
+    };
+    fields
+        .iter()
+        .filter(|(k, v)| !v.is_null() && !matches!(k.as_str(), "command" | "args"))
+        .filter(|(k, v)| after.get(k.as_str()) != Some(v))
+        .map(|(k, _)| k.clone())
+        .collect()
+}
+
+pub(crate) fn adapt_command_for_platform(mut command: Vec<String>, target: Platform) -> Option<Vec<String>> {
     if command.is_empty() {
         return None;
     }
@@ -139,6 +517,8 @@ fn adapt_command_for_platform(mut command: Vec<String>, target: Platform) -> Opt
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::canonical::unrepresented_fields;
+    use std::collections::HashMap;
 
     #[test]
     fn test_claude_to_opencode_stdio() {
@@ -150,7 +530,7 @@ mod tests {
             ..Default::default()
         };
 
-        let opencode = claude_to_opencode(&claude, Platform::Linux);
+        let opencode: OpenCodeMCPServer = convert(SourceKind::Claude, &claude, SourceKind::OpenCode, Platform::Linux).unwrap();
 
         assert_eq!(opencode.server_type, "local");
         assert_eq!(opencode.enabled, Some(true));
@@ -177,7 +557,7 @@ mod tests {
             ..Default::default()
         };
 
-        let claude = opencode_to_claude(&opencode, Platform::Linux);
+        let claude: ClaudeMCPServer = convert(SourceKind::OpenCode, &opencode, SourceKind::Claude, Platform::Linux).unwrap();
 
         assert_eq!(claude.server_type, Some("stdio".to_string()));
         assert_eq!(claude.is_active, Some(true));
@@ -186,6 +566,97 @@ mod tests {
             claude.args,
             Some(vec!["-y".to_string(), "@pkg/test".to_string()])
         );
+
+        let unknown = OpenCodeMCPServer { server_type: "websocket".to_string(), ..opencode };
+        assert!(convert::<_, ClaudeMCPServer>(SourceKind::OpenCode, &unknown, SourceKind::Claude, Platform::Linux).is_err());
+    }
+
+    #[test]
+    fn test_claude_opencode_round_trip_is_lossless() {
+        let claude_source = SourceKind::Claude.source();
+        let opencode_source = SourceKind::OpenCode.source();
+        let samples = [
+            serde_json::json!({
+                "type": "sse",
+                "name": "Docs",
+                "isActive": false,
+                "url": "https://mcp.example.com/sse",
+                "headers": {"Authorization": "Bearer x"}
+            }),
+            serde_json::json!({
+                "type": "stdio",
+                "command": "npx",
+                "args": ["-y", "@pkg/test"],
+                "env": {"TOKEN": "x"}
+            }),
+            serde_json::json!({"url": "https://mcp.example.com/mcp", "alwaysAllow": ["search"]}),
+        ];
+
+        for claude in samples {
+            let canonical = claude_source.to_canonical(&claude, Platform::Linux).unwrap();
+            let opencode = opencode_source.to_native(&canonical, Platform::Linux).unwrap();
+            // The OpenCode entry holds only its own keys; what it can't hold is kept by the app
+            assert_eq!(opencode, opencode_source.format_server(&canonical).unwrap());
+            let carried = unrepresented_fields(opencode_source, &canonical, &opencode).unwrap();
+            let canonical_back = opencode_source.to_canonical_carried(&opencode, Some(&carried), Platform::Linux).unwrap();
+            assert_eq!(canonical_back, canonical);
+            assert_eq!(claude_source.to_native(&canonical_back, Platform::Linux).unwrap(), claude);
+        }
+    }
+
+    #[test]
+    fn test_carried_transport_gives_way_to_hand_edits() {
+        let sse = serde_json::json!({"type": "sse", "url": "https://mcp.example.com/sse"});
+        let canonical = SourceKind::Claude.source().to_canonical(&sse, Platform::Linux).unwrap();
+        let mut opencode = SourceKind::OpenCode.source().to_native(&canonical, Platform::Linux).unwrap();
+        assert_eq!(opencode["type"], "remote");
+        let carried = unrepresented_fields(SourceKind::OpenCode.source(), &canonical, &opencode).unwrap();
+        assert_eq!(carried["transport"], "sse");
+        let restored = SourceKind::OpenCode.source().to_canonical_carried(&opencode, Some(&carried), Platform::Linux).unwrap();
+        assert_eq!(restored.transport, Transport::Sse);
+
+        opencode["type"] = Value::from("local");
+        opencode["command"] = serde_json::json!(["mcp-docs"]);
+        let edited = SourceKind::OpenCode.source().to_canonical_carried(&opencode, Some(&carried), Platform::Linux).unwrap();
+        assert_eq!(edited.transport, Transport::Stdio);
+        assert_eq!(edited.command.as_deref(), Some("mcp-docs"));
+    }
+
+    #[test]
+    fn test_every_source_round_trips_its_own_entries() {
+        let samples = [
+            (SourceKind::Claude, serde_json::json!({"type": "streamable-http", "url": "https://a.example.com/mcp", "name": "A"})),
+            (SourceKind::ClaudeDesktop, serde_json::json!({"command": "npx", "args": ["-y", "pkg"], "env": {"K": "v"}, "note": "x"})),
+            (
+                SourceKind::Codex,
+                serde_json::json!({"command": "npx", "cwd": "/srv", "startup_timeout_sec": 10.0, "tool_timeout_sec": 1.5, "enabled_tools": ["a"]}),
+            ),
+            (SourceKind::Cursor, serde_json::json!({"type": "sse", "url": "https://a.example.com/sse", "auth": {"CLIENT_ID": "x"}})),
+            (
+                SourceKind::Gemini,
+                serde_json::json!({"httpUrl": "https://a.example.com/mcp", "url": "https://a.example.com/sse", "timeout": 500, "oauth": {"enabled": true, "scopes": ["read"]}}),
+            ),
+            (
+                SourceKind::OpenCode,
+                serde_json::json!({"type": "remote", "url": "https://a.example.com/mcp", "timeout": 5000, "oauth": {"clientId": "c", "scope": "read write"}}),
+            ),
+            (SourceKind::OpenCode, serde_json::json!({"type": "local", "command": ["uvx", "mcp-git"], "oauth": false})),
+            (SourceKind::VsCode, serde_json::json!({"type": "stdio", "command": "uvx", "envFile": "${workspaceFolder}/.env", "dev": {}})),
+        ];
+
+        for (kind, native) in samples {
+            let source = kind.source();
+            let canonical = source.to_canonical(&native, Platform::Linux).unwrap();
+            assert_eq!(source.to_native(&canonical, Platform::Linux).unwrap(), native, "{}", kind.as_str());
+        }
+
+        let codex = codex_to_canonical(&serde_json::from_value(serde_json::json!({"command": "x", "tool_timeout_sec": 1.5})).unwrap());
+        assert_eq!(codex.tool_timeout_ms, Some(1500));
+        let opencode = SourceKind::OpenCode
+            .source()
+            .parse_server(&serde_json::json!({"type": "remote", "url": "u", "oauth": {"scope": "read write"}}))
+            .unwrap();
+        assert_eq!(opencode.oauth.unwrap().scopes, Some(vec!["read".to_string(), "write".to_string()]));
     }
 
     #[test]
@@ -222,6 +693,150 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_platform_adaptation_keeps_native_command() {
+        let cmd = vec!["ls".to_string(), "-la".to_string()];
+        assert_eq!(adapt_command_for_platform(cmd.clone(), Platform::Linux), Some(cmd));
+    }
+
+    #[test]
+    fn test_adapt_claude_for_platform_round_trip() {
+        let linux = McpServer {
+            command: Some("npx".to_string()),
+            args: Some(vec!["-y".to_string(), "@pkg/test".to_string()]),
+            ..Default::default()
+        };
+
+        let windows = linux.adapted_for(Platform::Windows);
+        assert_eq!(windows.command, Some("cmd".to_string()));
+        assert_eq!(
+            windows.args,
+            Some(vec![
+                "/c".to_string(),
+                "npx".to_string(),
+                "-y".to_string(),
+                "@pkg/test".to_string()
+            ])
+        );
+
+        let back = windows.adapted_for(Platform::Linux);
+        assert_eq!(back.command, linux.command);
+        assert_eq!(back.args, linux.args);
+
+        let native = serde_json::json!({"command": "npx", "args": ["-y", "@pkg/test"]});
+        let adapted = SourceKind::Claude.source().adapt_for_platform(&native, Platform::Windows).unwrap();
+        assert_eq!(adapted["command"], "cmd");
+        assert!(adapted.get("type").is_none());
+    }
+
+    #[test]
+    fn test_claude_desktop_conversions() {
+        let desktop: ClaudeDesktopMCPServer = serde_json::from_str(
+            r#"{"command": "npx", "args": ["-y", "@pkg/test"], "env": {"TOKEN": "x"}}"#,
+        )
+        .unwrap();
+
+        let canonical = claude_desktop_to_canonical(&desktop);
+        assert_eq!(canonical.transport, Transport::Stdio);
+        assert_eq!(canonical.args, Some(vec!["-y".to_string(), "@pkg/test".to_string()]));
+
+        let windows = canonical_to_claude_desktop(&canonical.adapted_for(Platform::Windows)).unwrap();
+        assert_eq!(windows.command, "cmd");
+        assert_eq!(windows.args[..2], ["/c".to_string(), "npx".to_string()]);
+        assert_eq!(windows.env, desktop.env);
+
+        // Converting an already adapted server doesn't wrap `cmd /c` twice
+        let back = canonical_to_claude_desktop(&claude_desktop_to_canonical(&windows).adapted_for(Platform::Windows)).unwrap();
+        assert_eq!(back.args, windows.args);
+
+        let remote = McpServer {
+            transport: Transport::Http,
+            url: Some("https://example.com/mcp".to_string()),
+            ..Default::default()
+        };
+        assert!(canonical_to_claude_desktop(&remote).is_err());
+    }
+
+    #[test]
+    fn test_cursor_and_vscode_conversions() {
+        let cursor: CursorMCPServer = serde_json::from_str(r#"{"url": "https://mcp.example.com/sse"}"#).unwrap();
+        let canonical = cursor_to_canonical(&cursor).unwrap();
+        assert_eq!(canonical.transport, Transport::Http);
+
+        let vscode = canonical_to_vscode(&canonical);
+        assert_eq!(vscode.server_type.as_deref(), Some("http"));
+        assert_eq!(vscode.url, cursor.url);
+
+        let local = McpServer {
+            command: Some("uvx".to_string()),
+            args: Some(vec!["mcp-server-git".to_string()]),
+            ..Default::default()
+        };
+        let vscode = canonical_to_vscode(&local.adapted_for(Platform::Windows));
+        assert_eq!(vscode.server_type.as_deref(), Some("stdio"));
+        assert_eq!(vscode.command.as_deref(), Some("cmd"));
+
+        let cursor = canonical_to_cursor(&vscode_to_canonical(&vscode).unwrap().adapted_for(Platform::Linux));
+        assert!(cursor.server_type.is_none());
+        assert_eq!(cursor.command.as_deref(), Some("uvx"));
+        assert_eq!(cursor.args, local.args);
+    }
+
+    #[test]
+    fn test_codex_conversions() {
+        let canonical = McpServer {
+            enabled: Some(false),
+            command: Some("npx".to_string()),
+            args: Some(vec!["-y".to_string(), "@pkg/test".to_string()]),
+            env: Some(HashMap::from([("KEY".to_string(), "v".to_string())])),
+            startup_timeout_ms: Some(20_000),
+            ..Default::default()
+        };
+
+        let codex = canonical_to_codex(&canonical);
+        assert_eq!(codex.enabled, Some(false));
+        assert_eq!(codex.env, canonical.env);
+        assert_eq!(codex.other_fields["startup_timeout_sec"], 20);
+
+        let back = codex_to_canonical(&codex);
+        assert_eq!(back, canonical);
+    }
+
+    #[test]
+    fn test_gemini_conversions_report_lost_fields() {
+        let gemini = serde_json::json!({
+            "httpUrl": "https://mcp.example.com/mcp",
+            "headers": {"Authorization": "Bearer x"},
+            "timeout": 30000,
+            "trust": true,
+            "includeTools": ["search"],
+            "description": "docs search"
+        });
+
+        let canonical = SourceKind::Gemini.source().to_canonical(&gemini, Platform::Linux).unwrap();
+        assert_eq!(canonical.transport, Transport::Http);
+        assert_eq!(canonical.url.as_deref(), Some("https://mcp.example.com/mcp"));
+
+        let lost = lost_fields(SourceKind::Gemini, &gemini, SourceKind::Claude, Platform::Linux).unwrap();
+        assert_eq!(lost, vec!["description", "includeTools", "timeout", "trust"]);
+
+        // sse and http are distinct in Gemini and Claude but both "remote" in OpenCode
+        let sse = serde_json::json!({"type": "sse", "url": "https://mcp.example.com/sse"});
+        assert!(lost_fields(SourceKind::Claude, &sse, SourceKind::Gemini, Platform::Linux).unwrap().is_empty());
+        assert_eq!(
+            lost_fields(SourceKind::Claude, &sse, SourceKind::OpenCode, Platform::Linux).unwrap(),
+            vec!["type"]
+        );
+
+        // A disabled server can't stay disabled in Claude Desktop
+        let disabled = serde_json::json!({"command": "npx", "args": ["-y", "pkg"], "isActive": false});
+        assert_eq!(
+            lost_fields(SourceKind::Claude, &disabled, SourceKind::ClaudeDesktop, Platform::Linux).unwrap(),
+            vec!["isActive"]
+        );
+        assert!(lost_fields(SourceKind::Claude, &disabled, SourceKind::Codex, Platform::Windows).unwrap().is_empty());
+    }
+
     #[test]
     fn test_platform_from_str() {
         assert_eq!(Platform::from_str("linux"), Ok(Platform::Linux));
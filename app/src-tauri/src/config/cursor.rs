@@ -0,0 +1,109 @@
+use super::converter;
+use super::json_edit::PreservingDocument;
+use super::source::{from_json, json_pointer, servers_to_json, to_json, to_pretty_json, ConfigSource, InstallCheck, RemoteLocation, SourceDocument};
+use super::{McpServer, SourceKind};
+use crate::paths::AppPaths;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// A server in Cursor's `~/.cursor/mcp.json` (or a project's `.cursor/mcp.json`). Local servers have a
+/// `command`, remote ones a `url`; `type` is optional and usually left out.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CursorMCPServer {
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub server_type: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<HashMap<String, String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headers: Option<HashMap<String, String>>,
+
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CursorConfig {
+    #[serde(rename = "mcpServers", default)]
+    pub mcp_servers: HashMap<String, CursorMCPServer>,
+
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, serde_json::Value>,
+}
+
+pub struct CursorSource;
+
+impl ConfigSource for CursorSource {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Cursor
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Cursor"
+    }
+
+    fn local_path(&self, paths: &AppPaths) -> PathBuf {
+        paths.cursor_path.clone()
+    }
+
+    fn remote_location(&self) -> RemoteLocation {
+        // Same location on every platform
+        RemoteLocation::single("~/.cursor/mcp.json")
+    }
+
+    fn install_check(&self) -> InstallCheck {
+        InstallCheck::Cli("cursor")
+    }
+
+    fn parse(&self, _path: &str, content: Option<&str>) -> Result<Box<dyn SourceDocument>, String> {
+        let config = match content {
+            Some(c) => serde_json::from_str(c).map_err(|e| format!("Failed to parse Cursor config: {}", e))?,
+            None => CursorConfig::default(),
+        };
+        Ok(Box::new(PreservingDocument::new(config, content)))
+    }
+
+    fn parse_server(&self, server: &Value) -> Result<McpServer, String> {
+        converter::cursor_to_canonical(&from_json(server)?)
+    }
+
+    fn format_server(&self, server: &McpServer) -> Result<Value, String> {
+        to_json(&converter::canonical_to_cursor(server))
+    }
+}
+
+/// Cursor keeps enabled state in its own UI, not in this file
+impl SourceDocument for CursorConfig {
+    fn servers(&self) -> Result<HashMap<String, Value>, String> {
+        servers_to_json(&self.mcp_servers)
+    }
+
+    fn server_pointer(&self, name: &str) -> String {
+        json_pointer(&["mcpServers", name])
+    }
+
+    fn put_server(&mut self, name: &str, server: Value) -> Result<(), String> {
+        self.mcp_servers.insert(name.to_string(), from_json(&server)?);
+        Ok(())
+    }
+
+    fn remove_server(&mut self, name: &str) -> bool {
+        self.mcp_servers.remove(name).is_some()
+    }
+
+    fn serialize(&self) -> Result<String, String> {
+        to_pretty_json(self)
+    }
+}
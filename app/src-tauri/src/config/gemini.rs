@@ -0,0 +1,163 @@
+use super::jsonc::strip_jsonc;
+use super::converter;
+use super::json_edit::PreservingDocument;
+use super::source::{from_json, json_pointer, servers_to_json, to_json, to_pretty_json, ConfigSource, InstallCheck, RemoteLocation, SourceDocument};
+use super::{McpServer, SourceKind};
+use crate::paths::AppPaths;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// A server in Gemini CLI's `~/.gemini/settings.json`. The transport follows from which of
+/// `command` (stdio), `url` (SSE) or `httpUrl` (streamable HTTP) is set.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GeminiMCPServer {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<HashMap<String, String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(rename = "httpUrl", skip_serializing_if = "Option::is_none")]
+    pub http_url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headers: Option<HashMap<String, String>>,
+
+    /// Request timeout in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+
+    /// Skip tool call confirmations for this server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trust: Option<bool>,
+
+    #[serde(rename = "includeTools", skip_serializing_if = "Option::is_none")]
+    pub include_tools: Option<Vec<String>>,
+
+    #[serde(rename = "excludeTools", skip_serializing_if = "Option::is_none")]
+    pub exclude_tools: Option<Vec<String>>,
+
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GeminiConfig {
+    #[serde(rename = "mcpServers", default)]
+    pub mcp_servers: HashMap<String, GeminiMCPServer>,
+
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, serde_json::Value>,
+}
+
+impl GeminiConfig {
+    /// Parse `settings.json` (`None` for a file that doesn't exist yet). Gemini CLI allows comments in it.
+    pub fn parse(content: Option<&str>) -> Result<Self, String> {
+        match content.map(strip_jsonc).filter(|c| !c.trim().is_empty()) {
+            Some(c) => serde_json::from_str(&c).map_err(|e| format!("Failed to parse Gemini settings: {}", e)),
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+pub struct GeminiSource;
+
+impl ConfigSource for GeminiSource {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Gemini
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Gemini CLI"
+    }
+
+    fn local_path(&self, paths: &AppPaths) -> PathBuf {
+        paths.gemini_path.clone()
+    }
+
+    fn remote_location(&self) -> RemoteLocation {
+        // %USERPROFILE%\.gemini on Windows too
+        RemoteLocation::single("~/.gemini/settings.json")
+    }
+
+    fn install_check(&self) -> InstallCheck {
+        InstallCheck::Cli("gemini")
+    }
+
+    fn parse(&self, _path: &str, content: Option<&str>) -> Result<Box<dyn SourceDocument>, String> {
+        Ok(Box::new(PreservingDocument::new(GeminiConfig::parse(content)?, content)))
+    }
+
+    fn parse_server(&self, server: &Value) -> Result<McpServer, String> {
+        Ok(converter::gemini_to_canonical(&from_json(server)?))
+    }
+
+    fn format_server(&self, server: &McpServer) -> Result<Value, String> {
+        to_json(&converter::canonical_to_gemini(server))
+    }
+}
+
+/// Gemini has no per-server enabled flag in settings.json
+impl SourceDocument for GeminiConfig {
+    fn servers(&self) -> Result<HashMap<String, Value>, String> {
+        servers_to_json(&self.mcp_servers)
+    }
+
+    fn server_pointer(&self, name: &str) -> String {
+        json_pointer(&["mcpServers", name])
+    }
+
+    fn put_server(&mut self, name: &str, server: Value) -> Result<(), String> {
+        let server: GeminiMCPServer = from_json(&server)?;
+        if server.command.is_none() && server.url.is_none() && server.http_url.is_none() {
+            return Err("Gemini servers need a command, url or httpUrl".to_string());
+        }
+        self.mcp_servers.insert(name.to_string(), server);
+        Ok(())
+    }
+
+    fn remove_server(&mut self, name: &str) -> bool {
+        self.mcp_servers.remove(name).is_some()
+    }
+
+    fn serialize(&self) -> Result<String, String> {
+        to_pretty_json(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gemini_settings_keep_unknown_fields() {
+        let settings = r#"{
+            // theme and other CLI settings live next to the servers
+            "theme": "GitHub",
+            "mcpServers": {
+                "docs": {"httpUrl": "https://mcp.example.com/mcp", "timeout": 30000, "trust": true, "oauth": {"enabled": true}},
+            },
+        }"#;
+        let config = GeminiConfig::parse(Some(settings)).unwrap();
+        let docs = &config.mcp_servers["docs"];
+        assert_eq!(docs.http_url.as_deref(), Some("https://mcp.example.com/mcp"));
+        assert_eq!(docs.timeout, Some(30000));
+        assert_eq!(docs.trust, Some(true));
+
+        let out = serde_json::to_value(&config).unwrap();
+        assert_eq!(out["theme"], "GitHub");
+        assert_eq!(out["mcpServers"]["docs"]["oauth"]["enabled"], true);
+        assert!(GeminiConfig::parse(None).unwrap().mcp_servers.is_empty());
+    }
+}
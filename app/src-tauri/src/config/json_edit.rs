@@ -0,0 +1,558 @@
+use super::source::SourceDocument;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Re-serialize `config` over the file it was read from (`original`, JSON or JSONC), touching only what
+/// changed. Keys keep their order, and formatting and comments outside the edited members are left as they
+/// were, so a config kept in a dotfile repo diffs cleanly. A new file is pretty-printed. Whatever is written
+/// fresh has its keys sorted, since models backed by `HashMap`s serialize them in no particular order.
+pub fn write_json<T: Serialize>(original: Option<&str>, config: &T) -> Result<String, String> {
+    let updated = serde_json::to_value(config).map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+    match original.filter(|o| !o.trim().is_empty()) {
+        Some(original) => patch_json(original, &updated),
+        None => serde_json::to_string_pretty(&sort_keys(&updated)).map_err(|e| format!("Failed to serialize JSON: {}", e)),
+    }
+}
+
+/// `value` with the members of every object in key order
+pub fn sort_keys(value: &Value) -> Value {
+    match value {
+        Value::Object(fields) => {
+            let mut sorted: Vec<(&String, &Value)> = fields.iter().collect();
+            sorted.sort_by_key(|(key, _)| *key);
+            Value::Object(sorted.into_iter().map(|(key, value)| (key.clone(), sort_keys(value))).collect())
+        }
+        Value::Array(items) => Value::Array(items.iter().map(sort_keys).collect()),
+        _ => value.clone(),
+    }
+}
+
+/// Edit the JSON(C) text `original` so it holds `updated`. Members whose value didn't change are kept
+/// byte for byte; changed ones are rewritten in place, new ones appended to their object in its indentation.
+pub fn patch_json(original: &str, updated: &Value) -> Result<String, String> {
+    let root = Parser::new(original).parse_document()?;
+    let style = Style::detect(original);
+
+    let mut edits = Vec::new();
+    patch_node(original, &style, &root, updated, true, &mut edits);
+
+    // Edits never overlap; apply them back to front so earlier offsets stay valid
+    edits.sort_by_key(|e| std::cmp::Reverse((e.start, e.end)));
+    let mut out = original.to_string();
+    for edit in edits {
+        out.replace_range(edit.start..edit.end, &edit.text);
+    }
+    Ok(out)
+}
+
+/// A parsed source document that is written back with `write_json` over the text it was parsed from
+pub struct PreservingDocument<D> {
+    doc: D,
+    original: Option<String>,
+}
+
+impl<D: SourceDocument> PreservingDocument<D> {
+    pub fn new(doc: D, original: Option<&str>) -> Self {
+        Self { doc, original: original.map(str::to_string) }
+    }
+}
+
+impl<D: SourceDocument> SourceDocument for PreservingDocument<D> {
+    fn servers(&self) -> Result<HashMap<String, Value>, String> {
+        self.doc.servers()
+    }
+
+    fn server_pointer(&self, name: &str) -> String {
+        self.doc.server_pointer(name)
+    }
+
+    fn is_enabled(&self, name: &str) -> bool {
+        self.doc.is_enabled(name)
+    }
+
+    fn put_server(&mut self, name: &str, server: Value) -> Result<(), String> {
+        self.doc.put_server(name, server)
+    }
+
+    fn remove_server(&mut self, name: &str) -> bool {
+        self.doc.remove_server(name)
+    }
+
+    fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), String> {
+        self.doc.set_enabled(name, enabled)
+    }
+
+    fn serialize(&self) -> Result<String, String> {
+        let serialized = self.doc.serialize()?;
+        let updated: Value = serde_json::from_str(&serialized).map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+        write_json(self.original.as_deref(), &updated)
+    }
+}
+
+struct Edit {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+/// Indentation unit and line ending of the file, used for the text we write
+struct Style {
+    indent: String,
+    newline: &'static str,
+}
+
+impl Style {
+    fn detect(text: &str) -> Self {
+        let indent = text
+            .lines()
+            .map(|line| &line[..line.len() - line.trim_start().len()])
+            .find(|ws| !ws.is_empty())
+            .unwrap_or("  ")
+            .to_string();
+        let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+        Self { indent, newline }
+    }
+
+    /// `value` pretty-printed with the file's indentation, continuation lines starting at `base`
+    fn render(&self, value: &Value, base: &str, multiline: bool) -> String {
+        let value = &sort_keys(value);
+        if !multiline {
+            return value.to_string();
+        }
+        let mut buf = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(self.indent.as_bytes());
+        let mut ser = serde_json::Serializer::with_formatter(&mut buf, formatter);
+        if value.serialize(&mut ser).is_err() {
+            return value.to_string();
+        }
+        let pretty = String::from_utf8(buf).unwrap_or_else(|_| value.to_string());
+        pretty.replace('\n', &format!("{}{}", self.newline, base))
+    }
+}
+
+fn patch_node(text: &str, style: &Style, node: &Node, updated: &Value, multiline: bool, edits: &mut Vec<Edit>) {
+    if node.value == *updated {
+        return;
+    }
+    match (&node.kind, updated) {
+        (NodeKind::Object { members, close }, Value::Object(fields)) => {
+            patch_object(text, style, node, members, *close, fields, edits);
+        }
+        _ => edits.push(Edit {
+            start: node.start,
+            end: node.end,
+            text: style.render(updated, line_indent(text, node.start), multiline),
+        }),
+    }
+}
+
+fn patch_object(
+    text: &str,
+    style: &Style,
+    node: &Node,
+    members: &[Member],
+    close: usize,
+    fields: &Map<String, Value>,
+    edits: &mut Vec<Edit>,
+) {
+    let multiline = text[node.start..node.end].contains('\n');
+    let kept: Vec<&Member> = members.iter().filter(|m| fields.contains_key(&m.key)).collect();
+    let mut added: Vec<(&String, &Value)> = fields.iter().filter(|(k, _)| !members.iter().any(|m| &m.key == *k)).collect();
+    added.sort_by_key(|(key, _)| *key);
+
+    // Nothing left to anchor on: write the members fresh
+    let Some(last_kept) = kept.last() else {
+        let outer = line_indent(text, node.start);
+        let inner = format!("{}{}", outer, style.indent);
+        let body = if added.is_empty() {
+            String::new()
+        } else {
+            let rendered: Vec<String> = added.iter().map(|(k, v)| render_member(style, k, v, &inner, true)).collect();
+            let separator = format!(",{}{}", style.newline, inner);
+            format!("{nl}{inner}{}{nl}{outer}", rendered.join(&separator), nl = style.newline)
+        };
+        edits.push(Edit { start: node.start + 1, end: close, text: body });
+        return;
+    };
+
+    for member in members {
+        match fields.get(&member.key) {
+            Some(value) => patch_node(text, style, &member.value, value, multiline, edits),
+            None => edits.push(removal(text, member)),
+        }
+    }
+
+    let trailing_commas = members.last().is_some_and(|m| m.comma.is_some());
+    let last_index = members.iter().position(|m| std::ptr::eq(m, *last_kept)).unwrap_or(0);
+    let tail_removed = last_index + 1 < members.len();
+
+    if added.is_empty() {
+        // The removed members after it took the separator; drop its comma unless the file ends lists with one
+        if let (true, false, Some(comma)) = (tail_removed, trailing_commas, last_kept.comma) {
+            edits.push(Edit { start: comma, end: comma + 1, text: String::new() });
+        }
+        return;
+    }
+
+    let indent = if multiline { line_indent(text, last_kept.key_start).to_string() } else { String::new() };
+    let separator = if multiline { format!("{}{}", style.newline, indent) } else { " ".to_string() };
+    let rendered: Vec<String> = added
+        .iter()
+        .map(|(k, v)| render_member(style, k, v, &indent, multiline))
+        .collect();
+    let mut insert = format!("{}{}", separator, rendered.join(&format!(",{}", separator)));
+    if trailing_commas {
+        insert.push(',');
+    }
+
+    // New members go after the last one's line, past a comment at its end
+    let after = last_kept.comma.map(|c| c + 1).unwrap_or(last_kept.value.end);
+    let at = if multiline { end_of_line(text, after) } else { after };
+    if last_kept.comma.is_none() {
+        if at == after {
+            insert.insert(0, ',');
+        } else {
+            edits.push(Edit { start: after, end: after, text: ",".to_string() });
+        }
+    }
+    edits.push(Edit { start: at, end: at, text: insert });
+}
+
+/// End of the line `pos` is on when only whitespace or a `//` comment follows it, else `pos`
+fn end_of_line(text: &str, pos: usize) -> usize {
+    let rest_of_line = text[pos..].split('\n').next().unwrap_or("").trim_end_matches('\r');
+    let rest = rest_of_line.trim();
+    if rest.is_empty() || rest.starts_with("//") {
+        pos + rest_of_line.len()
+    } else {
+        pos
+    }
+}
+
+fn render_member(style: &Style, key: &str, value: &Value, indent: &str, multiline: bool) -> String {
+    format!("{}: {}", Value::from(key), style.render(value, indent, multiline))
+}
+
+/// Remove a member with its comma, and its whole line (with a trailing comment) when it has one to itself
+fn removal(text: &str, member: &Member) -> Edit {
+    let line_start = text[..member.key_start].rfind('\n').map(|p| p + 1).unwrap_or(0);
+    let own_line = text[line_start..member.key_start].trim().is_empty();
+    let start = if own_line { line_start } else { member.key_start };
+
+    let mut end = member.comma.map(|c| c + 1).unwrap_or(member.value.end);
+    let rest_of_line = text[end..].split('\n').next().unwrap_or("");
+    let rest = rest_of_line.trim();
+    if own_line && (rest.is_empty() || rest.starts_with("//")) {
+        end = (end + rest_of_line.len() + 1).min(text.len());
+    } else {
+        end += rest_of_line.len() - rest_of_line.trim_start().len();
+    }
+    Edit { start, end, text: String::new() }
+}
+
+/// Leading whitespace of the line `pos` is on
+fn line_indent(text: &str, pos: usize) -> &str {
+    let line_start = text[..pos].rfind('\n').map(|p| p + 1).unwrap_or(0);
+    let line = &text[line_start..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+struct Node {
+    start: usize,
+    end: usize,
+    value: Value,
+    kind: NodeKind,
+}
+
+enum NodeKind {
+    Object { members: Vec<Member>, close: usize },
+    Other,
+}
+
+struct Member {
+    key: String,
+    key_start: usize,
+    value: Node,
+    /// Position of the comma after the value, if any
+    comma: Option<usize>,
+}
+
+/// JSONC parser that keeps the byte span of every value
+struct Parser<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, bytes: text.as_bytes(), pos: 0 }
+    }
+
+    fn parse_document(mut self) -> Result<Node, String> {
+        let node = self.parse_value()?;
+        self.skip_insignificant();
+        if self.pos < self.bytes.len() {
+            return Err(self.error("Unexpected content after the JSON value"));
+        }
+        Ok(node)
+    }
+
+    fn error(&self, message: &str) -> String {
+        let line = self.text[..self.pos.min(self.text.len())].matches('\n').count() + 1;
+        format!("Failed to parse JSON: {} at line {}", message, line)
+    }
+
+    fn skip_insignificant(&mut self) {
+        while self.pos < self.bytes.len() {
+            match self.bytes[self.pos] {
+                b' ' | b'\t' | b'\r' | b'\n' => self.pos += 1,
+                b'/' if self.bytes.get(self.pos + 1) == Some(&b'/') => {
+                    while self.pos < self.bytes.len() && self.bytes[self.pos] != b'\n' {
+                        self.pos += 1;
+                    }
+                }
+                b'/' if self.bytes.get(self.pos + 1) == Some(&b'*') => {
+                    self.pos = self.text[self.pos + 2..]
+                        .find("*/")
+                        .map(|p| self.pos + 2 + p + 2)
+                        .unwrap_or(self.bytes.len());
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Node, String> {
+        self.skip_insignificant();
+        match self.bytes.get(self.pos) {
+            Some(b'{') => self.parse_object(),
+            Some(b'[') => self.parse_array(),
+            Some(b'"') => {
+                let start = self.pos;
+                self.scan_string()?;
+                self.scalar(start)
+            }
+            Some(_) => {
+                let start = self.pos;
+                while self.pos < self.bytes.len()
+                    && !matches!(self.bytes[self.pos], b',' | b'}' | b']' | b' ' | b'\t' | b'\r' | b'\n' | b'/')
+                {
+                    self.pos += 1;
+                }
+                self.scalar(start)
+            }
+            None => Err(self.error("Unexpected end of input")),
+        }
+    }
+
+    fn scalar(&self, start: usize) -> Result<Node, String> {
+        let value = serde_json::from_str(&self.text[start..self.pos]).map_err(|_| self.error("Invalid value"))?;
+        Ok(Node { start, end: self.pos, value, kind: NodeKind::Other })
+    }
+
+    fn scan_string(&mut self) -> Result<(), String> {
+        self.pos += 1;
+        while self.pos < self.bytes.len() && self.bytes[self.pos] != b'"' {
+            self.pos += if self.bytes[self.pos] == b'\\' { 2 } else { 1 };
+        }
+        if self.pos >= self.bytes.len() {
+            return Err(self.error("Unterminated string"));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// After a member or element: its comma, if there is one, and whether the container closes with `end`
+    fn separator(&mut self, end: u8) -> Result<(Option<usize>, bool), String> {
+        self.skip_insignificant();
+        let comma = (self.bytes.get(self.pos) == Some(&b',')).then_some(self.pos);
+        if comma.is_some() {
+            self.pos += 1;
+            self.skip_insignificant();
+        }
+        match self.bytes.get(self.pos) {
+            Some(&b) if b == end => Ok((comma, true)),
+            Some(_) if comma.is_some() => Ok((comma, false)),
+            _ => Err(self.error(&format!("Expected ',' or '{}'", end as char))),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Node, String> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut members = Vec::new();
+        let mut fields = Map::new();
+
+        loop {
+            self.skip_insignificant();
+            if self.bytes.get(self.pos) == Some(&b'}') {
+                break;
+            }
+            let key_start = self.pos;
+            if self.bytes.get(self.pos) != Some(&b'"') {
+                return Err(self.error("Expected a key"));
+            }
+            self.scan_string()?;
+            let key: String = serde_json::from_str(&self.text[key_start..self.pos]).map_err(|_| self.error("Invalid key"))?;
+            self.skip_insignificant();
+            if self.bytes.get(self.pos) != Some(&b':') {
+                return Err(self.error("Expected ':'"));
+            }
+            self.pos += 1;
+
+            let value = self.parse_value()?;
+            let (comma, closed) = self.separator(b'}')?;
+            fields.insert(key.clone(), value.value.clone());
+            members.push(Member { key, key_start, value, comma });
+            if closed {
+                break;
+            }
+        }
+
+        let close = self.pos;
+        self.pos += 1;
+        Ok(Node { start, end: self.pos, value: Value::Object(fields), kind: NodeKind::Object { members, close } })
+    }
+
+    fn parse_array(&mut self) -> Result<Node, String> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut items = Vec::new();
+
+        loop {
+            self.skip_insignificant();
+            if self.bytes.get(self.pos) == Some(&b']') {
+                break;
+            }
+            items.push(self.parse_value()?.value);
+            if self.separator(b']')?.1 {
+                break;
+            }
+        }
+
+        self.pos += 1;
+        Ok(Node { start, end: self.pos, value: Value::Array(items), kind: NodeKind::Other })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CLAUDE_JSON: &str = r#"{
+  "numStartups": 42,
+  "mcpServers": {
+    "zeta": {
+      "command": "npx",
+      "args": ["-y", "zeta"]
+    },
+    "alpha": {
+      "type": "http",
+      "url": "https://alpha.example.com/mcp"
+    }
+  },
+  "projects": {}
+}"#;
+
+    fn patched(original: &str, edit: impl FnOnce(&mut Value)) -> String {
+        let mut value: Value = serde_json::from_str(&crate::config::jsonc::strip_jsonc(original)).unwrap();
+        edit(&mut value);
+        let out = patch_json(original, &value).unwrap();
+        let reparsed: Value = serde_json::from_str(&crate::config::jsonc::strip_jsonc(&out)).unwrap();
+        assert_eq!(reparsed, value, "{}", out);
+        out
+    }
+
+    #[test]
+    fn test_unchanged_document_is_byte_identical() {
+        assert_eq!(patched(CLAUDE_JSON, |_| {}), CLAUDE_JSON);
+    }
+
+    #[test]
+    fn test_edit_touches_only_the_changed_member() {
+        let out = patched(CLAUDE_JSON, |v| v["mcpServers"]["alpha"]["url"] = json!("https://new.example.com/mcp"));
+        assert_eq!(out, CLAUDE_JSON.replace("https://alpha.example.com/mcp", "https://new.example.com/mcp"));
+    }
+
+    #[test]
+    fn test_add_and_remove_servers_keep_order_and_indentation() {
+        let out = patched(CLAUDE_JSON, |v| {
+            v["mcpServers"]["beta"] = json!({"command": "uvx", "args": ["beta"]});
+        });
+        assert_eq!(
+            out,
+            CLAUDE_JSON.replace(
+                "    }\n  },\n  \"projects\"",
+                "    },\n    \"beta\": {\n      \"args\": [\n        \"beta\"\n      ],\n      \"command\": \"uvx\"\n    }\n  },\n  \"projects\""
+            )
+        );
+
+        let out = patched(CLAUDE_JSON, |v| {
+            v["mcpServers"].as_object_mut().unwrap().remove("alpha");
+        });
+        assert_eq!(
+            out,
+            "{\n  \"numStartups\": 42,\n  \"mcpServers\": {\n    \"zeta\": {\n      \"command\": \"npx\",\n      \"args\": [\"-y\", \"zeta\"]\n    }\n  },\n  \"projects\": {}\n}"
+        );
+
+        let out = patched(CLAUDE_JSON, |v| {
+            v["mcpServers"].as_object_mut().unwrap().remove("zeta");
+        });
+        assert!(out.starts_with("{\n  \"numStartups\": 42,\n  \"mcpServers\": {\n    \"alpha\": {"), "{}", out);
+
+        let out = patched(CLAUDE_JSON, |v| v["mcpServers"] = json!({}));
+        assert!(out.contains("\"mcpServers\": {},"), "{}", out);
+        let out = patched(&out, |v| v["mcpServers"]["gamma"] = json!({"url": "u"}));
+        assert!(out.contains("\"mcpServers\": {\n    \"gamma\": {\n      \"url\": \"u\"\n    }\n  },"), "{}", out);
+    }
+
+    #[test]
+    fn test_new_members_are_written_in_key_order() {
+        let out = patched(CLAUDE_JSON, |v| {
+            v["mcpServers"]["omega"] = json!({"command": "o", "env": {"Z": "1", "A": "2"}});
+            v["mcpServers"]["beta"] = json!({"command": "b"});
+        });
+        let beta = out.find("\"beta\"").unwrap();
+        assert!(beta < out.find("\"omega\"").unwrap(), "{}", out);
+        assert!(out.find("\"A\"").unwrap() < out.find("\"Z\"").unwrap(), "{}", out);
+
+        let fresh = write_json(None, &json!({"b": 1, "a": {"d": 2, "c": 3}})).unwrap();
+        assert_eq!(fresh, "{\n  \"a\": {\n    \"c\": 3,\n    \"d\": 2\n  },\n  \"b\": 1\n}");
+    }
+
+    #[test]
+    fn test_jsonc_comments_and_trailing_commas_survive() {
+        let opencode = "{\n\t// my servers\n\t\"mcp\": {\n\t\t\"fs\": {\"type\": \"local\", \"command\": [\"fs\"]}, // files\n\t\t\"old\": {\"type\": \"local\", \"command\": [\"old\"]}, // to remove\n\t},\n\t/* theme */ \"theme\": \"dark\",\n}\n";
+        let out = patched(opencode, |v| {
+            v["mcp"].as_object_mut().unwrap().remove("old");
+            v["mcp"]["fs"]["enabled"] = json!(false);
+            v["mcp"]["web"] = json!({"type": "remote", "url": "u"});
+        });
+        assert!(out.contains("// my servers") && out.contains("// files") && out.contains("/* theme */"), "{}", out);
+        assert!(!out.contains("to remove"), "{}", out);
+        assert!(out.contains("\"command\": [\"fs\"], \"enabled\": false}, // files"), "{}", out);
+        assert!(out.contains("\n\t\t\"web\": {\n\t\t\t\"type\": \"remote\",\n\t\t\t\"url\": \"u\"\n\t\t},\n\t},"), "{}", out);
+    }
+
+    #[test]
+    fn test_source_documents_write_over_the_original() {
+        let source = crate::config::SourceKind::OpenCode.source();
+        let original = "{\n  // servers\n  \"mcp\": {\n    \"fs\": {\"type\": \"local\", \"command\": [\"fs\"]},\n  },\n  \"theme\": \"dark\",\n}\n";
+        let mut doc = source.parse("~/.config/opencode/opencode.jsonc", Some(original)).unwrap();
+        doc.put_server("web", json!({"type": "remote", "url": "https://mcp.example.com/mcp"})).unwrap();
+
+        let out = doc.serialize().unwrap();
+        assert!(out.starts_with("{\n  // servers\n  \"mcp\": {\n    \"fs\": {\"type\": \"local\", \"command\": [\"fs\"]},\n    \"web\": {\n      \"type\": \"remote\",\n"), "{}", out);
+        assert!(out.ends_with("  },\n  \"theme\": \"dark\",\n}\n"), "{}", out);
+    }
+
+    #[test]
+    fn test_write_json_pretty_prints_new_files() {
+        let out = write_json(None, &json!({"mcp": {}})).unwrap();
+        assert_eq!(out, "{\n  \"mcp\": {}\n}");
+        assert!(patch_json("{\"a\": 1,,}", &json!({})).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+/// Strip `//` and `/* */` comments and trailing commas so JSONC files (VS Code's settings.json) parse with
+/// serde_json. String contents are left untouched; removed comments are replaced by whitespace so error
+/// positions still point at the original text.
+pub fn strip_jsonc(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = bytes.to_vec();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    out[i] = b' ';
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = input[i + 2..].find("*/").map(|p| i + 2 + p + 2).unwrap_or(bytes.len());
+                for b in &mut out[i..end] {
+                    if *b != b'\n' {
+                        *b = b' ';
+                    }
+                }
+                i = end;
+            }
+            b',' => {
+                if matches!(next_significant(bytes, i + 1), Some(b'}') | Some(b']')) {
+                    out[i] = b' ';
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+
+    // Whole comments and single commas were blanked, so this stays valid UTF-8
+    String::from_utf8(out).unwrap_or_else(|_| input.to_string())
+}
+
+/// First byte after `from` that isn't whitespace or part of a comment
+fn next_significant(bytes: &[u8], mut from: usize) -> Option<u8> {
+    while from < bytes.len() {
+        match bytes[from] {
+            b' ' | b'\t' | b'\r' | b'\n' => from += 1,
+            b'/' if bytes.get(from + 1) == Some(&b'/') => {
+                while from < bytes.len() && bytes[from] != b'\n' {
+                    from += 1;
+                }
+            }
+            b'/' if bytes.get(from + 1) == Some(&b'*') => {
+                from += 2;
+                while from < bytes.len() && !(bytes[from] == b'*' && bytes.get(from + 1) == Some(&b'/')) {
+                    from += 1;
+                }
+                from += 2;
+            }
+            b => return Some(b),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_jsonc() {
+        let input = r#"{
+            // editor settings
+            "editor.fontSize": 14, /* inline */
+            "url": "http://example.com/*not-a-comment*/",
+            "path": "C:\\dir\\",
+            "mcp": {
+                "servers": {
+                    "fs": { "command": "npx", "args": ["-y", "fs",], }, // trailing
+                },
+            },
+        }"#;
+        let value: serde_json::Value = serde_json::from_str(&strip_jsonc(input)).unwrap();
+        assert_eq!(value["url"], "http://example.com/*not-a-comment*/");
+        assert_eq!(value["path"], "C:\\dir\\");
+        assert_eq!(value["mcp"]["servers"]["fs"]["args"][1], "fs");
+    }
+}
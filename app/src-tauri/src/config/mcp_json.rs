@@ -0,0 +1,16 @@
+use super::ClaudeMCPServer;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// File name Claude Code looks for at a project root
+pub const MCP_JSON_FILE_NAME: &str = ".mcp.json";
+
+/// A checked-in, project-scoped `.mcp.json`. Same server format as `~/.claude.json`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct McpJsonConfig {
+    #[serde(rename = "mcpServers", default)]
+    pub mcp_servers: HashMap<String, ClaudeMCPServer>,
+
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, serde_json::Value>,
+}
@@ -1,7 +1,56 @@
+pub mod canonical;
 pub mod claude;
+pub mod claude_desktop;
+pub mod codex;
 pub mod converter;
+pub mod cursor;
+pub mod gemini;
+pub mod json_edit;
+pub mod jsonc;
+pub mod mcp_json;
 pub mod opencode;
 pub mod platform_adapter;
+pub mod schema;
+pub mod source;
+pub mod vscode;
 
+pub use canonical::*;
 pub use claude::*;
+pub use claude_desktop::*;
+pub use codex::*;
+pub use cursor::*;
+pub use gemini::*;
+pub use mcp_json::*;
 pub use opencode::*;
+pub use source::*;
+pub use vscode::*;
+
+use serde::{Deserialize, Serialize};
+
+/// The client whose config file a set of servers belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Claude,
+    #[serde(rename = "claude_desktop")]
+    ClaudeDesktop,
+    Codex,
+    Cursor,
+    Gemini,
+    OpenCode,
+    VsCode,
+}
+
+impl SourceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SourceKind::Claude => "claude",
+            SourceKind::ClaudeDesktop => "claude_desktop",
+            SourceKind::Codex => "codex",
+            SourceKind::Cursor => "cursor",
+            SourceKind::Gemini => "gemini",
+            SourceKind::OpenCode => "opencode",
+            SourceKind::VsCode => "vscode",
+        }
+    }
+}
@@ -1,5 +1,13 @@
+use super::converter;
+use super::json_edit::PreservingDocument;
+use super::jsonc::strip_jsonc;
+use super::source::{from_json, json_pointer, servers_to_json, to_json, to_pretty_json, ConfigSource, InstallCheck, RemoteLocation, SourceDocument};
+use super::{McpServer, SourceKind};
+use crate::paths::AppPaths;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct OpenCodeMCPServer {
@@ -20,6 +28,17 @@ pub struct OpenCodeMCPServer {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub headers: Option<HashMap<String, String>>,
+
+    /// Milliseconds to wait for the server's tool list
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+
+    /// `false` to turn automatic OAuth off, or an object with `clientId`, `clientSecret` and `scope`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oauth: Option<serde_json::Value>,
+
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +72,15 @@ impl Default for OpenCodeConfig {
 }
 
 impl OpenCodeConfig {
+    /// Parse `opencode.json` or `opencode.jsonc` (`None` for a file that doesn't exist yet). OpenCode allows
+    /// comments and trailing commas in either.
+    pub fn parse(content: Option<&str>) -> Result<Self, String> {
+        match content.map(strip_jsonc).filter(|c| !c.trim().is_empty()) {
+            Some(c) => serde_json::from_str(&c).map_err(|e| format!("Failed to parse OpenCode config: {}", e)),
+            None => Ok(Self::default()),
+        }
+    }
+
     #[allow(dead_code)]
     pub fn get_enabled_servers(&self) -> Vec<(&String, &OpenCodeMCPServer)> {
         self.mcp
@@ -69,3 +97,94 @@ impl OpenCodeConfig {
             .collect()
     }
 }
+
+pub struct OpenCodeSource;
+
+impl ConfigSource for OpenCodeSource {
+    fn kind(&self) -> SourceKind {
+        SourceKind::OpenCode
+    }
+
+    fn display_name(&self) -> &'static str {
+        "OpenCode"
+    }
+
+    /// opencode.json, or opencode.jsonc when that is the one that exists
+    fn local_path(&self, paths: &AppPaths) -> PathBuf {
+        let jsonc = paths.opencode_path.with_extension("jsonc");
+        if !paths.opencode_path.exists() && jsonc.exists() {
+            return jsonc;
+        }
+        paths.opencode_path.clone()
+    }
+
+    fn remote_location(&self) -> RemoteLocation {
+        let default = "~/.config/opencode/opencode.json".to_string();
+        let paths = [
+            "~/.config/opencode/opencode", // Linux/Mac default
+            "~/AppData/Roaming/opencode/opencode", // Windows guess
+            "~/.opencode",
+            "~/opencode",
+        ];
+        RemoteLocation {
+            candidates: paths.iter().flat_map(|p| [format!("{}.json", p), format!("{}.jsonc", p)]).collect(),
+            default_by_platform: [default.clone(), default.clone(), default],
+        }
+    }
+
+    fn install_check(&self) -> InstallCheck {
+        InstallCheck::Cli("opencode")
+    }
+
+    fn parse(&self, _path: &str, content: Option<&str>) -> Result<Box<dyn SourceDocument>, String> {
+        Ok(Box::new(PreservingDocument::new(OpenCodeConfig::parse(content)?, content)))
+    }
+
+    fn supports_enabled(&self) -> bool {
+        true
+    }
+
+    fn parse_server(&self, server: &Value) -> Result<McpServer, String> {
+        converter::opencode_to_canonical(&from_json(server)?)
+    }
+
+    fn format_server(&self, server: &McpServer) -> Result<Value, String> {
+        to_json(&converter::canonical_to_opencode(server))
+    }
+}
+
+impl SourceDocument for OpenCodeConfig {
+    fn servers(&self) -> Result<HashMap<String, Value>, String> {
+        servers_to_json(&self.mcp)
+    }
+
+    fn server_pointer(&self, name: &str) -> String {
+        json_pointer(&["mcp", name])
+    }
+
+    fn is_enabled(&self, name: &str) -> bool {
+        self.mcp.get(name).map(|s| s.enabled.unwrap_or(true)).unwrap_or(true)
+    }
+
+    fn put_server(&mut self, name: &str, server: Value) -> Result<(), String> {
+        self.mcp.insert(name.to_string(), from_json(&server)?);
+        Ok(())
+    }
+
+    fn remove_server(&mut self, name: &str) -> bool {
+        self.mcp.remove(name).is_some()
+    }
+
+    fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), String> {
+        let server = self
+            .mcp
+            .get_mut(name)
+            .ok_or_else(|| format!("Server '{}' not found in OpenCode config", name))?;
+        server.enabled = Some(enabled);
+        Ok(())
+    }
+
+    fn serialize(&self) -> Result<String, String> {
+        to_pretty_json(self)
+    }
+}
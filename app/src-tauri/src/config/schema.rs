@@ -0,0 +1,179 @@
+use super::source::{ConfigSource, SourceDocument};
+use super::SourceKind;
+use serde::Serialize;
+use serde_json::Value;
+
+/// One reason a client would reject a server entry
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaViolation {
+    /// Server the problem is in
+    pub server: String,
+    /// JSON pointer to the offending value in the config file
+    pub pointer: String,
+    pub message: String,
+}
+
+/// Schema for one server entry in the client's own format. Bundled rather than taken from a file's `$schema`
+/// (OpenCode declares one) so validation works offline and covers every client the same way. Only what the
+/// client checks at startup is constrained; unknown fields are allowed.
+fn server_schema(kind: SourceKind) -> &'static str {
+    match kind {
+        SourceKind::Claude => include_str!("schemas/claude.json"),
+        SourceKind::ClaudeDesktop => include_str!("schemas/claude_desktop.json"),
+        SourceKind::Codex => include_str!("schemas/codex.json"),
+        SourceKind::Cursor => include_str!("schemas/cursor.json"),
+        SourceKind::Gemini => include_str!("schemas/gemini.json"),
+        SourceKind::OpenCode => include_str!("schemas/opencode.json"),
+        SourceKind::VsCode => include_str!("schemas/vscode.json"),
+    }
+}
+
+fn validator(kind: SourceKind) -> Result<jsonschema::Validator, String> {
+    let schema: Value = serde_json::from_str(server_schema(kind))
+        .map_err(|e| format!("Invalid bundled schema for {}: {}", kind.as_str(), e))?;
+    jsonschema::validator_for(&schema).map_err(|e| format!("Invalid bundled schema for {}: {}", kind.as_str(), e))
+}
+
+/// Check the named servers of `doc` (all of them when `names` is `None`) against `source`'s schema
+pub fn validate_servers(
+    source: &dyn ConfigSource,
+    doc: &dyn SourceDocument,
+    names: Option<&[String]>,
+) -> Result<Vec<SchemaViolation>, String> {
+    let validator = validator(source.kind())?;
+    let servers = doc.servers()?;
+
+    let mut checked: Vec<&String> = match names {
+        Some(names) => names.iter().filter(|n| servers.contains_key(*n)).collect(),
+        None => servers.keys().collect(),
+    };
+    checked.sort();
+
+    let mut violations = Vec::new();
+    for name in checked {
+        let pointer = doc.server_pointer(name);
+        for error in validator.iter_errors(&servers[name]) {
+            violations.push(SchemaViolation {
+                server: name.clone(),
+                pointer: format!("{}{}", pointer, error.instance_path.as_str()),
+                message: error.to_string(),
+            });
+        }
+    }
+    Ok(violations)
+}
+
+/// A write refused because the client would reject what it adds or changes. It reaches the UI as
+/// `SCHEMA_VIOLATION: ` followed by this struct as JSON, so each violation can be shown at its pointer.
+#[derive(Debug, Clone, Serialize, thiserror::Error)]
+#[serde(rename_all = "camelCase")]
+#[error("SCHEMA_VIOLATION: {}", serde_json::to_string(self).unwrap_or_default())]
+pub struct SchemaRejection {
+    pub client: String,
+    pub path: String,
+    pub violations: Vec<SchemaViolation>,
+}
+
+impl From<SchemaRejection> for String {
+    fn from(e: SchemaRejection) -> Self {
+        e.to_string()
+    }
+}
+
+/// Refuse to write `output` over `original` when a server it adds or changes would be rejected by the client.
+/// Servers left as they were aren't checked, so an existing bad entry doesn't block unrelated edits. Output
+/// that can't be checked at all is refused with one violation at the document root.
+pub fn check_write(source: &dyn ConfigSource, path: &str, original: Option<&str>, output: &str) -> Result<(), SchemaRejection> {
+    let violations = match write_violations(source, path, original, output) {
+        Ok(violations) => violations,
+        Err(e) => vec![SchemaViolation { server: String::new(), pointer: String::new(), message: e }],
+    };
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let details: Vec<String> = violations.iter().map(|v| format!("{}: {}", v.pointer, v.message)).collect();
+    println!("[Schema] Refused to write {}: {}", path, details.join("; "));
+    Err(SchemaRejection { client: source.display_name().to_string(), path: path.to_string(), violations })
+}
+
+fn write_violations(source: &dyn ConfigSource, path: &str, original: Option<&str>, output: &str) -> Result<Vec<SchemaViolation>, String> {
+    let before = match original {
+        Some(content) => source.parse(path, Some(content)).and_then(|doc| doc.servers()).unwrap_or_default(),
+        None => Default::default(),
+    };
+    let doc = source.parse(path, Some(output))?;
+    let changed: Vec<String> = doc
+        .servers()?
+        .into_iter()
+        .filter(|(name, server)| before.get(name) != Some(server))
+        .map(|(name, _)| name)
+        .collect();
+
+    validate_servers(source, doc.as_ref(), Some(&changed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_schemas_compile() {
+        for kind in SourceKind::ALL {
+            validator(kind).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_violations_point_into_the_file() {
+        let source = SourceKind::OpenCode.source();
+        let content = r#"{"mcp": {
+            "ok": {"type": "local", "command": ["fs"]},
+            "typo": {"type": "locl", "command": ["fs"]},
+            "empty": {"type": "local", "command": []},
+            "web": {"type": "remote"}
+        }}"#;
+        let doc = source.parse("opencode.json", Some(content)).unwrap();
+        let violations = validate_servers(source, doc.as_ref(), None).unwrap();
+
+        let pointers: Vec<&str> = violations.iter().map(|v| v.pointer.as_str()).collect();
+        assert_eq!(pointers, vec!["/mcp/empty/command", "/mcp/typo/type", "/mcp/web"]);
+        assert!(violations[1].message.contains("locl"), "{}", violations[1].message);
+
+        let claude = SourceKind::Claude.source();
+        let doc = claude.parse(".claude.json", Some(r#"{"mcpServers": {"_disabled_a/b": {"type": "stdio"}}}"#)).unwrap();
+        let violations = validate_servers(claude, doc.as_ref(), None).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].pointer, "/mcpServers/_disabled_a~1b");
+        assert_eq!(violations[0].server, "a/b");
+    }
+
+    #[test]
+    fn test_check_write_only_blocks_changed_servers() {
+        let source = SourceKind::Claude.source();
+        let original = r#"{"mcpServers": {"broken": {"type": "http"}}}"#;
+
+        let mut doc = source.parse(".claude.json", Some(original)).unwrap();
+        doc.put_server("fs", serde_json::json!({"type": "stdio", "command": "npx"})).unwrap();
+        assert!(check_write(source, ".claude.json", Some(original), &doc.serialize().unwrap()).is_ok());
+
+        doc.put_server("web", serde_json::json!({"type": "sse"})).unwrap();
+        let rejection = check_write(source, ".claude.json", Some(original), &doc.serialize().unwrap()).unwrap_err();
+        let pointers: Vec<&str> = rejection.violations.iter().map(|v| v.pointer.as_str()).collect();
+        assert_eq!(pointers, vec!["/mcpServers/web"]);
+        assert_eq!(rejection.violations[0].server, "web");
+
+        // The UI gets the violations as JSON after the error code
+        let message = String::from(rejection);
+        let json: Value = serde_json::from_str(message.strip_prefix("SCHEMA_VIOLATION: ").unwrap()).unwrap();
+        assert_eq!(json["violations"][0]["pointer"], "/mcpServers/web");
+        assert_eq!(json["path"], ".claude.json");
+    }
+
+    #[test]
+    fn test_check_write_refuses_unparsable_output() {
+        let rejection = check_write(SourceKind::Claude.source(), ".claude.json", None, "{not json").unwrap_err();
+        assert_eq!(rejection.violations.len(), 1);
+        assert_eq!(rejection.violations[0].pointer, "");
+    }
+}
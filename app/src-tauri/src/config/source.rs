@@ -0,0 +1,251 @@
+use super::canonical::{restore_carried, unrepresented_fields, McpServer};
+use super::converter::Platform;
+use super::{
+    ClaudeDesktopSource, ClaudeSource, CodexSource, CursorSource, GeminiSource, OpenCodeSource, SourceKind,
+    VsCodeSource,
+};
+use crate::paths::AppPaths;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Where a source keeps its config on a managed machine
+pub struct RemoteLocation {
+    /// Probed in order; the first readable file the source `accepts` is used
+    pub candidates: Vec<String>,
+    /// Where a new config is created when no candidate exists: `[macOS, Windows, Linux]`
+    pub default_by_platform: [String; 3],
+}
+
+impl RemoteLocation {
+    /// One path on every platform, relative to the home directory
+    pub fn single(path: &str) -> Self {
+        Self {
+            candidates: vec![path.to_string()],
+            default_by_platform: [path.to_string(), path.to_string(), path.to_string()],
+        }
+    }
+}
+
+/// How to tell whether the client itself is installed on a machine
+pub enum InstallCheck {
+    /// A binary on the PATH
+    Cli(&'static str),
+    /// Shell commands printing `INSTALLED`: `[macOS, Windows, Linux]`
+    Shell([&'static str; 3]),
+}
+
+/// A client that reads MCP servers from a config file (Claude Code, OpenCode, Cursor...).
+///
+/// Servers cross this interface as JSON in the client's own format, so commands, sync and fan-out work for
+/// any source. Conversion between sources goes through the canonical `McpServer`.
+pub trait ConfigSource: Sync {
+    fn kind(&self) -> SourceKind;
+
+    fn display_name(&self) -> &'static str;
+
+    /// Default config on this host
+    fn local_path(&self, paths: &AppPaths) -> PathBuf;
+
+    fn remote_location(&self) -> RemoteLocation;
+
+    fn install_check(&self) -> InstallCheck;
+
+    /// Parse `content` (`None` for a file that doesn't exist yet). `path` matters for sources with more than one layout.
+    fn parse(&self, path: &str, content: Option<&str>) -> Result<Box<dyn SourceDocument>, String>;
+
+    /// Whether a file found while probing a machine is this source's config
+    fn accepts(&self, path: &str, content: &str) -> bool {
+        self.parse(path, Some(content)).is_ok()
+    }
+
+    /// Whether the file has a per-server enabled flag (`SourceDocument::set_enabled`)
+    fn supports_enabled(&self) -> bool {
+        false
+    }
+
+    /// Convert one of this source's servers to the canonical model, as written
+    fn parse_server(&self, server: &Value) -> Result<McpServer, String>;
+
+    /// Convert a canonical server to this source's format, leaving out what the format has no place for
+    fn format_server(&self, server: &McpServer) -> Result<Value, String>;
+
+    /// `parse_server` with the command adapted for `platform`
+    fn to_canonical(&self, server: &Value, platform: Platform) -> Result<McpServer, String> {
+        Ok(self.parse_server(server)?.adapted_for(platform))
+    }
+
+    /// `to_canonical` plus the fields the format couldn't hold when the app last wrote the entry
+    /// (see `db::carried_fields`), so converting back through the canonical model is lossless
+    fn to_canonical_carried(&self, server: &Value, carried: Option<&Map<String, Value>>, platform: Platform) -> Result<McpServer, String> {
+        match carried {
+            Some(carried) => Ok(restore_carried(self, self.parse_server(server)?, carried)?.adapted_for(platform)),
+            None => self.to_canonical(server, platform),
+        }
+    }
+
+    /// `format_server` for `platform`
+    fn to_native(&self, server: &McpServer, platform: Platform) -> Result<Value, String> {
+        self.format_server(&server.adapted_for(platform))
+    }
+
+    /// `to_native` plus the fields the format can't hold, for the app to keep (see `to_canonical_carried`)
+    fn to_native_carried(&self, server: &McpServer, platform: Platform) -> Result<(Value, Map<String, Value>), String> {
+        let server = server.adapted_for(platform);
+        let native = self.format_server(&server)?;
+        let carried = unrepresented_fields(self, &server, &native)?;
+        Ok((native, carried))
+    }
+
+    /// Re-target a server's command for another platform, keeping everything else
+    fn adapt_for_platform(&self, server: &Value, platform: Platform) -> Result<Value, String> {
+        self.to_native(&self.to_canonical(server, platform)?, platform)
+    }
+}
+
+/// A parsed config file. Edits keep settings and server fields the model doesn't know about.
+pub trait SourceDocument: Send {
+    /// Servers by name in the source's own format, disabled ones included
+    fn servers(&self) -> Result<HashMap<String, Value>, String>;
+
+    /// JSON pointer to a server in the file, for reporting where it is invalid
+    fn server_pointer(&self, name: &str) -> String;
+
+    fn is_enabled(&self, _name: &str) -> bool {
+        true
+    }
+
+    /// Add or replace a server
+    fn put_server(&mut self, name: &str, server: Value) -> Result<(), String>;
+
+    /// `false` if there was no such server
+    fn remove_server(&mut self, name: &str) -> bool;
+
+    fn set_enabled(&mut self, _name: &str, _enabled: bool) -> Result<(), String> {
+        Err("This config has no per-server enabled setting".to_string())
+    }
+
+    fn serialize(&self) -> Result<String, String>;
+}
+
+impl SourceKind {
+    pub const ALL: [SourceKind; 7] = [
+        SourceKind::Claude,
+        SourceKind::ClaudeDesktop,
+        SourceKind::Codex,
+        SourceKind::Cursor,
+        SourceKind::Gemini,
+        SourceKind::OpenCode,
+        SourceKind::VsCode,
+    ];
+
+    pub fn source(self) -> &'static dyn ConfigSource {
+        match self {
+            SourceKind::Claude => &ClaudeSource,
+            SourceKind::ClaudeDesktop => &ClaudeDesktopSource,
+            SourceKind::Codex => &CodexSource,
+            SourceKind::Cursor => &CursorSource,
+            SourceKind::Gemini => &GeminiSource,
+            SourceKind::OpenCode => &OpenCodeSource,
+            SourceKind::VsCode => &VsCodeSource,
+        }
+    }
+
+    /// The source whose config lives at `path`, judged by where it sits in the home directory.
+    /// A project's `.mcp.json` is in Claude Code's format.
+    pub fn for_path(path: &str) -> Option<SourceKind> {
+        let path = path.replace('\\', "/");
+        if path == ".mcp.json" || path.ends_with("/.mcp.json") {
+            return Some(SourceKind::Claude);
+        }
+        SourceKind::ALL.into_iter().find(|kind| {
+            kind.source()
+                .remote_location()
+                .candidates
+                .iter()
+                .any(|candidate| path.ends_with(candidate.trim_start_matches('~')))
+        })
+    }
+}
+
+/// JSON pointer from unescaped path segments
+pub(crate) fn json_pointer(segments: &[&str]) -> String {
+    segments
+        .iter()
+        .map(|s| format!("/{}", s.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// Deserialize a server received as JSON into a source's model
+pub(crate) fn from_json<T: DeserializeOwned>(server: &Value) -> Result<T, String> {
+    serde_json::from_value(server.clone()).map_err(|e| format!("Invalid server definition: {}", e))
+}
+
+pub(crate) fn to_json<T: Serialize>(server: &T) -> Result<Value, String> {
+    serde_json::to_value(server).map_err(|e| format!("Failed to serialize server: {}", e))
+}
+
+pub(crate) fn to_pretty_json<T: Serialize>(config: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(config).map_err(|e| format!("Failed to serialize JSON: {}", e))
+}
+
+/// Servers of a `HashMap`-backed config as JSON
+pub(crate) fn servers_to_json<T: Serialize>(servers: &HashMap<String, T>) -> Result<HashMap<String, Value>, String> {
+    servers
+        .iter()
+        .map(|(name, server)| Ok((name.clone(), to_json(server)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Transport;
+
+    #[test]
+    fn test_every_source_round_trips_a_stdio_server() {
+        let server = McpServer {
+            transport: Transport::Stdio,
+            command: Some("npx".to_string()),
+            args: Some(vec!["-y".to_string(), "server-fs".to_string()]),
+            ..Default::default()
+        };
+
+        for kind in SourceKind::ALL {
+            let source = kind.source();
+            assert_eq!(source.kind(), kind);
+
+            let native = source.to_native(&server, Platform::Linux).unwrap();
+            let mut doc = source.parse("mcp.json", None).unwrap();
+            doc.put_server("fs", native.clone()).unwrap();
+
+            let reparsed = source.parse("mcp.json", Some(&doc.serialize().unwrap())).unwrap();
+            let servers = reparsed.servers().unwrap();
+            assert_eq!(servers["fs"], native, "{}", kind.as_str());
+            assert!(reparsed.is_enabled("fs"));
+
+            let back = source.to_canonical(&servers["fs"], Platform::Linux).unwrap();
+            assert_eq!(back.command.as_deref(), Some("npx"), "{}", kind.as_str());
+            assert_eq!(back.args, server.args, "{}", kind.as_str());
+
+            let mut doc = reparsed;
+            assert_eq!(doc.set_enabled("fs", false).is_ok(), source.supports_enabled(), "{}", kind.as_str());
+            assert!(doc.remove_server("fs"));
+            assert!(!doc.remove_server("fs"));
+        }
+    }
+
+    #[test]
+    fn test_source_for_path() {
+        assert_eq!(SourceKind::for_path("/home/dev/.claude.json"), Some(SourceKind::Claude));
+        assert_eq!(SourceKind::for_path("/srv/app/.mcp.json"), Some(SourceKind::Claude));
+        assert_eq!(SourceKind::for_path("~/.config/opencode/opencode.jsonc"), Some(SourceKind::OpenCode));
+        assert_eq!(
+            SourceKind::for_path("C:\\Users\\dev\\AppData\\Roaming\\Code\\User\\mcp.json"),
+            Some(SourceKind::VsCode)
+        );
+        assert_eq!(SourceKind::for_path("/home/dev/notes.json"), None);
+    }
+}
@@ -0,0 +1,251 @@
+use super::converter;
+use super::json_edit::PreservingDocument;
+use super::jsonc::strip_jsonc;
+use super::source::{from_json, json_pointer, to_json, ConfigSource, InstallCheck, RemoteLocation, SourceDocument};
+use super::{McpServer, SourceKind};
+use crate::paths::AppPaths;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// A server as VS Code stores it in `mcp.json` or under `mcp.servers` in settings.json.
+/// Values may reference `${input:...}` prompts declared in `inputs`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct VsCodeMCPServer {
+    /// `stdio`, `http` or `sse`
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub server_type: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<HashMap<String, String>>,
+
+    #[serde(rename = "envFile", skip_serializing_if = "Option::is_none")]
+    pub env_file: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headers: Option<HashMap<String, String>>,
+
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, serde_json::Value>,
+}
+
+/// The `mcp.json` shape (user profile or a workspace's `.vscode/mcp.json`), also used for settings.json's `mcp` key
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct VsCodeMcpConfig {
+    #[serde(default)]
+    pub servers: HashMap<String, VsCodeMCPServer>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<serde_json::Value>,
+
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, serde_json::Value>,
+}
+
+/// VS Code's settings.json; only the `mcp` key is modelled
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct VsCodeSettings {
+    #[serde(default)]
+    pub mcp: VsCodeMcpConfig,
+
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, serde_json::Value>,
+}
+
+/// A VS Code config file in either layout, told apart by its file name.
+/// Both are JSONC; comments are not kept when the file is written back.
+#[derive(Debug, Clone)]
+pub enum VsCodeDocument {
+    McpJson(VsCodeMcpConfig),
+    Settings(VsCodeSettings),
+}
+
+impl VsCodeDocument {
+    pub fn is_settings_path(path: &str) -> bool {
+        path.replace('\\', "/").rsplit('/').next() == Some("settings.json")
+    }
+
+    /// Parse `content` (`None` for a file that doesn't exist yet) in the layout implied by `path`
+    pub fn parse(path: &str, content: Option<&str>) -> Result<Self, String> {
+        let stripped = content.map(strip_jsonc).filter(|c| !c.trim().is_empty());
+        let parse_err = |e: serde_json::Error| format!("Failed to parse VS Code config: {}", e);
+
+        if Self::is_settings_path(path) {
+            let settings = match stripped {
+                Some(c) => serde_json::from_str(&c).map_err(parse_err)?,
+                None => VsCodeSettings::default(),
+            };
+            Ok(VsCodeDocument::Settings(settings))
+        } else {
+            let config = match stripped {
+                Some(c) => serde_json::from_str(&c).map_err(parse_err)?,
+                None => VsCodeMcpConfig::default(),
+            };
+            Ok(VsCodeDocument::McpJson(config))
+        }
+    }
+
+    pub fn servers(&self) -> &HashMap<String, VsCodeMCPServer> {
+        match self {
+            VsCodeDocument::McpJson(c) => &c.servers,
+            VsCodeDocument::Settings(s) => &s.mcp.servers,
+        }
+    }
+
+    pub fn servers_mut(&mut self) -> &mut HashMap<String, VsCodeMCPServer> {
+        match self {
+            VsCodeDocument::McpJson(c) => &mut c.servers,
+            VsCodeDocument::Settings(s) => &mut s.mcp.servers,
+        }
+    }
+
+    pub fn to_json_string(&self) -> Result<String, String> {
+        match self {
+            VsCodeDocument::McpJson(c) => serde_json::to_string_pretty(c),
+            VsCodeDocument::Settings(s) => serde_json::to_string_pretty(s),
+        }
+        .map_err(|e| format!("Failed to serialize JSON: {}", e))
+    }
+}
+
+/// VS Code's user-profile MCP config: mcp.json, or `mcp.servers` in settings.json for older setups
+pub struct VsCodeSource;
+
+impl ConfigSource for VsCodeSource {
+    fn kind(&self) -> SourceKind {
+        SourceKind::VsCode
+    }
+
+    fn display_name(&self) -> &'static str {
+        "VS Code"
+    }
+
+    /// The user-profile mcp.json, unless it doesn't exist yet and settings.json still holds the servers
+    fn local_path(&self, paths: &AppPaths) -> PathBuf {
+        if !paths.vscode_path.exists() {
+            let settings = paths.vscode_settings_path.to_string_lossy().to_string();
+            let has_servers = fs::read_to_string(&paths.vscode_settings_path)
+                .map(|c| self.accepts(&settings, &c))
+                .unwrap_or(false);
+            if has_servers {
+                return paths.vscode_settings_path.clone();
+            }
+        }
+        paths.vscode_path.clone()
+    }
+
+    /// mcp.json first, then a settings.json that still has `mcp.servers`
+    fn remote_location(&self) -> RemoteLocation {
+        let user_dirs = [
+            "~/Library/Application Support/Code/User", // macOS
+            "~/AppData/Roaming/Code/User", // Windows (%APPDATA%)
+            "~/.config/Code/User", // Linux
+        ];
+        let mcp_json = user_dirs.map(|d| format!("{}/mcp.json", d));
+        let candidates = mcp_json
+            .iter()
+            .cloned()
+            .chain(user_dirs.iter().map(|d| format!("{}/settings.json", d)))
+            .collect();
+        RemoteLocation { candidates, default_by_platform: mcp_json }
+    }
+
+    fn install_check(&self) -> InstallCheck {
+        InstallCheck::Cli("code")
+    }
+
+    fn parse(&self, path: &str, content: Option<&str>) -> Result<Box<dyn SourceDocument>, String> {
+        Ok(Box::new(PreservingDocument::new(VsCodeDocument::parse(path, content)?, content)))
+    }
+
+    /// A settings.json only counts when it has servers; every user has one
+    fn accepts(&self, path: &str, content: &str) -> bool {
+        match VsCodeDocument::parse(path, Some(content)) {
+            Ok(doc) => !VsCodeDocument::is_settings_path(path) || !VsCodeDocument::servers(&doc).is_empty(),
+            Err(_) => false,
+        }
+    }
+
+    fn parse_server(&self, server: &Value) -> Result<McpServer, String> {
+        converter::vscode_to_canonical(&from_json(server)?)
+    }
+
+    fn format_server(&self, server: &McpServer) -> Result<Value, String> {
+        to_json(&converter::canonical_to_vscode(server))
+    }
+}
+
+impl SourceDocument for VsCodeDocument {
+    fn servers(&self) -> Result<HashMap<String, Value>, String> {
+        VsCodeDocument::servers(self)
+            .iter()
+            .map(|(name, server)| Ok((name.clone(), to_json(server)?)))
+            .collect()
+    }
+
+    fn server_pointer(&self, name: &str) -> String {
+        match self {
+            VsCodeDocument::McpJson(_) => json_pointer(&["servers", name]),
+            VsCodeDocument::Settings(_) => json_pointer(&["mcp", "servers", name]),
+        }
+    }
+
+    fn put_server(&mut self, name: &str, server: Value) -> Result<(), String> {
+        self.servers_mut().insert(name.to_string(), from_json(&server)?);
+        Ok(())
+    }
+
+    fn remove_server(&mut self, name: &str) -> bool {
+        self.servers_mut().remove(name).is_some()
+    }
+
+    fn serialize(&self) -> Result<String, String> {
+        self.to_json_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vscode_layouts() {
+        let settings = r#"{
+            // user settings
+            "editor.tabSize": 2,
+            "mcp": {
+                "inputs": [{"type": "promptString", "id": "token", "password": true}],
+                "servers": {
+                    "github": {"type": "http", "url": "https://api.githubcopilot.com/mcp/", "headers": {"Authorization": "Bearer ${input:token}"}},
+                },
+            },
+        }"#;
+        let doc = VsCodeDocument::parse("/home/me/.config/Code/User/settings.json", Some(settings)).unwrap();
+        assert!(matches!(doc, VsCodeDocument::Settings(_)));
+        assert_eq!(doc.servers()["github"].server_type.as_deref(), Some("http"));
+
+        let out: serde_json::Value = serde_json::from_str(&doc.to_json_string().unwrap()).unwrap();
+        assert_eq!(out["editor.tabSize"], 2);
+        assert_eq!(out["mcp"]["inputs"][0]["id"], "token");
+
+        let mut workspace = VsCodeDocument::parse("C:\\repo\\.vscode\\mcp.json", None).unwrap();
+        workspace.servers_mut().insert("fs".to_string(), VsCodeMCPServer {
+            server_type: Some("stdio".to_string()),
+            command: Some("npx".to_string()),
+            ..Default::default()
+        });
+        let out: serde_json::Value = serde_json::from_str(&workspace.to_json_string().unwrap()).unwrap();
+        assert_eq!(out["servers"]["fs"]["command"], "npx");
+    }
+}
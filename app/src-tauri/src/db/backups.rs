@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Row, Sqlite};
+
+/// A config backup taken before we modified a file, locally (`machine_id: None`) or on a machine
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupRecord {
+    pub id: i64,
+    pub machine_id: Option<i64>,
+    pub path: String,        // The config file that was backed up
+    pub backup_path: String, // Where the copy lives, next to the original
+    pub content_hash: String,
+    pub size: i64,
+    pub reason: String,      // What was about to change it, e.g. "update_server", "sync"
+    pub created_at: i64,     // Unix seconds
+}
+
+fn row_to_backup(row: &sqlx::sqlite::SqliteRow) -> BackupRecord {
+    BackupRecord {
+        id: row.try_get("id").unwrap_or_default(),
+        machine_id: row.try_get("machine_id").unwrap_or_default(),
+        path: row.try_get("path").unwrap_or_default(),
+        backup_path: row.try_get("backup_path").unwrap_or_default(),
+        content_hash: row.try_get("content_hash").unwrap_or_default(),
+        size: row.try_get("size").unwrap_or_default(),
+        reason: row.try_get("reason").unwrap_or_default(),
+        created_at: row.try_get("created_at").unwrap_or_default(),
+    }
+}
+
+/// A backup to catalog, see `BackupRecord`
+#[derive(Debug, Clone)]
+pub struct NewBackup<'a> {
+    pub machine_id: Option<i64>,
+    pub path: &'a str,
+    pub backup_path: &'a str,
+    pub content_hash: &'a str,
+    pub size: i64,
+    pub reason: &'a str,
+    pub created_at: i64,
+}
+
+pub async fn insert_backup(pool: &Pool<Sqlite>, backup: &NewBackup<'_>) -> Result<i64, String> {
+    let result = sqlx::query(
+        "INSERT INTO backups (machine_id, path, backup_path, content_hash, size, reason, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(backup.machine_id)
+    .bind(backup.path)
+    .bind(backup.backup_path)
+    .bind(backup.content_hash)
+    .bind(backup.size)
+    .bind(backup.reason)
+    .bind(backup.created_at)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record backup: {}", e))?;
+
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn get_backup(pool: &Pool<Sqlite>, id: i64) -> Result<BackupRecord, String> {
+    let row = sqlx::query(
+        "SELECT id, machine_id, path, backup_path, content_hash, size, reason, created_at
+         FROM backups WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to get backup: {}", e))?
+    .ok_or_else(|| format!("Backup {} not found", id))?;
+
+    Ok(row_to_backup(&row))
+}
+
+/// Backups on one machine (`None` = local), optionally for a single config file, newest first
+pub async fn list_backups(pool: &Pool<Sqlite>, machine_id: Option<i64>, path: Option<&str>) -> Result<Vec<BackupRecord>, String> {
+    let rows = sqlx::query(
+        "SELECT id, machine_id, path, backup_path, content_hash, size, reason, created_at
+         FROM backups
+         WHERE machine_id IS ? AND (? IS NULL OR path = ?)
+         ORDER BY created_at DESC, id DESC"
+    )
+    .bind(machine_id)
+    .bind(path)
+    .bind(path)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to list backups: {}", e))?;
+
+    Ok(rows.iter().map(row_to_backup).collect())
+}
+
+pub async fn list_all_backups(pool: &Pool<Sqlite>) -> Result<Vec<BackupRecord>, String> {
+    let rows = sqlx::query(
+        "SELECT id, machine_id, path, backup_path, content_hash, size, reason, created_at
+         FROM backups ORDER BY created_at DESC, id DESC"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to list backups: {}", e))?;
+
+    Ok(rows.iter().map(row_to_backup).collect())
+}
+
+pub async fn delete_backup(pool: &Pool<Sqlite>, id: i64) -> Result<(), String> {
+    sqlx::query("DELETE FROM backups WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete backup: {}", e))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{insert_test_machine, test_pool};
+
+    fn backup<'a>(machine_id: Option<i64>, path: &'a str, backup_path: &'a str, created_at: i64) -> NewBackup<'a> {
+        NewBackup { machine_id, path, backup_path, content_hash: "hash", size: 2, reason: "sync", created_at }
+    }
+
+    #[tokio::test]
+    async fn test_list_backups_by_machine_and_path() {
+        let pool = test_pool().await;
+        insert_test_machine(&pool, 7).await;
+
+        insert_backup(&pool, &backup(None, "/a.json", "/a.json.bak.1", 100)).await.unwrap();
+        insert_backup(&pool, &backup(None, "/a.json", "/a.json.bak.2", 200)).await.unwrap();
+        insert_backup(&pool, &backup(None, "/b.json", "/b.json.bak.1", 150)).await.unwrap();
+        insert_backup(&pool, &backup(Some(7), "~/.claude.json", "~/.claude.json.bak.1", 300)).await.unwrap();
+
+        let local = list_backups(&pool, None, None).await.unwrap();
+        assert_eq!(local.len(), 3);
+
+        let a = list_backups(&pool, None, Some("/a.json")).await.unwrap();
+        assert_eq!(a.iter().map(|b| b.created_at).collect::<Vec<_>>(), vec![200, 100]);
+
+        let remote = list_backups(&pool, Some(7), None).await.unwrap();
+        assert_eq!(remote.len(), 1);
+        assert_eq!(remote[0].machine_id, Some(7));
+    }
+}
@@ -0,0 +1,95 @@
+use serde_json::{Map, Value};
+use sqlx::{Pool, Row, Sqlite};
+use std::collections::HashMap;
+
+/// Canonical fields a client's config has no place for (e.g. `sse` in OpenCode, which only knows "remote"),
+/// kept per server entry when the app writes it so converting back restores them. They live here rather than
+/// in the client's file, which keeps only its own keys.
+pub async fn get_carried_fields(
+    pool: &Pool<Sqlite>,
+    machine_id: Option<i64>,
+    source: &str,
+    path: &str,
+) -> Result<HashMap<String, Map<String, Value>>, String> {
+    let rows = sqlx::query(
+        "SELECT server_name, fields FROM carried_fields WHERE machine_id IS ? AND source = ? AND path = ?"
+    )
+    .bind(machine_id)
+    .bind(source)
+    .bind(path)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to get carried fields: {}", e))?;
+
+    let mut carried = HashMap::new();
+    for row in rows {
+        let name: String = row.try_get("server_name").unwrap_or_default();
+        let fields: String = row.try_get("fields").unwrap_or_default();
+        if let Ok(Value::Object(fields)) = serde_json::from_str(&fields) {
+            carried.insert(name, fields);
+        }
+    }
+    Ok(carried)
+}
+
+/// Replace what is kept for one server entry; nothing is kept when `fields` is `None` or empty
+pub async fn set_carried_fields(
+    pool: &Pool<Sqlite>,
+    machine_id: Option<i64>,
+    source: &str,
+    path: &str,
+    server_name: &str,
+    fields: Option<&Map<String, Value>>,
+) -> Result<(), String> {
+    // UNIQUE doesn't apply across NULL machine ids, so replace by deleting first
+    sqlx::query("DELETE FROM carried_fields WHERE machine_id IS ? AND source = ? AND path = ? AND server_name = ?")
+        .bind(machine_id)
+        .bind(source)
+        .bind(path)
+        .bind(server_name)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to save carried fields: {}", e))?;
+
+    let Some(fields) = fields.filter(|f| !f.is_empty()) else {
+        return Ok(());
+    };
+    sqlx::query(
+        "INSERT INTO carried_fields (machine_id, source, path, server_name, fields) VALUES (?, ?, ?, ?, ?)"
+    )
+    .bind(machine_id)
+    .bind(source)
+    .bind(path)
+    .bind(server_name)
+    .bind(serde_json::to_string(fields).unwrap_or_default())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save carried fields: {}", e))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    #[tokio::test]
+    async fn test_carried_fields_per_entry() {
+        let pool = test_pool().await;
+
+        let fields: Map<String, Value> = serde_json::from_str(r#"{"transport": "sse"}"#).unwrap();
+        set_carried_fields(&pool, None, "opencode", "/a.json", "docs", Some(&fields)).await.unwrap();
+        set_carried_fields(&pool, None, "opencode", "/a.json", "docs", Some(&fields)).await.unwrap();
+        set_carried_fields(&pool, None, "opencode", "/b.json", "docs", Some(&fields)).await.unwrap();
+
+        let carried = get_carried_fields(&pool, None, "opencode", "/a.json").await.unwrap();
+        assert_eq!(carried.len(), 1);
+        assert_eq!(carried["docs"]["transport"], "sse");
+        assert!(get_carried_fields(&pool, None, "claude", "/a.json").await.unwrap().is_empty());
+
+        set_carried_fields(&pool, None, "opencode", "/a.json", "docs", Some(&Map::new())).await.unwrap();
+        assert!(get_carried_fields(&pool, None, "opencode", "/a.json").await.unwrap().is_empty());
+        assert_eq!(get_carried_fields(&pool, None, "opencode", "/b.json").await.unwrap().len(), 1);
+    }
+}
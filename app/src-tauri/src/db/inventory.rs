@@ -0,0 +1,376 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{Pool, Row, Sqlite};
+
+/// What a server offers over MCP
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemKind {
+    Tool,
+    Resource,
+    Prompt,
+}
+
+impl ItemKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ItemKind::Tool => "tool",
+            ItemKind::Resource => "resource",
+            ItemKind::Prompt => "prompt",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "resource" => ItemKind::Resource,
+            "prompt" => ItemKind::Prompt,
+            _ => ItemKind::Tool,
+        }
+    }
+}
+
+/// One tool, resource or prompt as the server listed it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryItem {
+    pub kind: ItemKind,
+    pub name: String, // Tool/prompt name, or the resource URI
+    pub description: Option<String>,
+    pub definition: Value,
+}
+
+impl InventoryItem {
+    /// Build from a `tools/list`, `resources/list` or `prompts/list` entry; `None` if it has no identifier
+    pub fn from_listed(kind: ItemKind, definition: &Value) -> Option<Self> {
+        let key = if kind == ItemKind::Resource { "uri" } else { "name" };
+        Some(InventoryItem {
+            kind,
+            name: definition.get(key)?.as_str()?.to_string(),
+            description: definition.get("description").and_then(Value::as_str).map(str::to_string),
+            definition: definition.clone(),
+        })
+    }
+}
+
+/// A distinct inventory of one server on one machine (`machine_id: None` = local) and source.
+/// Probes that find the same items only move `last_probed_at`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InventorySnapshot {
+    pub id: i64,
+    pub machine_id: Option<i64>,
+    pub source: String,
+    pub server_name: String,
+    pub probed_at: i64,      // Unix seconds, first probe that found this inventory
+    pub last_probed_at: i64, // Unix seconds, latest probe that still found it
+    /// Unix seconds of the first failed probe since then; a stale inventory may no longer be offered
+    pub stale_since: Option<i64>,
+}
+
+/// A server whose latest inventory contains a given item
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemProvider {
+    pub machine_id: Option<i64>,
+    pub source: String,
+    pub server_name: String,
+    pub description: Option<String>,
+    pub last_probed_at: i64,
+}
+
+/// Difference between two inventories of the same server
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryChanges {
+    pub added: Vec<InventoryItem>,
+    pub removed: Vec<InventoryItem>,
+    /// Same kind and name, different definition (description, input schema, ...); the newer one
+    pub changed: Vec<InventoryItem>,
+}
+
+fn row_to_snapshot(row: &sqlx::sqlite::SqliteRow) -> InventorySnapshot {
+    InventorySnapshot {
+        id: row.try_get("id").unwrap_or_default(),
+        machine_id: row.try_get("machine_id").unwrap_or_default(),
+        source: row.try_get("source").unwrap_or_default(),
+        server_name: row.try_get("server_name").unwrap_or_default(),
+        probed_at: row.try_get("probed_at").unwrap_or_default(),
+        last_probed_at: row.try_get("last_probed_at").unwrap_or_default(),
+        stale_since: row.try_get("stale_since").unwrap_or_default(),
+    }
+}
+
+fn row_to_item(row: &sqlx::sqlite::SqliteRow) -> InventoryItem {
+    let kind: String = row.try_get("kind").unwrap_or_default();
+    let definition: String = row.try_get("definition").unwrap_or_default();
+    InventoryItem {
+        kind: ItemKind::parse(&kind),
+        name: row.try_get("name").unwrap_or_default(),
+        description: row.try_get("description").unwrap_or_default(),
+        definition: serde_json::from_str(&definition).unwrap_or(Value::Null),
+    }
+}
+
+fn sort_items(items: &mut [InventoryItem]) {
+    items.sort_by(|a, b| (a.kind, &a.name).cmp(&(b.kind, &b.name)));
+}
+
+/// Newest inventory of a server as of `at` (unix seconds, `None` = now)
+pub async fn latest_snapshot(
+    pool: &Pool<Sqlite>,
+    machine_id: Option<i64>,
+    source: &str,
+    server_name: &str,
+    at: Option<i64>,
+) -> Result<Option<InventorySnapshot>, String> {
+    let row = sqlx::query(
+        "SELECT id, machine_id, source, server_name, probed_at, last_probed_at, stale_since
+         FROM inventory_snapshots
+         WHERE machine_id IS ? AND source = ? AND server_name = ? AND (? IS NULL OR probed_at <= ?)
+         ORDER BY probed_at DESC, id DESC LIMIT 1"
+    )
+    .bind(machine_id)
+    .bind(source)
+    .bind(server_name)
+    .bind(at)
+    .bind(at)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to get inventory: {}", e))?;
+
+    Ok(row.as_ref().map(row_to_snapshot))
+}
+
+/// Oldest inventory recorded for a server
+pub async fn first_snapshot(
+    pool: &Pool<Sqlite>,
+    machine_id: Option<i64>,
+    source: &str,
+    server_name: &str,
+) -> Result<Option<InventorySnapshot>, String> {
+    let row = sqlx::query(
+        "SELECT id, machine_id, source, server_name, probed_at, last_probed_at, stale_since
+         FROM inventory_snapshots
+         WHERE machine_id IS ? AND source = ? AND server_name = ?
+         ORDER BY probed_at ASC, id ASC LIMIT 1"
+    )
+    .bind(machine_id)
+    .bind(source)
+    .bind(server_name)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to get inventory: {}", e))?;
+
+    Ok(row.as_ref().map(row_to_snapshot))
+}
+
+/// Items of a snapshot, by kind then name
+pub async fn snapshot_items(pool: &Pool<Sqlite>, snapshot_id: i64) -> Result<Vec<InventoryItem>, String> {
+    let rows = sqlx::query(
+        "SELECT kind, name, description, definition FROM inventory_items
+         WHERE snapshot_id = ? ORDER BY kind, name"
+    )
+    .bind(snapshot_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to get inventory items: {}", e))?;
+
+    let mut items: Vec<InventoryItem> = rows.iter().map(row_to_item).collect();
+    sort_items(&mut items);
+    Ok(items)
+}
+
+/// Record what a probe found. A new snapshot is only stored when the items differ from the latest one.
+/// Either way the server's inventory is current again.
+pub async fn record_inventory(
+    pool: &Pool<Sqlite>,
+    machine_id: Option<i64>,
+    source: &str,
+    server_name: &str,
+    mut items: Vec<InventoryItem>,
+    probed_at: i64,
+) -> Result<i64, String> {
+    sort_items(&mut items);
+    if let Some(latest) = latest_snapshot(pool, machine_id, source, server_name, None).await? {
+        if snapshot_items(pool, latest.id).await? == items {
+            sqlx::query("UPDATE inventory_snapshots SET last_probed_at = ?, stale_since = NULL WHERE id = ?")
+                .bind(probed_at)
+                .bind(latest.id)
+                .execute(pool)
+                .await
+                .map_err(|e| format!("Failed to record inventory: {}", e))?;
+            return Ok(latest.id);
+        }
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| format!("Failed to record inventory: {}", e))?;
+    let snapshot_id = sqlx::query(
+        "INSERT INTO inventory_snapshots (machine_id, source, server_name, probed_at, last_probed_at)
+         VALUES (?, ?, ?, ?, ?)"
+    )
+    .bind(machine_id)
+    .bind(source)
+    .bind(server_name)
+    .bind(probed_at)
+    .bind(probed_at)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to record inventory: {}", e))?
+    .last_insert_rowid();
+
+    for item in &items {
+        sqlx::query(
+            "INSERT INTO inventory_items (snapshot_id, kind, name, description, definition)
+             VALUES (?, ?, ?, ?, ?)"
+        )
+        .bind(snapshot_id)
+        .bind(item.kind.as_str())
+        .bind(&item.name)
+        .bind(&item.description)
+        .bind(item.definition.to_string())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to record inventory: {}", e))?;
+    }
+    tx.commit().await.map_err(|e| format!("Failed to record inventory: {}", e))?;
+
+    Ok(snapshot_id)
+}
+
+/// Flag a server's latest inventory as stale after a failed probe, keeping the time of the first failure
+pub async fn mark_stale(
+    pool: &Pool<Sqlite>,
+    machine_id: Option<i64>,
+    source: &str,
+    server_name: &str,
+    failed_at: i64,
+) -> Result<(), String> {
+    let Some(latest) = latest_snapshot(pool, machine_id, source, server_name, None).await? else {
+        return Ok(());
+    };
+    sqlx::query("UPDATE inventory_snapshots SET stale_since = ? WHERE id = ? AND stale_since IS NULL")
+        .bind(failed_at)
+        .bind(latest.id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to mark inventory stale: {}", e))?;
+    Ok(())
+}
+
+/// Servers, on any machine and source, whose latest inventory has `kind` named `name` and isn't stale
+pub async fn find_providers(pool: &Pool<Sqlite>, kind: ItemKind, name: &str) -> Result<Vec<ItemProvider>, String> {
+    let rows = sqlx::query(
+        "SELECT s.machine_id, s.source, s.server_name, s.last_probed_at, i.description
+         FROM inventory_snapshots s
+         JOIN inventory_items i ON i.snapshot_id = s.id
+         WHERE i.kind = ? AND i.name = ? AND s.stale_since IS NULL
+           AND s.id = (
+             SELECT l.id FROM inventory_snapshots l
+             WHERE l.machine_id IS s.machine_id AND l.source = s.source AND l.server_name = s.server_name
+             ORDER BY l.probed_at DESC, l.id DESC LIMIT 1
+           )
+         ORDER BY s.machine_id, s.source, s.server_name"
+    )
+    .bind(kind.as_str())
+    .bind(name)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to search inventory: {}", e))?;
+
+    Ok(rows
+        .iter()
+        .map(|row| ItemProvider {
+            machine_id: row.try_get("machine_id").unwrap_or_default(),
+            source: row.try_get("source").unwrap_or_default(),
+            server_name: row.try_get("server_name").unwrap_or_default(),
+            description: row.try_get("description").unwrap_or_default(),
+            last_probed_at: row.try_get("last_probed_at").unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// Compare two sorted item lists
+pub fn diff_items(old: &[InventoryItem], new: &[InventoryItem]) -> InventoryChanges {
+    let key = |item: &InventoryItem| (item.kind, item.name.clone());
+    let mut changes = InventoryChanges::default();
+    for item in new {
+        match old.iter().find(|o| key(o) == key(item)) {
+            None => changes.added.push(item.clone()),
+            Some(o) if o.definition != item.definition => changes.changed.push(item.clone()),
+            Some(_) => {}
+        }
+    }
+    for item in old {
+        if !new.iter().any(|n| key(n) == key(item)) {
+            changes.removed.push(item.clone());
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::db::{insert_test_machine, test_pool};
+
+    fn tool(definition: Value) -> InventoryItem {
+        InventoryItem::from_listed(ItemKind::Tool, &definition).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_record_inventory_dedups_and_finds_providers() {
+        let pool = test_pool().await;
+        insert_test_machine(&pool, 3).await;
+        let read = tool(json!({"name": "read", "description": "Read a file"}));
+        let write = tool(json!({"name": "write"}));
+
+        let first = record_inventory(&pool, None, "claude", "fs", vec![write.clone(), read.clone()], 100).await.unwrap();
+        let same = record_inventory(&pool, None, "claude", "fs", vec![read.clone(), write.clone()], 200).await.unwrap();
+        assert_eq!(first, same);
+        let latest = latest_snapshot(&pool, None, "claude", "fs", None).await.unwrap().unwrap();
+        assert_eq!((latest.probed_at, latest.last_probed_at), (100, 200));
+
+        record_inventory(&pool, Some(3), "codex", "files", vec![read.clone()], 150).await.unwrap();
+        // The local server dropped `read` later, so only the machine still exposes it
+        record_inventory(&pool, None, "claude", "fs", vec![write.clone()], 300).await.unwrap();
+
+        let providers = find_providers(&pool, ItemKind::Tool, "read").await.unwrap();
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers[0].machine_id, Some(3));
+        assert_eq!(providers[0].server_name, "files");
+        assert_eq!(providers[0].description.as_deref(), Some("Read a file"));
+
+        // Once the machine's server fails to start, its old tools aren't offered any more
+        mark_stale(&pool, Some(3), "codex", "files", 400).await.unwrap();
+        mark_stale(&pool, Some(3), "codex", "files", 500).await.unwrap();
+        assert!(find_providers(&pool, ItemKind::Tool, "read").await.unwrap().is_empty());
+        let stale = latest_snapshot(&pool, Some(3), "codex", "files", None).await.unwrap().unwrap();
+        assert_eq!(stale.stale_since, Some(400));
+
+        // A successful probe with the same items makes it current again
+        record_inventory(&pool, Some(3), "codex", "files", vec![read.clone()], 600).await.unwrap();
+        assert_eq!(find_providers(&pool, ItemKind::Tool, "read").await.unwrap().len(), 1);
+
+        let before = latest_snapshot(&pool, None, "claude", "fs", Some(250)).await.unwrap().unwrap();
+        assert_eq!(before.id, first);
+        assert_eq!(snapshot_items(&pool, before.id).await.unwrap(), vec![read, write]);
+    }
+
+    #[test]
+    fn test_diff_items() {
+        let resource = InventoryItem::from_listed(ItemKind::Resource, &json!({"uri": "file:///a", "name": "a"})).unwrap();
+        assert_eq!(resource.name, "file:///a");
+
+        let old = vec![tool(json!({"name": "read"})), tool(json!({"name": "search", "description": "v1"}))];
+        let new = vec![
+            tool(json!({"name": "search", "description": "v2"})),
+            tool(json!({"name": "write"})),
+            resource.clone(),
+        ];
+        let changes = diff_items(&old, &new);
+        assert_eq!(changes.added.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(), vec!["write", "file:///a"]);
+        assert_eq!(changes.removed.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(), vec!["read"]);
+        assert_eq!(changes.changed.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(), vec!["search"]);
+    }
+}
@@ -0,0 +1,239 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Row, Sqlite};
+
+/// A trusted SSH host key, recorded on first connection (TOFU)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KnownHost {
+    pub id: i64,
+    pub host: String,
+    pub port: i64,
+    /// Bastions the host is reached through (`host_keys::jump_route`), empty when direct
+    pub via: String,
+    pub key_type: String,
+    pub fingerprint: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+fn row_to_known_host(row: &sqlx::sqlite::SqliteRow) -> KnownHost {
+    KnownHost {
+        id: row.try_get("id").unwrap_or_default(),
+        host: row.try_get("host").unwrap_or_default(),
+        port: row.try_get("port").unwrap_or_default(),
+        via: row.try_get("via").unwrap_or_default(),
+        key_type: row.try_get("key_type").unwrap_or_default(),
+        fingerprint: row.try_get("fingerprint").unwrap_or_default(),
+        created_at: row.try_get("created_at").unwrap_or_default(),
+        updated_at: row.try_get("updated_at").unwrap_or_default(),
+    }
+}
+
+pub async fn get_known_host(pool: &Pool<Sqlite>, host: &str, port: u16, via: &str) -> Result<Option<KnownHost>, String> {
+    let row = sqlx::query(
+        "SELECT id, host, port, via, key_type, fingerprint, created_at, updated_at
+         FROM known_hosts WHERE host = ? AND port = ? AND via = ?"
+    )
+    .bind(host)
+    .bind(port as i64)
+    .bind(via)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to get known host: {}", e))?;
+
+    Ok(row.as_ref().map(row_to_known_host))
+}
+
+pub async fn list_known_hosts(pool: &Pool<Sqlite>) -> Result<Vec<KnownHost>, String> {
+    let rows = sqlx::query(
+        "SELECT id, host, port, via, key_type, fingerprint, created_at, updated_at
+         FROM known_hosts ORDER BY host, port, via"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to list known hosts: {}", e))?;
+
+    Ok(rows.iter().map(row_to_known_host).collect())
+}
+
+/// Record a key for a host seen for the first time. Never replaces an existing entry.
+pub async fn insert_known_host_if_absent(
+    pool: &Pool<Sqlite>,
+    host: &str,
+    port: u16,
+    via: &str,
+    key_type: &str,
+    fingerprint: &str,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO known_hosts (host, port, via, key_type, fingerprint) VALUES (?, ?, ?, ?, ?)"
+    )
+    .bind(host)
+    .bind(port as i64)
+    .bind(via)
+    .bind(key_type)
+    .bind(fingerprint)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to add known host: {}", e))?;
+
+    Ok(())
+}
+
+/// Replace the trusted key for a host on one route (explicit user re-accept after a change)
+pub async fn replace_known_host(
+    pool: &Pool<Sqlite>,
+    host: &str,
+    port: u16,
+    via: &str,
+    key_type: &str,
+    fingerprint: &str,
+) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO known_hosts (host, port, via, key_type, fingerprint) VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT(host, port, via) DO UPDATE SET
+            key_type = excluded.key_type,
+            fingerprint = excluded.fingerprint,
+            updated_at = CURRENT_TIMESTAMP"
+    )
+    .bind(host)
+    .bind(port as i64)
+    .bind(via)
+    .bind(key_type)
+    .bind(fingerprint)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to update known host: {}", e))?;
+
+    Ok(())
+}
+
+/// Key known hosts by route as well: tables from before jump hosts are rebuilt with `UNIQUE(host, port, via)`,
+/// their keys kept as trusted for direct connections
+pub async fn migrate_known_hosts_table(pool: &Pool<Sqlite>) -> Result<(), String> {
+    let columns = sqlx::query("PRAGMA table_info(known_hosts)")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to inspect known_hosts table: {}", e))?;
+    if columns.iter().any(|c| c.try_get::<String, _>("name").unwrap_or_default() == "via") {
+        return Ok(());
+    }
+
+    println!("[DB] Migrating known_hosts table (per jump route)...");
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query(
+        "CREATE TABLE known_hosts_new (
+            id INTEGER PRIMARY KEY,
+            host TEXT NOT NULL,
+            port INTEGER NOT NULL,
+            via TEXT NOT NULL DEFAULT '',
+            key_type TEXT NOT NULL,
+            fingerprint TEXT NOT NULL,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(host, port, via)
+        )",
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to migrate known_hosts: {}", e))?;
+
+    sqlx::query(
+        "INSERT INTO known_hosts_new (id, host, port, key_type, fingerprint, created_at, updated_at)
+         SELECT id, host, port, key_type, fingerprint, created_at, updated_at FROM known_hosts",
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to migrate known_hosts: {}", e))?;
+
+    sqlx::query("DROP TABLE known_hosts")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to migrate known_hosts: {}", e))?;
+
+    sqlx::query("ALTER TABLE known_hosts_new RENAME TO known_hosts")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to migrate known_hosts: {}", e))?;
+
+    tx.commit().await.map_err(|e| format!("Failed to migrate known_hosts: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    #[tokio::test]
+    async fn test_first_use_is_not_overwritten() {
+        let pool = test_pool().await;
+
+        insert_known_host_if_absent(&pool, "box", 22, "", "ssh-ed25519", "SHA256:first").await.unwrap();
+        insert_known_host_if_absent(&pool, "box", 22, "", "ssh-ed25519", "SHA256:attacker").await.unwrap();
+
+        let known = get_known_host(&pool, "box", 22, "").await.unwrap().unwrap();
+        assert_eq!(known.fingerprint, "SHA256:first");
+        assert!(get_known_host(&pool, "box", 2222, "").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replace_known_host() {
+        let pool = test_pool().await;
+
+        insert_known_host_if_absent(&pool, "box", 22, "", "ssh-rsa", "SHA256:old").await.unwrap();
+        replace_known_host(&pool, "box", 22, "", "ssh-ed25519", "SHA256:new").await.unwrap();
+
+        let known = get_known_host(&pool, "box", 22, "").await.unwrap().unwrap();
+        assert_eq!(known.fingerprint, "SHA256:new");
+        assert_eq!(known.key_type, "ssh-ed25519");
+        assert_eq!(list_known_hosts(&pool).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_same_address_behind_different_bastions() {
+        let pool = test_pool().await;
+        let (via_a, via_b) = ("ops@bastion-a:22", "ops@outer:22,ops@bastion-b:22");
+
+        insert_known_host_if_absent(&pool, "10.0.0.5", 22, via_a, "ssh-ed25519", "SHA256:a").await.unwrap();
+        insert_known_host_if_absent(&pool, "10.0.0.5", 22, via_b, "ssh-ed25519", "SHA256:b").await.unwrap();
+        assert!(get_known_host(&pool, "10.0.0.5", 22, "").await.unwrap().is_none());
+
+        // Re-accepting the key behind one bastion leaves the other route's key alone
+        replace_known_host(&pool, "10.0.0.5", 22, via_b, "ssh-ed25519", "SHA256:b2").await.unwrap();
+        assert_eq!(get_known_host(&pool, "10.0.0.5", 22, via_a).await.unwrap().unwrap().fingerprint, "SHA256:a");
+        assert_eq!(get_known_host(&pool, "10.0.0.5", 22, via_b).await.unwrap().unwrap().fingerprint, "SHA256:b2");
+        assert_eq!(list_known_hosts(&pool).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_keeps_keys_as_direct() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::query(
+            "CREATE TABLE known_hosts (
+                id INTEGER PRIMARY KEY,
+                host TEXT NOT NULL,
+                port INTEGER NOT NULL,
+                key_type TEXT NOT NULL,
+                fingerprint TEXT NOT NULL,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE(host, port)
+            )",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO known_hosts (host, port, key_type, fingerprint) VALUES ('box', 22, 'ssh-rsa', 'SHA256:old')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        migrate_known_hosts_table(&pool).await.unwrap();
+        migrate_known_hosts_table(&pool).await.unwrap();
+
+        assert_eq!(get_known_host(&pool, "box", 22, "").await.unwrap().unwrap().fingerprint, "SHA256:old");
+        insert_known_host_if_absent(&pool, "box", 22, "ops@bastion:22", "ssh-rsa", "SHA256:new").await.unwrap();
+        assert_eq!(list_known_hosts(&pool).await.unwrap().len(), 2);
+    }
+}
@@ -1,5 +1,5 @@
 use sqlx::{Pool, Sqlite, Row};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Serialize)]
 pub struct Machine {
@@ -7,39 +7,172 @@ pub struct Machine {
     pub name: String,
     pub host: String,
     pub username: String,
-    pub ssh_key_id: i64,
+    pub ssh_key_id: Option<i64>, // None for agent-authenticated machines
     pub port: i64,
     pub platform: String, // "linux" or "windows"
+    pub auth_method: String, // "key" or "agent"
+    pub jump_host_id: Option<i64>, // Machine to tunnel through (ProxyJump), if any
     pub created_at: String,
 }
 
-pub async fn add_machine(
-    pool: &Pool<Sqlite>,
-    name: &str,
-    host: &str,
-    username: &str,
-    ssh_key_id: i64,
-    port: i64,
-    platform: &str,
-) -> Result<i64, String> {
-    // Ideally we would use a migration, but for this task we'll just handle it dynamically or assume the user handles migrations.
-    // However, since we can't run migrations easily here, we'll alter the table if column missing logic is too complex for this tool.
-    // Let's assume we can just add the column if it doesn't exist? No, SQLite ALTER TABLE ADD COLUMN is safe.
-    
-    // Check if column exists or try to add it (simple migration hack for this session)
-    let _ = sqlx::query("ALTER TABLE machines ADD COLUMN platform TEXT DEFAULT 'linux'")
-        .execute(pool)
-        .await;
+/// Connection settings of a machine being added or updated
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewMachine {
+    pub name: String,
+    pub host: String,
+    pub username: String,
+    pub ssh_key_id: Option<i64>,
+    pub port: i64,
+    #[serde(default = "default_platform")]
+    pub platform: String,
+    #[serde(default = "default_auth_method")]
+    pub auth_method: String,
+    pub jump_host_id: Option<i64>,
+}
+
+fn default_platform() -> String {
+    "linux".to_string()
+}
+
+fn default_auth_method() -> String {
+    "key".to_string()
+}
+
+/// Longest bastion chain we follow; also stops runaway loops in corrupted data
+pub const MAX_JUMP_DEPTH: usize = 8;
+
+/// Check the auth method / key combination and return the key id to store
+fn resolve_auth(auth_method: &str, ssh_key_id: Option<i64>) -> Result<Option<i64>, String> {
+    match auth_method {
+        "key" => ssh_key_id
+            .map(Some)
+            .ok_or_else(|| "An SSH key is required for key authentication".to_string()),
+        // Agent machines never reference stored key material
+        "agent" => Ok(None),
+        other => Err(format!("Unknown auth method: {}", other)),
+    }
+}
+
+fn row_to_machine(row: &sqlx::sqlite::SqliteRow) -> Machine {
+    Machine {
+        id: row.try_get("id").unwrap_or_default(),
+        name: row.try_get("name").unwrap_or_default(),
+        host: row.try_get("host").unwrap_or_default(),
+        username: row.try_get("username").unwrap_or_default(),
+        ssh_key_id: row.try_get("ssh_key_id").unwrap_or_default(),
+        port: row.try_get("port").unwrap_or_default(),
+        platform: row.try_get("platform").unwrap_or("linux".to_string()),
+        auth_method: row.try_get("auth_method").unwrap_or("key".to_string()),
+        jump_host_id: row.try_get("jump_host_id").unwrap_or_default(),
+        created_at: row.try_get("created_at").unwrap_or_default(),
+    }
+}
+
+/// Rebuild `machines` from the original schema (NOT NULL ssh_key_id, no platform/auth_method columns).
+/// SQLite can't drop a NOT NULL constraint in place, so the table is copied into the new layout.
+pub async fn migrate_machines_table(pool: &Pool<Sqlite>) -> Result<(), String> {
+    let columns = sqlx::query("PRAGMA table_info(machines)")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to inspect machines table: {}", e))?;
+
+    let key_not_null = columns.iter().any(|c| {
+        c.try_get::<String, _>("name").unwrap_or_default() == "ssh_key_id"
+            && c.try_get::<i64, _>("notnull").unwrap_or_default() == 1
+    });
+    if !key_not_null {
+        return Ok(());
+    }
+
+    let has_platform = columns
+        .iter()
+        .any(|c| c.try_get::<String, _>("name").unwrap_or_default() == "platform");
+    let platform_expr = if has_platform { "COALESCE(platform, 'linux')" } else { "'linux'" };
+
+    println!("[DB] Migrating machines table (optional SSH key, auth method)...");
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::query(
+        "CREATE TABLE machines_new (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            host TEXT NOT NULL,
+            username TEXT NOT NULL,
+            ssh_key_id INTEGER,
+            port INTEGER NOT NULL DEFAULT 22,
+            platform TEXT DEFAULT 'linux',
+            auth_method TEXT NOT NULL DEFAULT 'key' CHECK(auth_method IN ('key', 'agent')),
+            jump_host_id INTEGER REFERENCES machines(id),
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (ssh_key_id) REFERENCES ssh_keys(id) ON DELETE RESTRICT
+        )",
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to migrate machines: {}", e))?;
+
+    sqlx::query(&format!(
+        "INSERT INTO machines_new (id, name, host, username, ssh_key_id, port, platform, auth_method, created_at)
+         SELECT id, name, host, username, ssh_key_id, port, {}, 'key', created_at FROM machines",
+        platform_expr
+    ))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Failed to migrate machines: {}", e))?;
+
+    sqlx::query("DROP TABLE machines")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to migrate machines: {}", e))?;
+
+    sqlx::query("ALTER TABLE machines_new RENAME TO machines")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to migrate machines: {}", e))?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Make sure `jump_host_id` exists and that following the chain from it never leads back to `machine_id`
+async fn check_jump_chain(pool: &Pool<Sqlite>, machine_id: Option<i64>, jump_host_id: Option<i64>) -> Result<(), String> {
+    let mut next = jump_host_id;
+    let mut depth = 0;
+
+    while let Some(id) = next {
+        if Some(id) == machine_id {
+            return Err("Jump host chain would loop back to this machine".to_string());
+        }
+        depth += 1;
+        if depth > MAX_JUMP_DEPTH {
+            return Err(format!("Jump host chain is longer than {} hops", MAX_JUMP_DEPTH));
+        }
+        next = get_machine(pool, id)
+            .await
+            .map_err(|_| format!("Jump host machine {} not found", id))?
+            .jump_host_id;
+    }
+
+    Ok(())
+}
+
+pub async fn add_machine(pool: &Pool<Sqlite>, machine: &NewMachine) -> Result<i64, String> {
+    let ssh_key_id = resolve_auth(&machine.auth_method, machine.ssh_key_id)?;
+    check_jump_chain(pool, None, machine.jump_host_id).await?;
 
     let result = sqlx::query(
-        "INSERT INTO machines (name, host, username, ssh_key_id, port, platform) VALUES (?, ?, ?, ?, ?, ?)",
+        "INSERT INTO machines (name, host, username, ssh_key_id, port, platform, auth_method, jump_host_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
     )
-    .bind(name)
-    .bind(host)
-    .bind(username)
+    .bind(&machine.name)
+    .bind(&machine.host)
+    .bind(&machine.username)
     .bind(ssh_key_id)
-    .bind(port)
-    .bind(platform)
+    .bind(machine.port)
+    .bind(&machine.platform)
+    .bind(&machine.auth_method)
+    .bind(machine.jump_host_id)
     .execute(pool)
     .await
     .map_err(|e| format!("Failed to add machine: {}", e))?;
@@ -50,14 +183,9 @@ pub async fn add_machine(
 }
 
 pub async fn list_machines(pool: &Pool<Sqlite>) -> Result<Vec<Machine>, String> {
-    // Ensure column exists for list query too
-    let _ = sqlx::query("ALTER TABLE machines ADD COLUMN platform TEXT DEFAULT 'linux'")
-        .execute(pool)
-        .await;
-
     println!("[DB] Listing machines...");
     let rows = sqlx::query(
-        "SELECT id, name, host, username, ssh_key_id, port, platform, created_at FROM machines"
+        "SELECT id, name, host, username, ssh_key_id, port, platform, auth_method, jump_host_id, created_at FROM machines"
     )
     .fetch_all(pool)
     .await
@@ -65,24 +193,24 @@ pub async fn list_machines(pool: &Pool<Sqlite>) -> Result<Vec<Machine>, String>
 
     println!("[DB] Found {} machines", rows.len());
 
-    let mut machines = Vec::new();
-    for row in rows {
-        machines.push(Machine {
-            id: row.try_get("id").unwrap_or_default(),
-            name: row.try_get("name").unwrap_or_default(),
-            host: row.try_get("host").unwrap_or_default(),
-            username: row.try_get("username").unwrap_or_default(),
-            ssh_key_id: row.try_get("ssh_key_id").unwrap_or_default(),
-            port: row.try_get("port").unwrap_or_default(),
-            platform: row.try_get("platform").unwrap_or("linux".to_string()),
-            created_at: row.try_get("created_at").unwrap_or_default(),
-        });
-    }
-
-    Ok(machines)
+    Ok(rows.iter().map(row_to_machine).collect())
 }
 
 pub async fn delete_machine(pool: &Pool<Sqlite>, id: i64) -> Result<(), String> {
+    // Check if any machines tunnel through this one
+    let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM machines WHERE jump_host_id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    if count.0 > 0 {
+        return Err(format!(
+            "Cannot delete machine: {} machine(s) use it as their jump host",
+            count.0
+        ));
+    }
+
     sqlx::query("DELETE FROM machines WHERE id = ?")
         .bind(id)
         .execute(pool)
@@ -93,7 +221,7 @@ pub async fn delete_machine(pool: &Pool<Sqlite>, id: i64) -> Result<(), String>
 
 pub async fn get_machine(pool: &Pool<Sqlite>, id: i64) -> Result<Machine, String> {
     let row = sqlx::query(
-        "SELECT id, name, host, username, ssh_key_id, port, platform, created_at FROM machines WHERE id = ?"
+        "SELECT id, name, host, username, ssh_key_id, port, platform, auth_method, jump_host_id, created_at FROM machines WHERE id = ?"
     )
     .bind(id)
     .fetch_optional(pool)
@@ -101,37 +229,24 @@ pub async fn get_machine(pool: &Pool<Sqlite>, id: i64) -> Result<Machine, String
     .map_err(|e| format!("Failed to get machine: {}", e))?
     .ok_or_else(|| "Machine not found".to_string())?;
 
-    Ok(Machine {
-        id: row.try_get("id").unwrap_or_default(),
-        name: row.try_get("name").unwrap_or_default(),
-        host: row.try_get("host").unwrap_or_default(),
-        username: row.try_get("username").unwrap_or_default(),
-        ssh_key_id: row.try_get("ssh_key_id").unwrap_or_default(),
-        port: row.try_get("port").unwrap_or_default(),
-        platform: row.try_get("platform").unwrap_or("linux".to_string()),
-        created_at: row.try_get("created_at").unwrap_or_default(),
-    })
+    Ok(row_to_machine(&row))
 }
 
-pub async fn update_machine(
-    pool: &Pool<Sqlite>,
-    id: i64,
-    name: &str,
-    host: &str,
-    username: &str,
-    ssh_key_id: i64,
-    port: i64,
-    platform: &str,
-) -> Result<(), String> {
+pub async fn update_machine(pool: &Pool<Sqlite>, id: i64, machine: &NewMachine) -> Result<(), String> {
+    let ssh_key_id = resolve_auth(&machine.auth_method, machine.ssh_key_id)?;
+    check_jump_chain(pool, Some(id), machine.jump_host_id).await?;
+
     sqlx::query(
-        "UPDATE machines SET name = ?, host = ?, username = ?, ssh_key_id = ?, port = ?, platform = ? WHERE id = ?"
+        "UPDATE machines SET name = ?, host = ?, username = ?, ssh_key_id = ?, port = ?, platform = ?, auth_method = ?, jump_host_id = ? WHERE id = ?"
     )
-    .bind(name)
-    .bind(host)
-    .bind(username)
+    .bind(&machine.name)
+    .bind(&machine.host)
+    .bind(&machine.username)
     .bind(ssh_key_id)
-    .bind(port)
-    .bind(platform)
+    .bind(machine.port)
+    .bind(&machine.platform)
+    .bind(&machine.auth_method)
+    .bind(machine.jump_host_id)
     .bind(id)
     .execute(pool)
     .await
@@ -139,3 +254,100 @@ pub async fn update_machine(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    fn new_machine(name: &str, ssh_key_id: Option<i64>, auth_method: &str, jump_host_id: Option<i64>) -> NewMachine {
+        NewMachine {
+            name: name.to_string(),
+            host: format!("{}.example.com", name),
+            username: "ops".to_string(),
+            ssh_key_id,
+            port: 22,
+            platform: "linux".to_string(),
+            auth_method: auth_method.to_string(),
+            jump_host_id,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_migrate_legacy_machines_table() {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::query("CREATE TABLE ssh_keys (id INTEGER PRIMARY KEY, name TEXT)")
+            .execute(&pool).await.unwrap();
+        sqlx::query("INSERT INTO ssh_keys (id, name) VALUES (1, 'work')")
+            .execute(&pool).await.unwrap();
+        sqlx::query(
+            "CREATE TABLE machines (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                host TEXT NOT NULL,
+                username TEXT NOT NULL,
+                ssh_key_id INTEGER NOT NULL,
+                port INTEGER NOT NULL DEFAULT 22,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (ssh_key_id) REFERENCES ssh_keys(id) ON DELETE RESTRICT
+            )",
+        )
+        .execute(&pool).await.unwrap();
+        sqlx::query("INSERT INTO machines (name, host, username, ssh_key_id, port) VALUES ('box', '10.0.0.5', 'root', 1, 22)")
+            .execute(&pool).await.unwrap();
+
+        migrate_machines_table(&pool).await.unwrap();
+        // Second run is a no-op
+        migrate_machines_table(&pool).await.unwrap();
+
+        let existing = get_machine(&pool, 1).await.unwrap();
+        assert_eq!(existing.ssh_key_id, Some(1));
+        assert_eq!(existing.auth_method, "key");
+        assert_eq!(existing.platform, "linux");
+
+        let id = add_machine(&pool, &new_machine("agent-box", Some(1), "agent", None)).await.unwrap();
+        let agent = get_machine(&pool, id).await.unwrap();
+        assert_eq!(agent.ssh_key_id, None);
+        assert_eq!(agent.auth_method, "agent");
+
+        assert!(add_machine(&pool, &new_machine("bad", None, "key", None)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_jump_host_cycles_are_rejected() {
+        let pool = SqlitePoolOptions::new()
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::query(
+            "CREATE TABLE machines (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                host TEXT NOT NULL,
+                username TEXT NOT NULL,
+                ssh_key_id INTEGER,
+                port INTEGER NOT NULL DEFAULT 22,
+                platform TEXT DEFAULT 'linux',
+                auth_method TEXT NOT NULL DEFAULT 'key',
+                jump_host_id INTEGER REFERENCES machines(id),
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )",
+        )
+        .execute(&pool).await.unwrap();
+
+        let bastion = add_machine(&pool, &new_machine("bastion", None, "agent", None)).await.unwrap();
+        let inner = add_machine(&pool, &new_machine("inner", None, "agent", Some(bastion))).await.unwrap();
+        assert_eq!(get_machine(&pool, inner).await.unwrap().jump_host_id, Some(bastion));
+
+        // bastion -> inner -> bastion
+        let looped = update_machine(&pool, bastion, &new_machine("bastion", None, "agent", Some(inner))).await;
+        assert!(looped.is_err());
+        assert!(add_machine(&pool, &new_machine("orphan", None, "agent", Some(999))).await.is_err());
+        assert!(delete_machine(&pool, bastion).await.is_err());
+    }
+}
@@ -1,11 +1,17 @@
-use sqlx::{sqlite::SqliteConnectOptions, Pool, Sqlite, SqlitePool};
+use sqlx::{sqlite::SqliteConnectOptions, Pool, Row, Sqlite, SqlitePool};
 use std::str::FromStr;
 use tauri::{AppHandle, Manager};
 use std::fs;
 
+pub mod backups;
+pub mod carried_fields;
+pub mod inventory;
+pub mod known_hosts;
 pub mod machines;
+pub mod settings;
 pub mod ssh_keys;
 pub mod sync_history;
+pub mod workspace_roots;
 
 pub async fn init_db(app_handle: &AppHandle) -> Result<Pool<Sqlite>, String> {
     let app_dir = app_handle
@@ -28,6 +34,12 @@ pub async fn init_db(app_handle: &AppHandle) -> Result<Pool<Sqlite>, String> {
         .await
         .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
+    migrate(&pool).await?;
+    Ok(pool)
+}
+
+/// Create or upgrade every table of the app database
+pub(crate) async fn migrate(pool: &Pool<Sqlite>) -> Result<(), String> {
     // Create ssh_keys table (ENCRYPTED storage with AES-GCM)
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS ssh_keys (
@@ -35,30 +47,51 @@ pub async fn init_db(app_handle: &AppHandle) -> Result<Pool<Sqlite>, String> {
             name TEXT NOT NULL UNIQUE,
             private_key_encrypted TEXT NOT NULL,
             iv TEXT NOT NULL,
+            is_encrypted INTEGER NOT NULL DEFAULT 0,
+            passphrase_encrypted TEXT,
+            passphrase_iv TEXT,
             created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
         );",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| format!("Failed to run migrations (ssh_keys): {}", e))?;
 
-    // Create machines table with reference to ssh_key
+    // Passphrase-protected key columns for databases created before them
+    for column in [
+        "is_encrypted INTEGER NOT NULL DEFAULT 0",
+        "passphrase_encrypted TEXT",
+        "passphrase_iv TEXT",
+    ] {
+        add_column_if_missing(pool, "ssh_keys", column).await?;
+    }
+
+    // Create machines table with optional reference to ssh_key (agent-authenticated machines have none)
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS machines (
             id INTEGER PRIMARY KEY,
             name TEXT NOT NULL,
             host TEXT NOT NULL,
             username TEXT NOT NULL,
-            ssh_key_id INTEGER NOT NULL,
+            ssh_key_id INTEGER,
             port INTEGER NOT NULL DEFAULT 22,
+            platform TEXT DEFAULT 'linux',
+            auth_method TEXT NOT NULL DEFAULT 'key' CHECK(auth_method IN ('key', 'agent')),
+            jump_host_id INTEGER REFERENCES machines(id),
             created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
             FOREIGN KEY (ssh_key_id) REFERENCES ssh_keys(id) ON DELETE RESTRICT
         );",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| format!("Failed to run migrations (machines): {}", e))?;
 
+    // Databases created before agent auth have ssh_key_id NOT NULL and no platform/auth_method columns
+    machines::migrate_machines_table(pool).await?;
+
+    // Jump host (bastion) reference, added after the rebuild above
+    add_column_if_missing(pool, "machines", "jump_host_id INTEGER REFERENCES machines(id)").await?;
+
     // Create sync_history table
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS sync_history (
@@ -67,17 +100,200 @@ pub async fn init_db(app_handle: &AppHandle) -> Result<Pool<Sqlite>, String> {
             target_id TEXT NOT NULL,
             server_name TEXT NOT NULL,
             last_hash TEXT NOT NULL,
-            last_synced_at TEXT NOT NULL
+            last_synced_at TEXT NOT NULL,
+            snapshot TEXT
         );",
     )
-    .execute(&pool)
+    .execute(pool)
     .await
     .map_err(|e| format!("Failed to run migrations (sync_history): {}", e))?;
 
+    // Canonical server as last synced, the base of three-way merges
+    add_column_if_missing(pool, "sync_history", "snapshot TEXT").await?;
+
+    // Create known_hosts table (trust-on-first-use host key fingerprints, per host and jump route)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS known_hosts (
+            id INTEGER PRIMARY KEY,
+            host TEXT NOT NULL,
+            port INTEGER NOT NULL,
+            via TEXT NOT NULL DEFAULT '',
+            key_type TEXT NOT NULL,
+            fingerprint TEXT NOT NULL,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(host, port, via)
+        );",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to run migrations (known_hosts): {}", e))?;
+
+    // Key host keys by jump route for databases created before jump hosts
+    known_hosts::migrate_known_hosts_table(pool).await?;
+
+    // Create backups table (catalog of config backups taken before each write, local or on a machine)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS backups (
+            id INTEGER PRIMARY KEY,
+            machine_id INTEGER REFERENCES machines(id) ON DELETE CASCADE,
+            path TEXT NOT NULL,
+            backup_path TEXT NOT NULL,
+            content_hash TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            reason TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        );",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to run migrations (backups): {}", e))?;
+
+    // Create settings table (key/value app preferences such as the backup retention policy)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to run migrations (settings): {}", e))?;
+
+    // Create workspace_roots table (directories searched for project .mcp.json files)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS workspace_roots (
+            id INTEGER PRIMARY KEY,
+            machine_id INTEGER REFERENCES machines(id) ON DELETE CASCADE,
+            path TEXT NOT NULL,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to run migrations (workspace_roots): {}", e))?;
+
+    // Create inventory tables (tools, resources and prompts each server listed when probed, one snapshot per change)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS inventory_snapshots (
+            id INTEGER PRIMARY KEY,
+            machine_id INTEGER REFERENCES machines(id) ON DELETE CASCADE,
+            source TEXT NOT NULL,
+            server_name TEXT NOT NULL,
+            probed_at INTEGER NOT NULL,
+            last_probed_at INTEGER NOT NULL,
+            stale_since INTEGER
+        );",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to run migrations (inventory_snapshots): {}", e))?;
+
+    // First failed probe since the inventory was last confirmed
+    add_column_if_missing(pool, "inventory_snapshots", "stale_since INTEGER").await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS inventory_items (
+            id INTEGER PRIMARY KEY,
+            snapshot_id INTEGER NOT NULL REFERENCES inventory_snapshots(id) ON DELETE CASCADE,
+            kind TEXT NOT NULL CHECK(kind IN ('tool', 'resource', 'prompt')),
+            name TEXT NOT NULL,
+            description TEXT,
+            definition TEXT NOT NULL
+        );",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to run migrations (inventory_items): {}", e))?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_inventory_items_name ON inventory_items(kind, name)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to run migrations (idx_inventory_items_name): {}", e))?;
+
+    // Create carried_fields table (server fields a client's config can't hold, kept for converting back)
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS carried_fields (
+            id INTEGER PRIMARY KEY,
+            machine_id INTEGER REFERENCES machines(id) ON DELETE CASCADE,
+            source TEXT NOT NULL,
+            path TEXT NOT NULL,
+            server_name TEXT NOT NULL,
+            fields TEXT NOT NULL,
+            updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );",
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to run migrations (carried_fields): {}", e))?;
+
     // Drop old credentials table (no longer needed)
     let _ = sqlx::query("DROP TABLE IF EXISTS credentials")
-        .execute(&pool)
+        .execute(pool)
         .await;
 
-    Ok(pool)
+    Ok(())
+}
+
+/// Add a column to a table created before it existed. `definition` starts with the column name.
+/// In-memory database with every table, as `init_db` leaves it
+#[cfg(test)]
+pub(crate) async fn test_pool() -> Pool<Sqlite> {
+    let pool = sqlx::sqlite::SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+    migrate(&pool).await.unwrap();
+    pool
+}
+
+/// A machine row for tests that record data against a machine id
+#[cfg(test)]
+pub(crate) async fn insert_test_machine(pool: &Pool<Sqlite>, id: i64) {
+    sqlx::query("INSERT INTO machines (id, name, host, username, auth_method) VALUES (?, ?, 'box', 'dev', 'agent')")
+        .bind(id)
+        .bind(format!("machine-{}", id))
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+async fn add_column_if_missing(pool: &Pool<Sqlite>, table: &str, definition: &str) -> Result<(), String> {
+    let column = definition.split_whitespace().next().unwrap_or(definition);
+    let columns = sqlx::query(&format!("PRAGMA table_info({})", table))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to inspect {} table: {}", table, e))?;
+
+    if columns.iter().any(|c| c.try_get::<String, _>("name").unwrap_or_default() == column) {
+        return Ok(());
+    }
+
+    sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {}", table, definition))
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to run migrations ({}.{}): {}", table, column, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn test_add_column_if_missing() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::query("CREATE TABLE sync_history (id INTEGER PRIMARY KEY, server_name TEXT NOT NULL)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO sync_history (server_name) VALUES ('fs')").execute(&pool).await.unwrap();
+
+        add_column_if_missing(&pool, "sync_history", "snapshot TEXT").await.unwrap();
+        // Already there: nothing to do
+        add_column_if_missing(&pool, "sync_history", "snapshot TEXT").await.unwrap();
+        sqlx::query("SELECT snapshot FROM sync_history").fetch_all(&pool).await.unwrap();
+
+        // Real failures are reported rather than taken for an existing column
+        assert!(add_column_if_missing(&pool, "sync_history", "broken INTEGER NOT NULL").await.is_err());
+        assert!(add_column_if_missing(&pool, "missing_table", "snapshot TEXT").await.is_err());
+    }
 }
@@ -0,0 +1,26 @@
+use sqlx::{Pool, Row, Sqlite};
+
+/// App-wide settings stored as key/value text (structured values are JSON)
+pub async fn get_setting(pool: &Pool<Sqlite>, key: &str) -> Result<Option<String>, String> {
+    let row = sqlx::query("SELECT value FROM settings WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to get setting '{}': {}", key, e))?;
+
+    Ok(row.map(|r| r.try_get("value").unwrap_or_default()))
+}
+
+pub async fn set_setting(pool: &Pool<Sqlite>, key: &str, value: &str) -> Result<(), String> {
+    sqlx::query(
+        "INSERT INTO settings (key, value) VALUES (?, ?)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value"
+    )
+    .bind(key)
+    .bind(value)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save setting '{}': {}", key, e))?;
+
+    Ok(())
+}
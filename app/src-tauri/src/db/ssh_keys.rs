@@ -11,6 +11,7 @@ pub struct SshKey {
     pub private_key_encrypted: String,
     #[serde(skip)]  // Never serialize the IV to frontend
     pub iv: String,
+    pub is_encrypted: bool,
     pub created_at: String,
 }
 
@@ -18,25 +19,50 @@ pub struct SshKey {
 pub struct SshKeyListItem {
     pub id: i64,
     pub name: String,
+    pub is_encrypted: bool,          // Key file itself is passphrase-protected
+    pub has_stored_passphrase: bool, // Passphrase saved (encrypted) so no prompt is needed
     pub created_at: String,
 }
 
+/// Decrypted key material needed to authenticate with a stored key
+pub struct SshKeyCredentials {
+    pub name: String,
+    pub private_key: String,
+    pub is_encrypted: bool,
+    pub passphrase: Option<String>, // Stored passphrase, if the user chose to remember it
+}
+
 /// Add a new SSH key with encryption
 pub async fn add_ssh_key(
     pool: &Pool<Sqlite>,
     name: &str,
     private_key: &str,
+    is_encrypted: bool,
+    passphrase: Option<&str>,
     master_key: &[u8; 32],
 ) -> Result<i64, String> {
     // Encrypt the private key
     let (encrypted, iv) = encryption::encrypt(private_key, master_key)?;
+
+    // The passphrase gets its own nonce under the same master key
+    let (passphrase_encrypted, passphrase_iv) = match passphrase {
+        Some(p) => {
+            let (enc, iv) = encryption::encrypt(p, master_key)?;
+            (Some(enc), Some(iv))
+        }
+        None => (None, None),
+    };
     
     let result = sqlx::query(
-        "INSERT INTO ssh_keys (name, private_key_encrypted, iv) VALUES (?, ?, ?)"
+        "INSERT INTO ssh_keys (name, private_key_encrypted, iv, is_encrypted, passphrase_encrypted, passphrase_iv)
+         VALUES (?, ?, ?, ?, ?, ?)"
     )
     .bind(name)
     .bind(&encrypted)
     .bind(&iv)
+    .bind(is_encrypted)
+    .bind(&passphrase_encrypted)
+    .bind(&passphrase_iv)
     .execute(pool)
     .await
     .map_err(|e| {
@@ -53,7 +79,8 @@ pub async fn add_ssh_key(
 /// Get all SSH keys (without decrypting private keys)
 pub async fn list_ssh_keys(pool: &Pool<Sqlite>) -> Result<Vec<SshKeyListItem>, String> {
     let rows = sqlx::query(
-        "SELECT id, name, created_at FROM ssh_keys ORDER BY created_at DESC"
+        "SELECT id, name, is_encrypted, passphrase_encrypted IS NOT NULL AS has_stored_passphrase, created_at
+         FROM ssh_keys ORDER BY created_at DESC"
     )
     .fetch_all(pool)
     .await
@@ -63,6 +90,8 @@ pub async fn list_ssh_keys(pool: &Pool<Sqlite>) -> Result<Vec<SshKeyListItem>, S
         SshKeyListItem {
             id: row.get("id"),
             name: row.get("name"),
+            is_encrypted: row.try_get("is_encrypted").unwrap_or_default(),
+            has_stored_passphrase: row.try_get("has_stored_passphrase").unwrap_or_default(),
             created_at: row.get("created_at"),
         }
     }).collect();
@@ -98,6 +127,65 @@ pub async fn get_ssh_key(
     Ok(decrypted)
 }
 
+/// Get a key together with its encryption state and stored passphrase (all decrypted)
+pub async fn get_ssh_key_credentials(
+    pool: &Pool<Sqlite>,
+    key_id: i64,
+    master_key: &[u8; 32],
+) -> Result<SshKeyCredentials, String> {
+    let row = sqlx::query(
+        "SELECT name, private_key_encrypted, iv, is_encrypted, passphrase_encrypted, passphrase_iv
+         FROM ssh_keys WHERE id = ?"
+    )
+    .bind(key_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?
+    .ok_or_else(|| format!("SSH key with ID {} not found", key_id))?;
+
+    let private_key = encryption::decrypt(row.get("private_key_encrypted"), row.get("iv"), master_key)?;
+
+    let passphrase_encrypted: Option<String> = row.try_get("passphrase_encrypted").unwrap_or_default();
+    let passphrase_iv: Option<String> = row.try_get("passphrase_iv").unwrap_or_default();
+    let passphrase = match (passphrase_encrypted, passphrase_iv) {
+        (Some(enc), Some(iv)) => Some(encryption::decrypt(&enc, &iv, master_key)?),
+        _ => None,
+    };
+
+    Ok(SshKeyCredentials {
+        name: row.get("name"),
+        private_key,
+        is_encrypted: row.try_get("is_encrypted").unwrap_or_default(),
+        passphrase,
+    })
+}
+
+/// Store (Some) or clear (None) the remembered passphrase of a key
+pub async fn set_ssh_key_passphrase(
+    pool: &Pool<Sqlite>,
+    key_id: i64,
+    passphrase: Option<&str>,
+    master_key: &[u8; 32],
+) -> Result<(), String> {
+    let (encrypted, iv) = match passphrase {
+        Some(p) => {
+            let (enc, iv) = encryption::encrypt(p, master_key)?;
+            (Some(enc), Some(iv))
+        }
+        None => (None, None),
+    };
+
+    sqlx::query("UPDATE ssh_keys SET passphrase_encrypted = ?, passphrase_iv = ? WHERE id = ?")
+        .bind(&encrypted)
+        .bind(&iv)
+        .bind(key_id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(())
+}
+
 /// Get SSH key by name and decrypt
 #[allow(dead_code)]
 pub async fn get_ssh_key_by_name(
@@ -127,6 +215,17 @@ pub async fn get_ssh_key_by_name(
     Ok(decrypted)
 }
 
+/// Find a key id by its (unique) name
+pub async fn find_ssh_key_id_by_name(pool: &Pool<Sqlite>, name: &str) -> Result<Option<i64>, String> {
+    let row: Option<(i64,)> = sqlx::query_as("SELECT id FROM ssh_keys WHERE name = ?")
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(row.map(|r| r.0))
+}
+
 /// Delete an SSH key
 pub async fn delete_ssh_key(pool: &Pool<Sqlite>, key_id: i64) -> Result<(), String> {
     // Check if any machines are using this key
@@ -175,3 +274,28 @@ pub async fn rename_ssh_key(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    #[tokio::test]
+    async fn test_stored_passphrase_round_trip() {
+        let pool = test_pool().await;
+
+        let master = [7u8; 32];
+        let id = add_ssh_key(&pool, "work", "KEY", true, Some("hunter2"), &master).await.unwrap();
+
+        let creds = get_ssh_key_credentials(&pool, id, &master).await.unwrap();
+        assert!(creds.is_encrypted);
+        assert_eq!(creds.private_key, "KEY");
+        assert_eq!(creds.passphrase.as_deref(), Some("hunter2"));
+
+        set_ssh_key_passphrase(&pool, id, None, &master).await.unwrap();
+        let listed = list_ssh_keys(&pool).await.unwrap();
+        assert!(listed[0].is_encrypted);
+        assert!(!listed[0].has_stored_passphrase);
+        assert!(get_ssh_key_credentials(&pool, id, &master).await.unwrap().passphrase.is_none());
+    }
+}
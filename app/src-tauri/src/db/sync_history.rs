@@ -9,6 +9,7 @@ pub struct SyncHistory {
     pub server_name: String,
     pub last_hash: String,  // MD5 hash
     pub last_synced_at: String,
+    pub snapshot: Option<String>, // Canonical server JSON as synced; None for rows recorded before snapshots
 }
 
 pub async fn get_sync_history(
@@ -18,7 +19,7 @@ pub async fn get_sync_history(
     server_name: &str,
 ) -> Result<Option<SyncHistory>, String> {
     let row = sqlx::query(
-        "SELECT id, scope, target_id, server_name, last_hash, last_synced_at 
+        "SELECT id, scope, target_id, server_name, last_hash, last_synced_at, snapshot
          FROM sync_history 
          WHERE scope = ? AND target_id = ? AND server_name = ?"
     )
@@ -37,6 +38,7 @@ pub async fn get_sync_history(
             server_name: row.try_get("server_name").unwrap_or_default(),
             last_hash: row.try_get("last_hash").unwrap_or_default(),
             last_synced_at: row.try_get("last_synced_at").unwrap_or_default(),
+            snapshot: row.try_get("snapshot").unwrap_or_default(),
         })),
         None => Ok(None),
     }
@@ -48,28 +50,31 @@ pub async fn upsert_sync_history(
     target_id: &str,
     server_name: &str,
     last_hash: &str,
+    snapshot: Option<&str>,
 ) -> Result<(), String> {
     // Check if exists
     let existing = get_sync_history(pool, scope, target_id, server_name).await?;
 
     if let Some(history) = existing {
         sqlx::query(
-            "UPDATE sync_history SET last_hash = ?, last_synced_at = datetime('now') WHERE id = ?"
+            "UPDATE sync_history SET last_hash = ?, snapshot = ?, last_synced_at = datetime('now') WHERE id = ?"
         )
         .bind(last_hash)
+        .bind(snapshot)
         .bind(history.id)
         .execute(pool)
         .await
         .map_err(|e| format!("Failed to update sync history: {}", e))?;
     } else {
         sqlx::query(
-            "INSERT INTO sync_history (scope, target_id, server_name, last_hash, last_synced_at) 
-             VALUES (?, ?, ?, ?, datetime('now'))"
+            "INSERT INTO sync_history (scope, target_id, server_name, last_hash, snapshot, last_synced_at)
+             VALUES (?, ?, ?, ?, ?, datetime('now'))"
         )
         .bind(scope)
         .bind(target_id)
         .bind(server_name)
         .bind(last_hash)
+        .bind(snapshot)
         .execute(pool)
         .await
         .map_err(|e| format!("Failed to insert sync history: {}", e))?;
@@ -77,3 +82,20 @@ pub async fn upsert_sync_history(
 
     Ok(())
 }
+
+pub async fn delete_sync_history(
+    pool: &Pool<Sqlite>,
+    scope: &str,
+    target_id: &str,
+    server_name: &str,
+) -> Result<(), String> {
+    sqlx::query("DELETE FROM sync_history WHERE scope = ? AND target_id = ? AND server_name = ?")
+        .bind(scope)
+        .bind(target_id)
+        .bind(server_name)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete sync history: {}", e))?;
+
+    Ok(())
+}
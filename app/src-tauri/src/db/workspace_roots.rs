@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Row, Sqlite};
+
+/// A directory searched for project `.mcp.json` files, locally (`machine_id: None`) or on a machine
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceRoot {
+    pub id: i64,
+    pub machine_id: Option<i64>,
+    pub path: String,
+    pub created_at: String,
+}
+
+fn row_to_root(row: &sqlx::sqlite::SqliteRow) -> WorkspaceRoot {
+    WorkspaceRoot {
+        id: row.try_get("id").unwrap_or_default(),
+        machine_id: row.try_get("machine_id").unwrap_or_default(),
+        path: row.try_get("path").unwrap_or_default(),
+        created_at: row.try_get("created_at").unwrap_or_default(),
+    }
+}
+
+pub async fn list_workspace_roots(pool: &Pool<Sqlite>, machine_id: Option<i64>) -> Result<Vec<WorkspaceRoot>, String> {
+    let rows = sqlx::query(
+        "SELECT id, machine_id, path, created_at FROM workspace_roots WHERE machine_id IS ? ORDER BY path"
+    )
+    .bind(machine_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to list workspace roots: {}", e))?;
+
+    Ok(rows.iter().map(row_to_root).collect())
+}
+
+/// Add a root unless the same path is already configured for that machine. Returns its id either way.
+pub async fn add_workspace_root(pool: &Pool<Sqlite>, machine_id: Option<i64>, path: &str) -> Result<i64, String> {
+    // UNIQUE doesn't apply across NULL machine ids, so check first
+    if let Some(existing) = list_workspace_roots(pool, machine_id).await?.into_iter().find(|r| r.path == path) {
+        return Ok(existing.id);
+    }
+
+    let result = sqlx::query("INSERT INTO workspace_roots (machine_id, path) VALUES (?, ?)")
+        .bind(machine_id)
+        .bind(path)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to add workspace root: {}", e))?;
+
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn delete_workspace_root(pool: &Pool<Sqlite>, id: i64) -> Result<(), String> {
+    sqlx::query("DELETE FROM workspace_roots WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Failed to delete workspace root: {}", e))?;
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// What a caller saw when it read a config file, used to detect changes made before its write
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileVersion {
+    /// SHA-256 of the content; `None` means the file did not exist
+    pub hash: Option<String>,
+    /// Modification time in seconds since the epoch, when the filesystem reports one
+    pub mtime: Option<u64>,
+}
+
+impl FileVersion {
+    pub fn of(content: &str, mtime: Option<u64>) -> Self {
+        FileVersion {
+            hash: Some(content_hash(content)),
+            mtime,
+        }
+    }
+
+    /// The version of a file that does not exist (yet)
+    pub fn missing() -> Self {
+        FileVersion::default()
+    }
+}
+
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "WRITE_CONFLICT: {path} was changed by another program since it was read. \
+    Nothing was written; reload the file and apply your change again."
+)]
+pub struct WriteConflict {
+    pub path: String,
+    pub expected: FileVersion,
+    pub actual: FileVersion,
+}
+
+impl From<WriteConflict> for String {
+    fn from(e: WriteConflict) -> Self {
+        e.to_string()
+    }
+}
+
+/// Reject the write when the file no longer matches what the caller read.
+/// The mtime is only compared when the caller recorded one.
+pub fn check_version(path: &str, expected: &FileVersion, actual: &FileVersion) -> Result<(), WriteConflict> {
+    let hash_changed = expected.hash != actual.hash;
+    let mtime_changed = expected.mtime.is_some() && expected.mtime != actual.mtime;
+
+    if hash_changed || mtime_changed {
+        println!("[Write] Conflict on {}: expected {:?}, found {:?}", path, expected, actual);
+        return Err(WriteConflict {
+            path: path.to_string(),
+            expected: expected.clone(),
+            actual: actual.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Sibling temp file name for an atomic replace of `path` (same directory, so rename stays on one filesystem)
+pub fn temp_path_for(path: &str) -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+
+    let split = path.rfind(['/', '\\']).map(|i| i + 1).unwrap_or(0);
+    let (dir, name) = path.split_at(split);
+    format!("{}.{}.mcphub-{}-{}.tmp", dir, name, std::process::id(), nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_version() {
+        let read = FileVersion::of("{}", Some(100));
+
+        assert!(check_version("f", &read, &FileVersion::of("{}", Some(100))).is_ok());
+        assert!(check_version("f", &read, &FileVersion::of("{\"a\":1}", Some(100))).is_err());
+        assert!(check_version("f", &read, &FileVersion::of("{}", Some(101))).is_err());
+        assert!(check_version("f", &read, &FileVersion::missing()).is_err());
+
+        // File that didn't exist must still not exist
+        assert!(check_version("f", &FileVersion::missing(), &FileVersion::missing()).is_ok());
+        let err: String = check_version("f", &FileVersion::missing(), &read).unwrap_err().into();
+        assert!(err.starts_with("WRITE_CONFLICT"));
+    }
+
+    #[test]
+    fn test_temp_path_is_sibling() {
+        let tmp = temp_path_for("/home/me/.claude.json");
+        assert!(tmp.starts_with("/home/me/..claude.json.mcphub-"));
+        assert!(tmp.ends_with(".tmp"));
+        assert!(temp_path_for("config.json").starts_with(".config.json.mcphub-"));
+    }
+}
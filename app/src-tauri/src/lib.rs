@@ -1,24 +1,41 @@
 mod commands;
 mod config;
 mod db;
+mod file_version;
+mod local_fs;
 mod ssh;
 mod sync;
 mod paths;
+mod probe;
 mod watcher;
 
 use commands::{
-    read_claude_config, read_opencode_config, get_default_config_paths,
+    read_claude_config,
+    read_opencode_config, get_default_config_paths,
     update_claude_server, update_opencode_server,
     disable_claude_server, enable_claude_server,
     delete_claude_server, delete_opencode_server,
     batch_toggle_claude_servers, batch_toggle_opencode_servers,
     test_ssh_connection,
+    review_host_key,
+    accept_host_key,
+    list_known_hosts,
     add_machine,
     list_machines,
     delete_machine,
     execute_remote_command,
     read_remote_claude_config,
     read_remote_opencode_config,
+    list_sources,
+    read_source_config,
+    validate_source_config,
+    probe_source_server,
+    get_server_inventory,
+    find_inventory_providers,
+    get_inventory_changes,
+    update_source_server,
+    delete_source_server,
+    toggle_source_servers,
     update_remote_claude_server,
     disable_remote_claude_server,
     enable_remote_claude_server,
@@ -28,15 +45,50 @@ use commands::{
     generate_sync_plan,
     apply_sync_opencode_to_claude,
     apply_sync_claude_to_opencode,
+    generate_cross_machine_sync_plan,
+    apply_cross_machine_sync,
+    generate_source_sync_plan,
+    apply_source_sync,
+    apply_cross_machine_merge,
+    apply_source_merge,
+    apply_sync_actions,
+    apply_cross_machine_actions,
+    apply_source_actions,
     add_ssh_key,
     list_ssh_keys,
     delete_ssh_key,
     rename_ssh_key,
     get_ssh_key_preview,
+    unlock_ssh_key,
+    forget_ssh_key_passphrase,
+    list_ssh_config_hosts,
+    import_ssh_config_hosts,
     get_host_platform,
     update_machine,
     nuclear_restart,
     check_environment,
+    apply_server_to_machines,
+    list_backups,
+    diff_backup,
+    restore_backup,
+    delete_backup,
+    get_backup_retention,
+    set_backup_retention,
+    apply_backup_retention,
+    list_claude_projects,
+    update_claude_project_server,
+    delete_claude_project_server,
+    toggle_claude_project_servers,
+    set_claude_project_mcpjson_servers,
+    sync_claude_project_servers,
+    list_workspace_roots,
+    add_workspace_root,
+    remove_workspace_root,
+    discover_mcp_json_files,
+    read_mcp_json,
+    update_mcp_json_server,
+    delete_mcp_json_server,
+    sync_mcp_json_servers,
 };
 use tauri::Manager;
 
@@ -49,7 +101,8 @@ pub fn run() {
             
             // Initialize watcher
             if let Some(paths) = paths::get_app_paths() {
-                watcher::watch_config_files(handle.clone(), vec![paths.claude_path, paths.opencode_path]);
+                let opencode_path = config::SourceKind::OpenCode.source().local_path(&paths);
+                watcher::watch_config_files(handle.clone(), vec![paths.claude_path, opencode_path]);
             }
 
             // Initialize SSH Connection Pool
@@ -75,6 +128,9 @@ pub fn run() {
             batch_toggle_claude_servers,
             batch_toggle_opencode_servers,
             test_ssh_connection,
+            review_host_key,
+            accept_host_key,
+            list_known_hosts,
             add_machine,
             list_machines,
             delete_machine,
@@ -82,6 +138,16 @@ pub fn run() {
             execute_remote_command,
             read_remote_claude_config,
             read_remote_opencode_config,
+            list_sources,
+            read_source_config,
+            validate_source_config,
+            probe_source_server,
+            get_server_inventory,
+            find_inventory_providers,
+            get_inventory_changes,
+            update_source_server,
+            delete_source_server,
+            toggle_source_servers,
             update_remote_claude_server,
             disable_remote_claude_server,
             enable_remote_claude_server,
@@ -91,14 +157,49 @@ pub fn run() {
             generate_sync_plan,
             apply_sync_opencode_to_claude,
             apply_sync_claude_to_opencode,
+            generate_cross_machine_sync_plan,
+            apply_cross_machine_sync,
+            generate_source_sync_plan,
+            apply_source_sync,
+            apply_cross_machine_merge,
+            apply_source_merge,
+            apply_sync_actions,
+            apply_cross_machine_actions,
+            apply_source_actions,
             add_ssh_key,
             list_ssh_keys,
             delete_ssh_key,
             rename_ssh_key,
             get_ssh_key_preview,
+            unlock_ssh_key,
+            forget_ssh_key_passphrase,
+            list_ssh_config_hosts,
+            import_ssh_config_hosts,
             get_host_platform,
             nuclear_restart,
             check_environment,
+            apply_server_to_machines,
+            list_backups,
+            diff_backup,
+            restore_backup,
+            delete_backup,
+            get_backup_retention,
+            set_backup_retention,
+            apply_backup_retention,
+            list_claude_projects,
+            update_claude_project_server,
+            delete_claude_project_server,
+            toggle_claude_project_servers,
+            set_claude_project_mcpjson_servers,
+            sync_claude_project_servers,
+            list_workspace_roots,
+            add_workspace_root,
+            remove_workspace_root,
+            discover_mcp_json_files,
+            read_mcp_json,
+            update_mcp_json_server,
+            delete_mcp_json_server,
+            sync_mcp_json_servers,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
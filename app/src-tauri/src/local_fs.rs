@@ -0,0 +1,147 @@
+use crate::file_version::{self, FileVersion};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Read a local file along with the version `write_atomic` should expect.
+/// A missing file is not an error: it yields `None` and `FileVersion::missing()`.
+pub fn read_versioned(path: &str) -> Result<(Option<String>, FileVersion), String> {
+    let target = resolve_target(path);
+    match fs::read_to_string(&target) {
+        Ok(content) => {
+            let version = FileVersion::of(&content, mtime_of(&target));
+            Ok((Some(content), version))
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok((None, FileVersion::missing())),
+        Err(e) => Err(format!("Failed to read file: {}", e)),
+    }
+}
+
+/// Replace a local file atomically: write a sibling temp file with the original permissions, fsync it
+/// and rename it over the target, so a crash never leaves a truncated config.
+///
+/// With `expected`, the write is refused with a `WriteConflict` when the file changed since it was read
+/// (Claude Code rewrites `.claude.json` all the time). Returns the version of the newly written file.
+pub fn write_atomic(path: &str, content: &str, expected: Option<&FileVersion>) -> Result<FileVersion, String> {
+    let target = resolve_target(path);
+
+    if let Some(expected) = expected {
+        let (_, current) = read_versioned(path)?;
+        file_version::check_version(path, expected, &current)?;
+    }
+
+    if let Some(parent) = target.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+
+    let tmp_path = PathBuf::from(file_version::temp_path_for(&target.to_string_lossy()));
+    let written = write_temp(&tmp_path, &target, content.as_bytes())
+        .and_then(|_| fs::rename(&tmp_path, &target))
+        .map_err(|e| format!("Failed to write file: {}", e));
+
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    written?;
+    sync_parent_dir(&target);
+
+    Ok(FileVersion::of(content, mtime_of(&target)))
+}
+
+/// Write through symlinks (e.g. dotfiles managed elsewhere) instead of replacing the link with a file
+fn resolve_target(path: &str) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+}
+
+fn mtime_of(path: &Path) -> Option<u64> {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+}
+
+fn write_temp(tmp_path: &Path, target: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        // New files may hold API keys in server env vars, so they start private
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(tmp_path)?;
+    if let Ok(meta) = fs::metadata(target) {
+        file.set_permissions(meta.permissions())?;
+    }
+    file.write_all(data)?;
+    file.sync_all()
+}
+
+/// Make the rename itself durable; not possible (or needed) on every platform
+fn sync_parent_dir(target: &Path) {
+    #[cfg(unix)]
+    if let Some(parent) = target.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if let Ok(dir) = fs::File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = target;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic_detects_concurrent_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("claude.json").to_string_lossy().to_string();
+        fs::write(&path, "{}").unwrap();
+
+        let (content, version) = read_versioned(&path).unwrap();
+        assert_eq!(content.as_deref(), Some("{}"));
+
+        // Another program rewrites the file between our read and write
+        fs::write(&path, r#"{"projects":{}}"#).unwrap();
+        let err = write_atomic(&path, r#"{"mcpServers":{}}"#, Some(&version)).unwrap_err();
+        assert!(err.starts_with("WRITE_CONFLICT"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), r#"{"projects":{}}"#);
+
+        let (_, version) = read_versioned(&path).unwrap();
+        write_atomic(&path, r#"{"mcpServers":{}}"#, Some(&version)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), r#"{"mcpServers":{}}"#);
+
+        // Only the target is left behind, no temp files
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_atomic_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("opencode.json").to_string_lossy().to_string();
+
+        let (content, version) = read_versioned(&path).unwrap();
+        assert!(content.is_none());
+        write_atomic(&path, "{}", Some(&version)).unwrap();
+
+        // Expecting it to still be missing now conflicts
+        assert!(write_atomic(&path, "{}", Some(&FileVersion::missing())).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("claude.json");
+        fs::write(&path, "{}").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        write_atomic(&path.to_string_lossy(), "{\"a\":1}", None).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o640);
+    }
+}
@@ -1,17 +1,44 @@
+use crate::config::CLAUDE_DESKTOP_FILE_NAME;
 use std::path::PathBuf;
 
 pub struct AppPaths {
     pub claude_path: PathBuf,
+    pub claude_desktop_path: PathBuf,
+    pub codex_path: PathBuf,
+    pub cursor_path: PathBuf,
+    pub gemini_path: PathBuf,
+    pub vscode_path: PathBuf,
+    pub vscode_settings_path: PathBuf,
     pub opencode_path: PathBuf,
 }
 
 pub fn get_app_paths() -> Option<AppPaths> {
     let home = dirs::home_dir()?;
     let claude_path = home.join(".claude.json");
+    // %APPDATA%, ~/Library/Application Support or ~/.config, depending on the OS
+    let config_dir = dirs::config_dir().unwrap_or_else(|| home.join(".config"));
+    let claude_desktop_path = config_dir.join("Claude").join(CLAUDE_DESKTOP_FILE_NAME);
+    // Codex honours CODEX_HOME, defaulting to ~/.codex
+    let codex_path = std::env::var_os("CODEX_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".codex"))
+        .join("config.toml");
+    let cursor_path = home.join(".cursor").join("mcp.json");
+    let gemini_path = home.join(".gemini").join("settings.json");
+    // VS Code moved MCP servers from settings.json (`mcp.servers`) to a dedicated mcp.json in the user profile
+    let vscode_user_dir = config_dir.join("Code").join("User");
+    let vscode_path = vscode_user_dir.join("mcp.json");
+    let vscode_settings_path = vscode_user_dir.join("settings.json");
     let opencode_path = home.join(".config").join("opencode").join("opencode.json");
 
     Some(AppPaths {
         claude_path,
+        claude_desktop_path,
+        codex_path,
+        cursor_path,
+        gemini_path,
+        vscode_path,
+        vscode_settings_path,
         opencode_path,
     })
 }
@@ -0,0 +1,955 @@
+use super::{probe, FailureKind, McpTransport, ProbeError, ProbeReport};
+use crate::config::converter::Platform;
+use crate::config::{McpServer, Transport};
+use crate::ssh::connection::{self, SshTarget};
+use crate::ssh::pool::SshPool;
+use crate::ssh::tunnel;
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use serde_json::Value;
+use ssh2::Session;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use url::{Host, Url};
+
+/// Cap on a response head or a non-streaming body
+const MAX_RESPONSE: usize = 4 * 1024 * 1024;
+
+/// How TCP connections to the server are made
+#[derive(Clone)]
+pub enum Dialer {
+    /// From this host
+    Direct,
+    /// From a machine, over `direct-tcpip` channels on its SSH session, so the machine's DNS and routes are
+    /// tested. The machine resolves the name itself, so a lookup failure there shows up as a refused channel
+    /// and the name is looked up on the machine (per its platform) to tell the two apart.
+    Ssh(Arc<Mutex<Session>>, Platform),
+}
+
+impl Dialer {
+    fn dial(&self, host: &str, port: u16, timeout: Duration) -> Result<TcpStream, ProbeError> {
+        match self {
+            Dialer::Direct => {
+                let addrs: Vec<_> = (host, port)
+                    .to_socket_addrs()
+                    .map_err(|e| ProbeError::new(FailureKind::Dns, format!("Could not resolve {}: {}", host, e)))?
+                    .collect();
+                if addrs.is_empty() {
+                    return Err(ProbeError::new(FailureKind::Dns, format!("{} has no addresses", host)));
+                }
+                let mut last_error = None;
+                for addr in addrs {
+                    match TcpStream::connect_timeout(&addr, timeout) {
+                        Ok(stream) => return Ok(stream),
+                        Err(e) => last_error = Some(e),
+                    }
+                }
+                let e = last_error.expect("at least one address was tried");
+                Err(io_error(e, FailureKind::Connect, &format!("Could not connect to {}:{}", host, port)))
+            }
+            Dialer::Ssh(session, platform) => tunnel::open_tunnel(session.clone(), host, port).map_err(|e| {
+                let kind = if lookup_fails(session, host, *platform) { FailureKind::Dns } else { FailureKind::Connect };
+                ProbeError::new(kind, e)
+            }),
+        }
+    }
+}
+
+/// Whether `host` doesn't resolve on the machine behind `session` (exit status 2 of the lookup below).
+/// False when it resolves, is an IP address, or can't be looked up there.
+fn lookup_fails(session: &Mutex<Session>, host: &str, platform: Platform) -> bool {
+    let is_name = host.chars().any(|c| c.is_ascii_alphabetic())
+        && host.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_');
+    if !is_name {
+        return false;
+    }
+    let command = match platform {
+        Platform::Windows => format!(
+            "powershell -NoProfile -Command \"try {{ [System.Net.Dns]::GetHostAddresses('{}') | Out-Null }} catch {{ exit 2 }}\"",
+            host
+        ),
+        Platform::Linux => format!(
+            "if command -v getent >/dev/null 2>&1; then getent hosts {0} >/dev/null 2>&1 || exit 2; \
+             elif command -v host >/dev/null 2>&1; then host {0} >/dev/null 2>&1 || exit 2; fi",
+            host
+        ),
+    };
+    connection::exit_status_of(session, &command) == Ok(2)
+}
+
+/// An I/O failure as a probe error: timeouts are their own kind, anything else is `kind`
+fn io_error(e: io::Error, kind: FailureKind, what: &str) -> ProbeError {
+    let kind = match e.kind() {
+        ErrorKind::TimedOut | ErrorKind::WouldBlock => FailureKind::Timeout,
+        _ => kind,
+    };
+    ProbeError::new(kind, format!("{}: {}", what, e))
+}
+
+fn tls_config() -> Arc<ClientConfig> {
+    static CONFIG: OnceLock<Arc<ClientConfig>> = OnceLock::new();
+    CONFIG
+        .get_or_init(|| {
+            let roots = RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+            let config = ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .expect("ring supports the default protocol versions")
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+            Arc::new(config)
+        })
+        .clone()
+}
+
+enum Stream {
+    Plain(TcpStream),
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+}
+
+/// One HTTP/1.1 connection, plain or TLS. `socket` is a handle on the same socket for timeouts and shutdown.
+struct Connection {
+    stream: Stream,
+    socket: TcpStream,
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.stream {
+            Stream::Plain(s) => s.read(buf),
+            Stream::Tls(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.stream {
+            Stream::Plain(s) => s.write(buf),
+            Stream::Tls(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.stream {
+            Stream::Plain(s) => s.flush(),
+            Stream::Tls(s) => s.flush(),
+        }
+    }
+}
+
+fn url_host(url: &Url) -> Result<String, ProbeError> {
+    match url.host() {
+        Some(Host::Domain(domain)) => Ok(domain.to_string()),
+        Some(Host::Ipv4(ip)) => Ok(ip.to_string()),
+        Some(Host::Ipv6(ip)) => Ok(ip.to_string()),
+        None => Err(ProbeError::new(FailureKind::Connect, format!("{} has no host", url))),
+    }
+}
+
+fn connect(dialer: &Dialer, url: &Url, timeout: Duration) -> Result<Connection, ProbeError> {
+    let host = url_host(url)?;
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| ProbeError::new(FailureKind::Connect, format!("{} has no port", url)))?;
+    let socket = dialer.dial(&host, port, timeout)?;
+    let handle = socket
+        .try_clone()
+        .and_then(|h| h.set_read_timeout(Some(timeout)).and(h.set_write_timeout(Some(timeout))).map(|_| h))
+        .map_err(|e| io_error(e, FailureKind::Connect, "Failed to set up socket"))?;
+
+    let stream = if url.scheme() == "https" {
+        let name = ServerName::try_from(host.clone())
+            .map_err(|e| ProbeError::new(FailureKind::Tls, format!("Invalid TLS name {}: {}", host, e)))?;
+        let conn = ClientConnection::new(tls_config(), name)
+            .map_err(|e| ProbeError::new(FailureKind::Tls, format!("TLS setup failed: {}", e)))?;
+        let mut tls = StreamOwned::new(conn, socket);
+        while tls.conn.is_handshaking() {
+            tls.conn
+                .complete_io(&mut tls.sock)
+                .map_err(|e| io_error(e, FailureKind::Tls, &format!("TLS handshake with {} failed", host)))?;
+        }
+        Stream::Tls(Box::new(tls))
+    } else {
+        Stream::Plain(socket)
+    };
+    Ok(Connection { stream, socket: handle })
+}
+
+fn host_header(url: &Url) -> String {
+    match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        _ => String::new(),
+    }
+}
+
+fn write_request(conn: &mut Connection, method: &str, url: &Url, headers: &[(String, String)], body: Option<&str>) -> io::Result<()> {
+    let mut target = url.path().to_string();
+    if let Some(query) = url.query() {
+        target.push('?');
+        target.push_str(query);
+    }
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: mcp-control-hub/{}\r\nConnection: close\r\n",
+        method,
+        target,
+        host_header(url),
+        env!("CARGO_PKG_VERSION")
+    );
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if let Some(body) = body {
+        head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    head.push_str("\r\n");
+    conn.write_all(head.as_bytes())?;
+    if let Some(body) = body {
+        conn.write_all(body.as_bytes())?;
+    }
+    conn.flush()
+}
+
+enum Framing {
+    Length(u64),
+    Chunked,
+    UntilClose,
+}
+
+/// A response body, decoded from its framing as it is read
+struct Body {
+    reader: BufReader<Connection>,
+    framing: Framing,
+    /// Bytes left in the body (`Length`) or in the current chunk (`Chunked`)
+    remaining: u64,
+    done: bool,
+}
+
+impl Body {
+    fn socket(&self) -> Option<TcpStream> {
+        self.reader.get_ref().socket.try_clone().ok()
+    }
+
+    fn next_chunk_size(&mut self) -> io::Result<u64> {
+        let mut line = String::new();
+        self.reader.read_line(&mut line)?;
+        let size = line.trim().split(';').next().unwrap_or("");
+        u64::from_str_radix(size.trim(), 16).map_err(|_| io::Error::new(ErrorKind::InvalidData, "Bad chunk size"))
+    }
+}
+
+impl Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+        match self.framing {
+            Framing::UntilClose => self.reader.read(buf),
+            Framing::Length(_) => {
+                if self.remaining == 0 {
+                    self.done = true;
+                    return Ok(0);
+                }
+                let max = buf.len().min(self.remaining as usize);
+                let n = self.reader.read(&mut buf[..max])?;
+                if n == 0 {
+                    return Err(io::Error::new(ErrorKind::UnexpectedEof, "Connection closed mid-body"));
+                }
+                self.remaining -= n as u64;
+                Ok(n)
+            }
+            Framing::Chunked => {
+                if self.remaining == 0 {
+                    self.remaining = self.next_chunk_size()?;
+                    if self.remaining == 0 {
+                        // Trailers, then the blank line ending the body
+                        let mut line = String::new();
+                        while self.reader.read_line(&mut line)? > 0 && !line.trim().is_empty() {
+                            line.clear();
+                        }
+                        self.done = true;
+                        return Ok(0);
+                    }
+                }
+                let max = buf.len().min(self.remaining as usize);
+                let n = self.reader.read(&mut buf[..max])?;
+                if n == 0 {
+                    return Err(io::Error::new(ErrorKind::UnexpectedEof, "Connection closed mid-chunk"));
+                }
+                self.remaining -= n as u64;
+                if self.remaining == 0 {
+                    let mut crlf = String::new();
+                    self.reader.read_line(&mut crlf)?;
+                }
+                Ok(n)
+            }
+        }
+    }
+}
+
+struct Response {
+    status: u16,
+    reason: String,
+    headers: Vec<(String, String)>,
+    body: Body,
+}
+
+impl Response {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+
+    fn content_type(&self) -> String {
+        self.header("content-type").unwrap_or("").to_ascii_lowercase()
+    }
+
+    fn read_text(&mut self) -> io::Result<String> {
+        let mut bytes = Vec::new();
+        (&mut self.body).take(MAX_RESPONSE as u64).read_to_end(&mut bytes)?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /// Fail unless 2xx, keeping the start of the body (servers often say why there)
+    fn check_status(mut self) -> Result<Response, ProbeError> {
+        if (200..300).contains(&self.status) {
+            return Ok(self);
+        }
+        let kind = match self.status {
+            401 => FailureKind::Unauthorized,
+            403 => FailureKind::Forbidden,
+            _ => FailureKind::Http,
+        };
+        let body = self.read_text().unwrap_or_default();
+        let snippet: String = body.trim().chars().take(200).collect();
+        let mut message = format!("HTTP {} {}", self.status, self.reason);
+        if !snippet.is_empty() {
+            message.push_str(&format!(": {}", snippet));
+        }
+        Err(ProbeError { status: Some(self.status), ..ProbeError::new(kind, message) })
+    }
+}
+
+fn read_response(conn: Connection, method: &str) -> Result<Response, ProbeError> {
+    let mut reader = BufReader::new(conn);
+    let mut head = Vec::new();
+    loop {
+        let n = reader
+            .read_until(b'\n', &mut head)
+            .map_err(|e| io_error(e, FailureKind::Connect, "Failed to read response"))?;
+        if n == 0 {
+            return Err(ProbeError::new(FailureKind::Protocol, "Connection closed before a response"));
+        }
+        if head.ends_with(b"\r\n\r\n") || head.ends_with(b"\n\n") {
+            break;
+        }
+        if head.len() > MAX_RESPONSE {
+            return Err(ProbeError::new(FailureKind::Protocol, "Response head too large"));
+        }
+    }
+
+    let mut header_buf = [httparse::EMPTY_HEADER; 64];
+    let mut parsed = httparse::Response::new(&mut header_buf);
+    match parsed.parse(&head) {
+        Ok(httparse::Status::Complete(_)) => {}
+        _ => return Err(ProbeError::new(FailureKind::Protocol, "Not an HTTP response")),
+    }
+    let status = parsed.code.unwrap_or(0);
+    let reason = parsed.reason.unwrap_or("").to_string();
+    let headers: Vec<(String, String)> = parsed
+        .headers
+        .iter()
+        .map(|h| (h.name.to_string(), String::from_utf8_lossy(h.value).trim().to_string()))
+        .collect();
+
+    let find = |name: &str| headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.clone());
+    let framing = if method == "HEAD" || status == 204 || status == 304 {
+        Framing::Length(0)
+    } else if find("transfer-encoding").is_some_and(|v| v.to_ascii_lowercase().contains("chunked")) {
+        Framing::Chunked
+    } else if let Some(length) = find("content-length").and_then(|v| v.parse().ok()) {
+        Framing::Length(length)
+    } else {
+        Framing::UntilClose
+    };
+    let remaining = match framing {
+        Framing::Length(length) => length,
+        _ => 0,
+    };
+    Ok(Response { status, reason, headers, body: Body { reader, framing, remaining, done: false } })
+}
+
+/// One server-sent event. `event` is "message" when the server didn't name it.
+#[derive(Debug, PartialEq)]
+struct SseEvent {
+    event: String,
+    data: String,
+}
+
+/// The next event of an SSE stream, `None` at its end
+fn next_event(reader: &mut impl BufRead) -> io::Result<Option<SseEvent>> {
+    let mut event = String::new();
+    let mut data: Vec<String> = Vec::new();
+    let mut raw = String::new();
+    loop {
+        raw.clear();
+        if reader.read_line(&mut raw)? == 0 {
+            return Ok(None);
+        }
+        let line = raw.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            if data.is_empty() && event.is_empty() {
+                continue;
+            }
+            let event = if event.is_empty() { "message".to_string() } else { event };
+            return Ok(Some(SseEvent { event, data: data.join("\n") }));
+        }
+        if line.starts_with(':') {
+            continue;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => event = value.to_string(),
+            "data" => data.push(value.to_string()),
+            _ => {}
+        }
+    }
+}
+
+type Inbox = Result<SseEvent, ProbeError>;
+
+/// What every HTTP transport shares: how to reach the server, the configured headers, and the messages
+/// received so far (from JSON bodies or event streams read on their own threads)
+struct HttpSession {
+    dialer: Dialer,
+    headers: Vec<(String, String)>,
+    timeout: Duration,
+    tx: Sender<Inbox>,
+    events: Receiver<Inbox>,
+    queue: VecDeque<Value>,
+    /// Open event streams, shut down on close so their reader threads end
+    streams: Vec<TcpStream>,
+}
+
+impl HttpSession {
+    fn new(server: &McpServer, dialer: Dialer, timeout: Duration) -> Result<Self, ProbeError> {
+        let mut headers: Vec<(String, String)> = server.headers.clone().unwrap_or_default().into_iter().collect();
+        headers.sort();
+        // A line break would end the header early and let the rest pass as headers or a body of its own
+        for (name, value) in &headers {
+            let bad_name = name.is_empty() || name.contains(|c: char| c.is_ascii_whitespace() || c.is_ascii_control() || c == ':');
+            if bad_name || value.contains(['\r', '\n', '\0']) {
+                return Err(ProbeError::new(FailureKind::Connect, format!("Invalid header {:?}", name)));
+            }
+        }
+        let (tx, events) = mpsc::channel();
+        Ok(HttpSession { dialer, headers, timeout, tx, events, queue: VecDeque::new(), streams: Vec::new() })
+    }
+
+    fn request(&self, method: &str, url: &Url, extra: &[(String, String)], body: Option<&str>) -> Result<Response, ProbeError> {
+        let mut conn = connect(&self.dialer, url, self.timeout)?;
+        let headers: Vec<(String, String)> = self.headers.iter().chain(extra).cloned().collect();
+        write_request(&mut conn, method, url, &headers, body)
+            .map_err(|e| io_error(e, FailureKind::Connect, "Failed to send request"))?;
+        read_response(conn, method)
+    }
+
+    /// Read an event stream on its own thread. A stream that ends is only an error for the long-lived one.
+    fn listen(&mut self, response: Response, end_is_error: bool) {
+        if let Some(socket) = response.body.socket() {
+            let _ = socket.set_read_timeout(None);
+            self.streams.push(socket);
+        }
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(response.body);
+            loop {
+                match next_event(&mut reader) {
+                    Ok(Some(event)) => {
+                        if tx.send(Ok(event)).is_err() {
+                            return;
+                        }
+                    }
+                    Ok(None) => {
+                        if end_is_error {
+                            let _ = tx.send(Err(ProbeError::new(FailureKind::Protocol, "Server closed the event stream")));
+                        }
+                        return;
+                    }
+                    Err(e) => {
+                        let _ = tx.send(Err(io_error(e, FailureKind::Connect, "Event stream failed")));
+                        return;
+                    }
+                }
+            }
+        });
+    }
+
+    fn queue_json(&mut self, text: &str) -> Result<(), ProbeError> {
+        let invalid = |e: serde_json::Error| ProbeError::new(FailureKind::Protocol, format!("Invalid JSON-RPC message: {}", e));
+        match serde_json::from_str(text).map_err(invalid)? {
+            Value::Array(batch) => self.queue.extend(batch),
+            message => self.queue.push_back(message),
+        }
+        Ok(())
+    }
+
+    /// Next queued message, else the next `message` event
+    fn receive(&mut self, timeout: Duration) -> Result<Value, ProbeError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(message) = self.queue.pop_front() {
+                return Ok(message);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.events.recv_timeout(remaining) {
+                Ok(Ok(event)) if event.event == "message" => self.queue_json(&event.data)?,
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => return Err(e),
+                Err(_) => return Err(ProbeError::new(FailureKind::Timeout, "Timed out waiting for the server")),
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        for stream in self.streams.drain(..) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// Event streams have no read timeout, so a session dropped without `close` (a failed open) must still end them
+impl Drop for HttpSession {
+    fn drop(&mut self) {
+        self.close()
+    }
+}
+
+fn header(name: &str, value: &str) -> (String, String) {
+    (name.to_string(), value.to_string())
+}
+
+enum Mode {
+    /// Streamable HTTP: every message is a POST; responses come back as JSON or as an event stream
+    Streamable { session_id: Option<String>, protocol_version: Option<String> },
+    /// Legacy HTTP+SSE: one long GET stream carries responses, messages are POSTed to the endpoint it announces
+    Sse { post_url: Url },
+}
+
+/// A server reached over HTTP. Streamable HTTP is tried first for `http` servers, falling back to legacy SSE
+/// when the server refuses the first POST as the MCP spec describes; `sse` servers use legacy SSE directly.
+pub struct HttpTransport {
+    session: HttpSession,
+    url: Url,
+    mode: Mode,
+    can_fall_back: bool,
+}
+
+impl HttpTransport {
+    pub fn open(server: &McpServer, dialer: Dialer, timeout: Duration) -> Result<Self, ProbeError> {
+        let url = server
+            .url
+            .as_deref()
+            .ok_or_else(|| ProbeError::new(FailureKind::Connect, "Server has no url"))?;
+        let url = Url::parse(url).map_err(|e| ProbeError::new(FailureKind::Connect, format!("Invalid url {}: {}", url, e)))?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err(ProbeError::new(FailureKind::Connect, format!("Unsupported url scheme: {}", url.scheme())));
+        }
+
+        let session = HttpSession::new(server, dialer, timeout)?;
+        let mut transport = HttpTransport {
+            session,
+            url,
+            mode: Mode::Streamable { session_id: None, protocol_version: None },
+            can_fall_back: server.transport == Transport::Http,
+        };
+        if server.transport == Transport::Sse {
+            transport.open_sse()?;
+        }
+        Ok(transport)
+    }
+
+    /// Open the legacy event stream and wait for the endpoint to POST to
+    fn open_sse(&mut self) -> Result<(), ProbeError> {
+        let accept = [header("Accept", "text/event-stream"), header("Cache-Control", "no-cache")];
+        let response = self.session.request("GET", &self.url, &accept, None)?.check_status()?;
+        let content_type = response.content_type();
+        if !content_type.starts_with("text/event-stream") {
+            let got = if content_type.is_empty() { "no content type" } else { content_type.as_str() };
+            let message = format!("Expected an event stream, got {}", got);
+            return Err(ProbeError::new(FailureKind::Protocol, message));
+        }
+        self.session.listen(response, true);
+
+        let deadline = Instant::now() + self.session.timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.session.events.recv_timeout(remaining) {
+                Ok(Ok(event)) if event.event == "endpoint" => {
+                    let post_url = self.url.join(event.data.trim()).map_err(|e| {
+                        ProbeError::new(FailureKind::Protocol, format!("Invalid endpoint {}: {}", event.data, e))
+                    })?;
+                    self.mode = Mode::Sse { post_url };
+                    return Ok(());
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => return Err(e),
+                Err(_) => return Err(ProbeError::new(FailureKind::Protocol, "Event stream sent no endpoint")),
+            }
+        }
+    }
+
+    fn post_streamable(&mut self, message: &Value) -> Result<(), ProbeError> {
+        let mut extra = vec![
+            header("Content-Type", "application/json"),
+            header("Accept", "application/json, text/event-stream"),
+        ];
+        if let Mode::Streamable { session_id, protocol_version } = &self.mode {
+            if let Some(id) = session_id {
+                extra.push(header("Mcp-Session-Id", id));
+            }
+            if let Some(version) = protocol_version {
+                extra.push(header("MCP-Protocol-Version", version));
+            }
+        }
+        let mut response = self.session.request("POST", &self.url, &extra, Some(&message.to_string()))?.check_status()?;
+
+        if let (Some(id), Mode::Streamable { session_id, .. }) = (response.header("mcp-session-id"), &mut self.mode) {
+            *session_id = Some(id.to_string());
+        }
+        let content_type = response.content_type();
+        if content_type.starts_with("text/event-stream") {
+            self.session.listen(response, false);
+        } else if content_type.starts_with("application/json") {
+            let text = response.read_text().map_err(|e| io_error(e, FailureKind::Connect, "Failed to read response"))?;
+            self.session.queue_json(&text)?;
+        } else if response.status != 202 && message.get("id").is_some() && message.get("method").is_some() {
+            let message = format!("Unexpected content type {:?} for a request", content_type);
+            return Err(ProbeError::new(FailureKind::Protocol, message));
+        }
+        Ok(())
+    }
+}
+
+impl McpTransport for HttpTransport {
+    fn name(&self) -> &'static str {
+        match self.mode {
+            Mode::Streamable { .. } => "streamable-http",
+            Mode::Sse { .. } => "sse",
+        }
+    }
+
+    fn send(&mut self, message: &Value) -> Result<(), ProbeError> {
+        match &self.mode {
+            Mode::Sse { post_url } => {
+                let post_url = post_url.clone();
+                let extra = [header("Content-Type", "application/json")];
+                self.session.request("POST", &post_url, &extra, Some(&message.to_string()))?.check_status()?;
+                Ok(())
+            }
+            Mode::Streamable { .. } => {
+                let is_initialize = message.get("method").and_then(Value::as_str) == Some("initialize");
+                match self.post_streamable(message) {
+                    Err(e) if is_initialize && self.can_fall_back && matches!(e.status, Some(400 | 404 | 405)) => {
+                        println!("[Probe] {} refused Streamable HTTP ({}), trying SSE", self.url, e);
+                        self.can_fall_back = false;
+                        self.open_sse().map_err(|sse| {
+                            ProbeError { message: format!("{}; SSE fallback: {}", e.message, sse.message), ..sse }
+                        })?;
+                        self.send(message)
+                    }
+                    result => result,
+                }
+            }
+        }
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Result<Value, ProbeError> {
+        let message = self.session.receive(timeout)?;
+        if let Mode::Streamable { protocol_version, .. } = &mut self.mode {
+            if let Some(version) = message.pointer("/result/protocolVersion").and_then(Value::as_str) {
+                *protocol_version = Some(version.to_string());
+            }
+        }
+        Ok(message)
+    }
+
+    fn close(&mut self) {
+        self.session.close();
+    }
+}
+
+fn run(server: &McpServer, dialer: Dialer, timeout: Duration) -> ProbeReport {
+    let started = Instant::now();
+    match HttpTransport::open(server, dialer, timeout) {
+        Ok(transport) => probe(transport, started, timeout),
+        Err(e) => {
+            let mut report = ProbeReport::failed(e);
+            report.transport = Some(server.transport.as_str().to_string());
+            report
+        }
+    }
+}
+
+/// Connect to a remote server from this host
+pub async fn probe_local(server: &McpServer, timeout: Duration) -> ProbeReport {
+    let server = server.clone();
+    tokio::task::spawn_blocking(move || run(&server, Dialer::Direct, timeout))
+        .await
+        .unwrap_or_else(|e| ProbeReport::failed(ProbeError::new(FailureKind::Connect, format!("Task join error: {}", e))))
+}
+
+/// Connect to a remote server from a machine, through its SSH session
+pub async fn probe_remote(pool: SshPool, target: &SshTarget, server: &McpServer, platform: Platform, timeout: Duration) -> ProbeReport {
+    let server = server.clone();
+    let target = target.clone();
+    tokio::task::spawn_blocking(move || match pool.get_connection(&target) {
+        Ok(session) => run(&server, Dialer::Ssh(session, platform), timeout),
+        Err(e) => ProbeReport::failed(ProbeError::new(FailureKind::Connect, e)),
+    })
+    .await
+    .unwrap_or_else(|e| ProbeReport::failed(ProbeError::new(FailureKind::Connect, format!("Task join error: {}", e))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::net::TcpListener;
+
+    #[derive(Clone)]
+    struct StubRequest {
+        method: String,
+        path: String,
+        headers: HashMap<String, String>,
+        body: String,
+    }
+
+    /// What the stub does with a request: answer and close, or keep the connection as the SSE stream
+    enum Reply {
+        Send(String),
+        Stream(String),
+    }
+
+    fn response(status: &str, headers: &[(&str, &str)], body: &str) -> Reply {
+        let mut text = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\n", status, body.len());
+        for (name, value) in headers {
+            text.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        Reply::Send(format!("{}\r\n{}", text, body))
+    }
+
+    fn sse_event(data: &Value) -> String {
+        format!("event: message\ndata: {}\n\n", data)
+    }
+
+    /// Answers JSON-RPC requests the way a minimal MCP server would
+    fn answer(body: &str) -> Option<Value> {
+        let request: Value = serde_json::from_str(body).ok()?;
+        let id = request.get("id")?.clone();
+        let result = match request["method"].as_str()? {
+            "initialize" => json!({"protocolVersion": "2025-03-26", "capabilities": {"tools": {}}, "serverInfo": {"name": "stub"}}),
+            "tools/list" => json!({"tools": [{"name": "search"}, {"name": "fetch"}]}),
+            _ => return None,
+        };
+        Some(json!({"jsonrpc": "2.0", "id": id, "result": result}))
+    }
+
+    /// A local HTTP server: `handle` decides each reply; `sse` holds the open legacy stream, if any
+    fn stub_server(handle: impl Fn(&StubRequest, &Mutex<Option<TcpStream>>) -> Reply + Send + Sync + 'static) -> (String, Arc<Mutex<Vec<StubRequest>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sse = Arc::new(Mutex::new(None));
+        let handle = Arc::new(handle);
+        let seen_by_server = seen.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().map_while(Result::ok) {
+                let (handle, seen, sse) = (handle.clone(), seen_by_server.clone(), sse.clone());
+                thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                        return;
+                    }
+                    let mut parts = line.split_whitespace();
+                    let (method, path) = (parts.next().unwrap_or("").to_string(), parts.next().unwrap_or("").to_string());
+                    let mut headers = HashMap::new();
+                    loop {
+                        line.clear();
+                        reader.read_line(&mut line).unwrap();
+                        match line.trim().split_once(':') {
+                            Some((name, value)) => headers.insert(name.to_ascii_lowercase(), value.trim().to_string()),
+                            None => break,
+                        };
+                    }
+                    let length = headers.get("content-length").and_then(|l| l.parse().ok()).unwrap_or(0);
+                    let mut body = vec![0u8; length];
+                    reader.read_exact(&mut body).unwrap();
+                    let request = StubRequest { method, path, headers, body: String::from_utf8(body).unwrap() };
+                    seen.lock().unwrap().push(request.clone());
+
+                    let mut stream = stream;
+                    match handle(&request, &sse) {
+                        Reply::Send(text) => {
+                            let _ = stream.write_all(text.as_bytes());
+                        }
+                        Reply::Stream(head) => {
+                            // Held while the head goes out, so a POST answered on the stream waits for it
+                            let mut slot = sse.lock().unwrap();
+                            stream.write_all(head.as_bytes()).unwrap();
+                            *slot = Some(stream);
+                        }
+                    }
+                });
+            }
+        });
+        (base, seen)
+    }
+
+    fn http_server(url: String, transport: Transport, headers: &[(&str, &str)]) -> McpServer {
+        McpServer {
+            transport,
+            url: Some(url),
+            headers: Some(headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_next_event_parses_fields_and_multiline_data() {
+        let mut stream = io::Cursor::new(": keepalive\n\nevent: endpoint\ndata: /messages?s=1\n\ndata: {\"a\":\ndata: 1}\r\n\r\n");
+        assert_eq!(next_event(&mut stream).unwrap(), Some(SseEvent { event: "endpoint".into(), data: "/messages?s=1".into() }));
+        assert_eq!(next_event(&mut stream).unwrap(), Some(SseEvent { event: "message".into(), data: "{\"a\":\n1}".into() }));
+        assert_eq!(next_event(&mut stream).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_streamable_http_with_session_and_event_stream_response() {
+        let (base, seen) = stub_server(|req, _| {
+            if req.headers.get("authorization").map(String::as_str) != Some("Bearer secret") {
+                return response("401 Unauthorized", &[], "missing token");
+            }
+            match answer(&req.body) {
+                Some(reply) if reply["result"]["tools"].is_array() => {
+                    let body = sse_event(&reply);
+                    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\n\r\n";
+                    Reply::Send(format!("{}{:x}\r\n{}\r\n0\r\n\r\n", head, body.len(), body))
+                }
+                Some(reply) => response("200 OK", &[("Content-Type", "application/json"), ("Mcp-Session-Id", "abc")], &reply.to_string()),
+                None => response("202 Accepted", &[], ""),
+            }
+        });
+
+        let server = http_server(format!("{}/mcp", base), Transport::Http, &[("Authorization", "Bearer secret")]);
+        let report = probe_local(&server, Duration::from_secs(5)).await;
+        assert!(report.ok, "{:?}", report.error);
+        assert_eq!(report.transport.as_deref(), Some("streamable-http"));
+        assert_eq!(report.tool_count, Some(2));
+
+        let list = seen.lock().unwrap().iter().find(|r| r.body.contains("tools/list")).cloned().unwrap();
+        assert_eq!(list.headers.get("mcp-session-id").map(String::as_str), Some("abc"));
+        assert_eq!(list.headers.get("mcp-protocol-version").map(String::as_str), Some("2025-03-26"));
+
+        let server = http_server(format!("{}/mcp", base), Transport::Http, &[]);
+        let report = probe_local(&server, Duration::from_secs(5)).await;
+        assert_eq!(report.failure, Some(FailureKind::Unauthorized));
+        assert!(report.error.unwrap().contains("missing token"));
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_legacy_sse() {
+        let (base, _) = stub_server(|req, sse| match (req.method.as_str(), req.path.as_str()) {
+            ("POST", "/mcp") => response("405 Method Not Allowed", &[], ""),
+            ("GET", "/mcp") => Reply::Stream(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\nevent: endpoint\ndata: /messages?session=1\n\n".to_string(),
+            ),
+            ("POST", "/messages?session=1") => {
+                if let Some(reply) = answer(&req.body) {
+                    let mut stream = sse.lock().unwrap();
+                    stream.as_mut().unwrap().write_all(sse_event(&reply).as_bytes()).unwrap();
+                }
+                response("202 Accepted", &[], "")
+            }
+            _ => response("404 Not Found", &[], ""),
+        });
+
+        let server = http_server(format!("{}/mcp", base), Transport::Http, &[]);
+        let report = probe_local(&server, Duration::from_secs(5)).await;
+        assert!(report.ok, "{:?}", report.error);
+        assert_eq!(report.transport.as_deref(), Some("sse"));
+        assert_eq!(report.server_info.unwrap().name, "stub");
+        assert_eq!(report.tool_count, Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_classifies_connection_failures() {
+        let (base, _) = stub_server(|_, _| response("403 Forbidden", &[], ""));
+        let server = http_server(format!("{}/mcp", base), Transport::Http, &[]);
+        let report = probe_local(&server, Duration::from_secs(5)).await;
+        assert_eq!(report.failure, Some(FailureKind::Forbidden));
+
+        // A TLS client talking to a plain HTTP server
+        let plain = TcpListener::bind("127.0.0.1:0").unwrap();
+        let plain_addr = plain.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = plain.accept().unwrap();
+            let mut hello = [0u8; 512];
+            let _ = stream.read(&mut hello);
+            let _ = stream.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n");
+        });
+        let server = http_server(format!("https://{}/mcp", plain_addr), Transport::Http, &[]);
+        let report = probe_local(&server, Duration::from_secs(5)).await;
+        assert_eq!(report.failure, Some(FailureKind::Tls), "{:?}", report.error);
+
+        // .invalid never resolves
+        let server = http_server("http://mcp.invalid/mcp".to_string(), Transport::Http, &[]);
+        let report = probe_local(&server, Duration::from_secs(5)).await;
+        assert_eq!(report.failure, Some(FailureKind::Dns), "{:?}", report.error);
+
+        // A port nothing listens on
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let server = http_server(format!("http://127.0.0.1:{}/mcp", port), Transport::Sse, &[]);
+        let report = probe_local(&server, Duration::from_secs(5)).await;
+        assert_eq!(report.failure, Some(FailureKind::Connect), "{:?}", report.error);
+        assert_eq!(report.transport.as_deref(), Some("sse"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_header_line_breaks() {
+        let (base, seen) = stub_server(|_, _| response("200 OK", &[], ""));
+        let server = http_server(format!("{}/mcp", base), Transport::Http, &[("X-Team", "a\r\nX-Admin: 1")]);
+        let report = probe_local(&server, Duration::from_secs(5)).await;
+        assert_eq!(report.failure, Some(FailureKind::Connect), "{:?}", report.error);
+        assert!(seen.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_sse_open_closes_the_stream() {
+        // An event stream that never announces its endpoint
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (closed_tx, closed) = mpsc::channel();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\n: waiting\n\n").unwrap();
+            stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+            let _ = closed_tx.send(matches!(stream.read(&mut request), Ok(0)));
+        });
+
+        let server = http_server(format!("http://{}/sse", addr), Transport::Sse, &[]);
+        let report = probe_local(&server, Duration::from_millis(300)).await;
+        assert_eq!(report.failure, Some(FailureKind::Protocol), "{:?}", report.error);
+        assert!(closed.recv_timeout(Duration::from_secs(5)).unwrap(), "event stream left open");
+    }
+}
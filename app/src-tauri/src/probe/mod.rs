@@ -0,0 +1,392 @@
+pub mod http;
+pub mod stdio;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Protocol version we offer in `initialize`; servers answer with the one they speak
+pub const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Default wait for a server to start and answer, when its config sets no startup timeout.
+/// Generous because `npx -y` may download the package first.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Pages of `tools/list` followed before giving up on a server that keeps returning a cursor
+const MAX_PAGES: usize = 50;
+
+/// Where a probe went wrong, so the UI can tell "fix your token" from "fix your network"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailureKind {
+    /// The command couldn't be started
+    Spawn,
+    /// The server process stopped before answering
+    Exited,
+    Timeout,
+    Dns,
+    /// Connection refused, reset, or a channel the SSH server wouldn't open
+    Connect,
+    Tls,
+    /// HTTP 401
+    Unauthorized,
+    /// HTTP 403
+    Forbidden,
+    /// Any other unexpected HTTP status
+    Http,
+    /// The server answered, but not with valid MCP
+    Protocol,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeError {
+    pub kind: FailureKind,
+    pub message: String,
+    /// HTTP status, when the failure was a response
+    pub status: Option<u16>,
+}
+
+impl ProbeError {
+    pub fn new(kind: FailureKind, message: impl Into<String>) -> Self {
+        ProbeError { kind, message: message.into(), status: None }
+    }
+
+    /// The same failure with what was being done in front, e.g. "initialize failed: ..."
+    fn context(self, what: &str) -> Self {
+        ProbeError { message: format!("{}: {}", what, self.message), ..self }
+    }
+}
+
+impl fmt::Display for ProbeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// A connection to a server that carries JSON-RPC messages (a child's stdio, an HTTP endpoint...)
+pub trait McpTransport {
+    /// How the messages travel, for the report
+    fn name(&self) -> &'static str;
+
+    fn send(&mut self, message: &Value) -> Result<(), ProbeError>;
+
+    /// Next message from the server, waiting at most `timeout`
+    fn receive(&mut self, timeout: Duration) -> Result<Value, ProbeError>;
+
+    /// What the server wrote to stderr so far, if the transport has one
+    fn stderr(&mut self) -> Option<String> {
+        None
+    }
+
+    /// Stop the server / close the connection
+    fn close(&mut self) {}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInfo {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+/// Outcome of starting a server and talking to it
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeReport {
+    pub ok: bool,
+    /// `stdio`, `streamable-http` or `sse`; for HTTP, the one the server accepted
+    pub transport: Option<String>,
+    pub protocol_version: Option<String>,
+    pub server_info: Option<ServerInfo>,
+    pub tool_count: Option<usize>,
+    /// From launching the server to its `initialize` response
+    pub startup_ms: Option<u64>,
+    pub error: Option<String>,
+    pub failure: Option<FailureKind>,
+    /// The server's stderr, kept when the probe failed
+    pub stderr: Option<String>,
+    /// Everything the server listed, for the inventory catalog
+    #[serde(skip)]
+    pub inventory: Option<Inventory>,
+}
+
+/// The `tools/list`, `resources/list` and `prompts/list` results of a server, as returned.
+/// A list is empty when the server doesn't declare the capability.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Inventory {
+    pub tools: Vec<Value>,
+    pub resources: Vec<Value>,
+    pub prompts: Vec<Value>,
+}
+
+impl ProbeReport {
+    pub fn failed(error: ProbeError) -> Self {
+        ProbeReport { error: Some(error.message), failure: Some(error.kind), ..Default::default() }
+    }
+}
+
+/// A JSON-RPC client for one server, speaking only what a probe needs
+pub struct McpClient<T: McpTransport> {
+    transport: T,
+    next_id: u64,
+    timeout: Duration,
+}
+
+impl<T: McpTransport> McpClient<T> {
+    pub fn new(transport: T, timeout: Duration) -> Self {
+        McpClient { transport, next_id: 1, timeout }
+    }
+
+    /// Send a request and wait for its response. Notifications and requests from the server are skipped
+    /// (requests get a "method not found" so the server doesn't wait on us).
+    pub fn request(&mut self, method: &str, params: Value) -> Result<Value, ProbeError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.transport
+            .send(&json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}))
+            .map_err(|e| e.context(&format!("{} failed", method)))?;
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                let message = format!("Timed out after {}s waiting for {}", self.timeout.as_secs(), method);
+                return Err(ProbeError::new(FailureKind::Timeout, message));
+            }
+            let message = self
+                .transport
+                .receive(remaining)
+                .map_err(|e| e.context(&format!("{} failed", method)))?;
+
+            if message.get("method").is_some() {
+                if let Some(request_id) = message.get("id") {
+                    let reply = json!({
+                        "jsonrpc": "2.0",
+                        "id": request_id,
+                        "error": {"code": -32601, "message": "Method not found"}
+                    });
+                    self.transport.send(&reply)?;
+                }
+                continue;
+            }
+            if message.get("id").and_then(Value::as_u64) != Some(id) {
+                continue;
+            }
+            if let Some(error) = message.get("error") {
+                let text = error.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+                let message = match error.get("code").and_then(Value::as_i64) {
+                    Some(code) => format!("{} returned error {}: {}", method, code, text),
+                    None => format!("{} returned error: {}", method, text),
+                };
+                return Err(ProbeError::new(FailureKind::Protocol, message));
+            }
+            return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+        }
+    }
+
+    pub fn notify(&mut self, method: &str) -> Result<(), ProbeError> {
+        self.transport.send(&json!({"jsonrpc": "2.0", "method": method}))
+    }
+
+    /// Every item of a paginated list (`tools/list`, `resources/list`...), found under `key` in each page
+    pub fn list_all(&mut self, method: &str, key: &str) -> Result<Vec<Value>, ProbeError> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..MAX_PAGES {
+            let params = match &cursor {
+                Some(cursor) => json!({"cursor": cursor}),
+                None => json!({}),
+            };
+            let page = self.request(method, params)?;
+            if let Some(page_items) = page.get(key).and_then(Value::as_array) {
+                items.extend(page_items.iter().cloned());
+            }
+            cursor = page.get("nextCursor").and_then(Value::as_str).map(str::to_string);
+            if cursor.is_none() {
+                return Ok(items);
+            }
+        }
+        Err(ProbeError::new(FailureKind::Protocol, format!("{} returned more than {} pages", method, MAX_PAGES)))
+    }
+
+    pub fn into_transport(self) -> T {
+        self.transport
+    }
+}
+
+/// Run the handshake and list what the server offers, filling `report` with what was learned before any failure
+fn handshake<T: McpTransport>(client: &mut McpClient<T>, started: Instant, report: &mut ProbeReport) -> Result<(), ProbeError> {
+    let init = client.request(
+        "initialize",
+        json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": {"name": "mcp-control-hub", "version": env!("CARGO_PKG_VERSION")}
+        }),
+    )?;
+    report.startup_ms = Some(started.elapsed().as_millis() as u64);
+    report.protocol_version = init.get("protocolVersion").and_then(Value::as_str).map(str::to_string);
+    report.server_info = init.get("serverInfo").and_then(|info| {
+        Some(ServerInfo {
+            name: info.get("name")?.as_str()?.to_string(),
+            version: info.get("version").and_then(Value::as_str).map(str::to_string),
+        })
+    });
+    if report.protocol_version.is_none() {
+        return Err(ProbeError::new(FailureKind::Protocol, "initialize response has no protocolVersion"));
+    }
+
+    client.notify("notifications/initialized")?;
+
+    // Only list what the server says it offers
+    let mut inventory = Inventory::default();
+    if init.pointer("/capabilities/tools").is_some() {
+        inventory.tools = client.list_all("tools/list", "tools")?;
+    }
+    report.tool_count = Some(inventory.tools.len());
+    if init.pointer("/capabilities/resources").is_some() {
+        inventory.resources = client.list_all("resources/list", "resources")?;
+    }
+    if init.pointer("/capabilities/prompts").is_some() {
+        inventory.prompts = client.list_all("prompts/list", "prompts")?;
+    }
+    report.inventory = Some(inventory);
+    Ok(())
+}
+
+/// Talk to a server that was launched (or connected to) at `started` and close it afterwards
+pub fn probe<T: McpTransport>(transport: T, started: Instant, timeout: Duration) -> ProbeReport {
+    let mut client = McpClient::new(transport, timeout);
+    let mut report = ProbeReport::default();
+    let result = handshake(&mut client, started, &mut report);
+
+    let mut transport = client.into_transport();
+    report.transport = Some(transport.name().to_string());
+    transport.close();
+    match result {
+        Ok(()) => report.ok = true,
+        Err(e) => {
+            report.error = Some(e.message);
+            report.failure = Some(e.kind);
+            report.stderr = transport.stderr().filter(|s| !s.trim().is_empty());
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Answers each request with the next scripted reply, and can interleave server messages
+    struct ScriptedTransport {
+        replies: VecDeque<Value>,
+        sent: Vec<Value>,
+    }
+
+    impl McpTransport for ScriptedTransport {
+        fn name(&self) -> &'static str {
+            "scripted"
+        }
+
+        fn send(&mut self, message: &Value) -> Result<(), ProbeError> {
+            self.sent.push(message.clone());
+            Ok(())
+        }
+
+        fn receive(&mut self, _timeout: Duration) -> Result<Value, ProbeError> {
+            self.replies.pop_front().ok_or_else(|| ProbeError::new(FailureKind::Exited, "Server exited"))
+        }
+
+        fn stderr(&mut self) -> Option<String> {
+            Some("Error: Cannot find module 'left-pad'\n".to_string())
+        }
+    }
+
+    fn scripted(replies: Vec<Value>) -> ScriptedTransport {
+        ScriptedTransport { replies: replies.into(), sent: Vec::new() }
+    }
+
+    #[test]
+    fn test_probe_reads_handshake_and_pages_of_tools() {
+        let transport = scripted(vec![
+            json!({"jsonrpc": "2.0", "method": "notifications/message", "params": {"data": "starting"}}),
+            json!({"jsonrpc": "2.0", "id": 1, "result": {
+                "protocolVersion": "2025-03-26",
+                "capabilities": {"tools": {}},
+                "serverInfo": {"name": "fs", "version": "1.2.0"}
+            }}),
+            json!({"jsonrpc": "2.0", "id": 2, "result": {"tools": [{"name": "read"}, {"name": "write"}], "nextCursor": "p2"}}),
+            json!({"jsonrpc": "2.0", "id": 99, "method": "roots/list"}),
+            json!({"jsonrpc": "2.0", "id": 3, "result": {"tools": [{"name": "list"}]}}),
+        ]);
+        let report = probe(transport, Instant::now(), DEFAULT_TIMEOUT);
+
+        assert!(report.ok, "{:?}", report.error);
+        assert_eq!(report.protocol_version.as_deref(), Some("2025-03-26"));
+        assert_eq!(report.server_info, Some(ServerInfo { name: "fs".to_string(), version: Some("1.2.0".to_string()) }));
+        assert_eq!(report.tool_count, Some(3));
+        assert!(report.startup_ms.is_some());
+        assert!(report.stderr.is_none());
+        assert_eq!(report.transport.as_deref(), Some("scripted"));
+    }
+
+    #[test]
+    fn test_probe_lists_only_declared_capabilities() {
+        let transport = scripted(vec![
+            json!({"jsonrpc": "2.0", "id": 1, "result": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": {"resources": {}, "prompts": {}}
+            }}),
+            json!({"jsonrpc": "2.0", "id": 2, "result": {"resources": [{"uri": "file:///a", "name": "a"}]}}),
+            json!({"jsonrpc": "2.0", "id": 3, "result": {"prompts": [{"name": "review"}]}}),
+        ]);
+        let report = probe(transport, Instant::now(), DEFAULT_TIMEOUT);
+
+        assert!(report.ok, "{:?}", report.error);
+        assert_eq!(report.tool_count, Some(0));
+        let inventory = report.inventory.unwrap();
+        assert!(inventory.tools.is_empty());
+        assert_eq!(inventory.resources, vec![json!({"uri": "file:///a", "name": "a"})]);
+        assert_eq!(inventory.prompts, vec![json!({"name": "review"})]);
+    }
+
+    #[test]
+    fn test_probe_answers_server_requests_and_sends_cursor() {
+        let mut client = McpClient::new(
+            scripted(vec![
+                json!({"jsonrpc": "2.0", "id": 7, "method": "ping"}),
+                json!({"jsonrpc": "2.0", "id": 1, "result": {"tools": [], "nextCursor": "next"}}),
+                json!({"jsonrpc": "2.0", "id": 2, "result": {"tools": []}}),
+            ]),
+            DEFAULT_TIMEOUT,
+        );
+        client.list_all("tools/list", "tools").unwrap();
+
+        let sent = client.into_transport().sent;
+        assert_eq!(sent[1]["id"], 7);
+        assert_eq!(sent[1]["error"]["code"], -32601);
+        assert_eq!(sent[2]["params"]["cursor"], "next");
+    }
+
+    #[test]
+    fn test_probe_failure_keeps_stderr_and_partial_results() {
+        let report = probe(scripted(vec![]), Instant::now(), DEFAULT_TIMEOUT);
+        assert!(!report.ok);
+        assert_eq!(report.error.as_deref(), Some("initialize failed: Server exited"));
+        assert_eq!(report.failure, Some(FailureKind::Exited));
+        assert!(report.stderr.unwrap().contains("left-pad"));
+
+        let transport = scripted(vec![
+            json!({"jsonrpc": "2.0", "id": 1, "result": {"protocolVersion": "2025-06-18", "capabilities": {"tools": {}}}}),
+            json!({"jsonrpc": "2.0", "id": 2, "error": {"code": -32603, "message": "boom"}}),
+        ]);
+        let report = probe(transport, Instant::now(), DEFAULT_TIMEOUT);
+        assert!(!report.ok);
+        assert_eq!(report.protocol_version.as_deref(), Some("2025-06-18"));
+        assert_eq!(report.error.as_deref(), Some("tools/list returned error -32603: boom"));
+        assert_eq!(report.failure, Some(FailureKind::Protocol));
+    }
+}
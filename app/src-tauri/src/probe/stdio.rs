@@ -0,0 +1,483 @@
+use super::{probe, FailureKind, McpTransport, ProbeError, ProbeReport};
+use crate::config::converter::Platform;
+use crate::config::McpServer;
+use crate::ssh::connection::SshTarget;
+use crate::ssh::pool::SshPool;
+use crate::ssh::tunnel;
+use serde_json::Value;
+use ssh2::{Channel, Session};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Only the end of a chatty server's stderr is kept
+const MAX_STDERR: usize = 16 * 1024;
+
+fn push_capped(buf: &mut String, text: &str) {
+    buf.push_str(text);
+    if buf.len() > MAX_STDERR {
+        let mut cut = buf.len() - MAX_STDERR;
+        while !buf.is_char_boundary(cut) {
+            cut += 1;
+        }
+        buf.drain(..cut);
+    }
+}
+
+/// A stdout line as a JSON-RPC message; anything else (a banner, a stray log line) is skipped
+fn parse_message(line: &str) -> Option<Value> {
+    let value: Value = serde_json::from_str(line.trim()).ok()?;
+    value.is_object().then_some(value)
+}
+
+fn to_line(message: &Value) -> String {
+    let mut line = message.to_string();
+    line.push('\n');
+    line
+}
+
+fn timed_out() -> ProbeError {
+    ProbeError::new(FailureKind::Timeout, "Timed out waiting for the server")
+}
+
+/// A server started as a child process on this host
+pub struct LocalStdio {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    lines: Receiver<String>,
+    stderr: Arc<Mutex<String>>,
+}
+
+impl LocalStdio {
+    pub fn spawn(server: &McpServer) -> Result<Self, ProbeError> {
+        let command = server
+            .command
+            .as_deref()
+            .ok_or_else(|| ProbeError::new(FailureKind::Spawn, "Server has no command"))?;
+        let mut cmd = Command::new(command);
+        cmd.args(server.args.clone().unwrap_or_default())
+            .envs(server.env.clone().unwrap_or_default())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(cwd) = &server.cwd {
+            cmd.current_dir(cwd);
+        }
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| ProbeError::new(FailureKind::Spawn, format!("Failed to start {}: {}", command, e)))?;
+
+        let stdout = child.stdout.take().ok_or_else(|| ProbeError::new(FailureKind::Spawn, "No stdout"))?;
+        let (tx, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let stderr = Arc::new(Mutex::new(String::new()));
+        if let Some(mut pipe) = child.stderr.take() {
+            let stderr = stderr.clone();
+            thread::spawn(move || {
+                let mut buf = [0u8; 4096];
+                while let Ok(n) = pipe.read(&mut buf) {
+                    if n == 0 {
+                        break;
+                    }
+                    if let Ok(mut text) = stderr.lock() {
+                        push_capped(&mut text, &String::from_utf8_lossy(&buf[..n]));
+                    }
+                }
+            });
+        }
+
+        let stdin = child.stdin.take();
+        Ok(LocalStdio { child, stdin, lines, stderr })
+    }
+
+    /// Why the server stopped talking, once its stdout closed or stdin broke
+    fn exited(&mut self) -> ProbeError {
+        for _ in 0..25 {
+            if let Ok(Some(status)) = self.child.try_wait() {
+                return ProbeError::new(FailureKind::Exited, format!("Server exited ({})", status));
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        ProbeError::new(FailureKind::Exited, "Server closed stdout")
+    }
+}
+
+impl McpTransport for LocalStdio {
+    fn name(&self) -> &'static str {
+        "stdio"
+    }
+
+    fn send(&mut self, message: &Value) -> Result<(), ProbeError> {
+        let stdin = self.stdin.as_mut().ok_or_else(|| ProbeError::new(FailureKind::Exited, "Server stdin is closed"))?;
+        match stdin.write_all(to_line(message).as_bytes()).and_then(|_| stdin.flush()) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::BrokenPipe => Err(self.exited()),
+            Err(e) => Err(ProbeError::new(FailureKind::Exited, format!("Failed to write to server: {}", e))),
+        }
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Result<Value, ProbeError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.lines.recv_timeout(remaining) {
+                Ok(line) => {
+                    if let Some(message) = parse_message(&line) {
+                        return Ok(message);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => return Err(timed_out()),
+                Err(RecvTimeoutError::Disconnected) => return Err(self.exited()),
+            }
+        }
+    }
+
+    fn stderr(&mut self) -> Option<String> {
+        self.stderr.lock().ok().map(|s| s.clone())
+    }
+
+    fn close(&mut self) {
+        // Closing stdin is how a stdio server is told to stop; kill it if it doesn't
+        self.stdin = None;
+        for _ in 0..10 {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Quote for a POSIX shell
+fn sh_quote(s: &str) -> String {
+    if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:@%+,".contains(c)) {
+        return s.to_string();
+    }
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Quote for cmd.exe, the default shell of OpenSSH on Windows
+fn cmd_quote(s: &str) -> String {
+    if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "-_./\\=:@+,".contains(c)) {
+        return s.to_string();
+    }
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+/// Caret-escape an unquoted cmd.exe word. A `%` keeps its caret through expansion, so no `%NAME%` pair
+/// that ends inside the word names a real variable.
+fn cmd_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if "^&|<>()\"%".contains(c) {
+            escaped.push('^');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn sorted_env(server: &McpServer) -> Vec<(String, String)> {
+    let mut env: Vec<(String, String)> = server.env.clone().unwrap_or_default().into_iter().collect();
+    env.sort();
+    env
+}
+
+/// The shell command line that starts `server` on a machine with its cwd and env
+pub(crate) fn remote_command_line(server: &McpServer, platform: Platform) -> Result<String, String> {
+    let command_line = server.command_line().ok_or("Server has no command")?;
+    let env = sorted_env(server);
+    let line = match platform {
+        Platform::Linux => {
+            let mut parts = Vec::new();
+            if let Some(cwd) = &server.cwd {
+                parts.push(format!("cd {} &&", sh_quote(cwd)));
+            }
+            parts.push("exec".to_string());
+            if !env.is_empty() {
+                parts.push("env".to_string());
+                parts.extend(env.iter().map(|(k, v)| sh_quote(&format!("{}={}", k, v))));
+            }
+            parts.extend(command_line.iter().map(|s| sh_quote(s)));
+            parts.join(" ")
+        }
+        Platform::Windows => {
+            let mut parts = Vec::new();
+            if let Some(cwd) = &server.cwd {
+                parts.push(format!("cd /d {} &&", cmd_quote(cwd)));
+            }
+            // `set` keeps everything up to `&&`, so there is no space before it
+            parts.extend(env.iter().map(|(k, v)| format!("set {}={}&&", cmd_escape(k), cmd_escape(v))));
+            parts.extend(command_line.iter().map(|s| cmd_quote(s)));
+            parts.join(" ")
+        }
+    };
+    Ok(line)
+}
+
+/// How long a write or close may hold the pooled session
+const CHANNEL_TIMEOUT_MS: u32 = 2000;
+
+/// A server started over its own exec channel on a pooled session. The session is only locked for each
+/// write and non-blocking poll, so other commands and tunnels on the machine keep running during a probe.
+pub struct RemoteStdio {
+    session: Arc<Mutex<Session>>,
+    channel: Channel,
+    pending: Vec<u8>,
+    stderr: String,
+}
+
+fn poisoned() -> ProbeError {
+    ProbeError::new(FailureKind::Connect, "Poisoned lock")
+}
+
+impl RemoteStdio {
+    pub fn start(session: Arc<Mutex<Session>>, command_line: &str) -> Result<Self, ProbeError> {
+        let channel = {
+            let sess = session.lock().map_err(|_| poisoned())?;
+            let mut channel = sess
+                .channel_session()
+                .map_err(|e| ProbeError::new(FailureKind::Connect, format!("Failed to open channel: {}", e)))?;
+            channel
+                .exec(command_line)
+                .map_err(|e| ProbeError::new(FailureKind::Spawn, format!("Failed to execute command: {}", e)))?;
+            channel
+        };
+        Ok(RemoteStdio { session, channel, pending: Vec::new(), stderr: String::new() })
+    }
+
+    fn take_line(&mut self) -> Option<String> {
+        let end = self.pending.iter().position(|&b| b == b'\n')?;
+        let line: Vec<u8> = self.pending.drain(..=end).collect();
+        Some(String::from_utf8_lossy(&line).into_owned())
+    }
+
+    /// Keep whatever stderr is buffered so a chatty server can't fill the channel window. Call with the
+    /// session in non-blocking mode.
+    fn drain_stderr(&mut self) -> bool {
+        let mut buf = [0u8; 4096];
+        let mut drained = false;
+        while let Ok(n) = self.channel.stderr().read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            push_capped(&mut self.stderr, &String::from_utf8_lossy(&buf[..n]));
+            drained = true;
+        }
+        drained
+    }
+
+    fn exited(&mut self) -> ProbeError {
+        let status = match self.session.clone().lock() {
+            Ok(sess) => {
+                sess.set_timeout(CHANNEL_TIMEOUT_MS);
+                let _ = self.channel.wait_close();
+                sess.set_timeout(0);
+                self.channel.exit_status().ok()
+            }
+            Err(_) => None,
+        };
+        let message = match status {
+            Some(status) => format!("Server exited (exit status: {})", status),
+            None => "Server closed stdout".to_string(),
+        };
+        ProbeError::new(FailureKind::Exited, message)
+    }
+}
+
+impl McpTransport for RemoteStdio {
+    fn name(&self) -> &'static str {
+        "stdio"
+    }
+
+    fn send(&mut self, message: &Value) -> Result<(), ProbeError> {
+        let session = self.session.clone();
+        let sess = session.lock().map_err(|_| poisoned())?;
+        sess.set_timeout(CHANNEL_TIMEOUT_MS);
+        let result = self.channel.write_all(to_line(message).as_bytes()).and_then(|_| self.channel.flush());
+        sess.set_timeout(0);
+        result.map_err(|e| ProbeError::new(FailureKind::Exited, format!("Failed to write to server: {}", e)))
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Result<Value, ProbeError> {
+        let deadline = Instant::now() + timeout;
+        let mut buf = [0u8; 8192];
+        let mut idle = tunnel::MIN_IDLE_WAIT;
+        loop {
+            while let Some(line) = self.take_line() {
+                if let Some(message) = parse_message(&line) {
+                    return Ok(message);
+                }
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(timed_out());
+            }
+
+            let (read, drained, eof) = {
+                let session = self.session.clone();
+                let sess = session.lock().map_err(|_| poisoned())?;
+                sess.set_blocking(false);
+                let read = self.channel.read(&mut buf);
+                let drained = self.drain_stderr();
+                let eof = self.channel.eof();
+                sess.set_blocking(true);
+                (read, drained, eof)
+            };
+
+            let progressed = match read {
+                Ok(0) if eof => return Err(self.exited()),
+                Ok(n) => {
+                    self.pending.extend_from_slice(&buf[..n]);
+                    n > 0 || drained
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => drained,
+                Err(e) => return Err(ProbeError::new(FailureKind::Exited, format!("Failed to read from server: {}", e))),
+            };
+            if progressed {
+                idle = tunnel::MIN_IDLE_WAIT;
+            } else {
+                thread::sleep(idle.min(remaining));
+                idle = (idle * 2).min(tunnel::MAX_IDLE_WAIT);
+            }
+        }
+    }
+
+    fn stderr(&mut self) -> Option<String> {
+        // Whatever libssh2 has buffered; don't wait on a server that is still running
+        if let Ok(sess) = self.session.clone().lock() {
+            sess.set_blocking(false);
+            self.drain_stderr();
+            sess.set_blocking(true);
+        }
+        Some(self.stderr.clone())
+    }
+
+    fn close(&mut self) {
+        if let Ok(sess) = self.session.clone().lock() {
+            sess.set_timeout(CHANNEL_TIMEOUT_MS);
+            let _ = self.channel.send_eof();
+            let _ = self.channel.close();
+            sess.set_timeout(0);
+        }
+    }
+}
+
+/// Start `server` on this host and run the handshake
+pub async fn probe_local(server: &McpServer, timeout: Duration) -> ProbeReport {
+    let server = server.clone();
+    tokio::task::spawn_blocking(move || {
+        let started = Instant::now();
+        match LocalStdio::spawn(&server) {
+            Ok(transport) => probe(transport, started, timeout),
+            Err(e) => ProbeReport::failed(e),
+        }
+    })
+    .await
+    .unwrap_or_else(|e| ProbeReport::failed(ProbeError::new(FailureKind::Spawn, format!("Task join error: {}", e))))
+}
+
+/// Start `server` on a machine over SSH and run the handshake there
+pub async fn probe_remote(pool: SshPool, target: &SshTarget, server: &McpServer, platform: Platform, timeout: Duration) -> ProbeReport {
+    let command_line = match remote_command_line(server, platform) {
+        Ok(line) => line,
+        Err(e) => return ProbeReport::failed(ProbeError::new(FailureKind::Spawn, e)),
+    };
+    let command_name = server.command.clone().unwrap_or_default();
+    let target = target.clone();
+    tokio::task::spawn_blocking(move || {
+        let session_arc = match pool.get_connection(&target) {
+            Ok(sess) => sess,
+            Err(e) => return ProbeReport::failed(ProbeError::new(FailureKind::Connect, e)),
+        };
+        // The command line carries the server's env, so only its name is logged
+        println!("[Probe] Starting {} on {}", command_name, target.host);
+        let started = Instant::now();
+        match RemoteStdio::start(session_arc, &command_line) {
+            Ok(transport) => probe(transport, started, timeout),
+            Err(e) => ProbeReport::failed(e),
+        }
+    })
+    .await
+    .unwrap_or_else(|e| ProbeReport::failed(ProbeError::new(FailureKind::Spawn, format!("Task join error: {}", e))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env_map(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_remote_command_line_quotes_for_each_shell() {
+        let server = McpServer {
+            command: Some("npx".to_string()),
+            args: Some(vec!["-y".to_string(), "@scope/pkg".to_string(), "it's here".to_string()]),
+            env: Some(env_map(&[("TOKEN", "a b"), ("DEBUG", "1"), ("LIMIT", r#"50% "soft" & up"#)])),
+            cwd: Some("/srv/mcp".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            remote_command_line(&server, Platform::Linux).unwrap(),
+            r#"cd /srv/mcp && exec env DEBUG=1 'LIMIT=50% "soft" & up' 'TOKEN=a b' npx -y @scope/pkg 'it'\''s here'"#
+        );
+
+        let windows = McpServer { cwd: None, ..server.adapted_for(Platform::Windows) };
+        assert_eq!(
+            remote_command_line(&windows, Platform::Windows).unwrap(),
+            r#"set DEBUG=1&& set LIMIT=50^% ^"soft^" ^& up&& set TOKEN=a b&& cmd /c npx -y @scope/pkg "it's here""#
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_probe_local_process() {
+        // Answers initialize and tools/list after some noise on stdout and stderr
+        let script = r#"
+            echo "server starting" >&2
+            echo "banner, not JSON"
+            read init
+            echo '{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2025-06-18","capabilities":{"tools":{}},"serverInfo":{"name":"sh"}}}'
+            read initialized
+            read list
+            echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"a"},{"name":"b"}]}}'
+            read eof
+        "#;
+        let server = McpServer {
+            command: Some("sh".to_string()),
+            args: Some(vec!["-c".to_string(), script.to_string()]),
+            ..Default::default()
+        };
+        let report = probe_local(&server, Duration::from_secs(10)).await;
+        assert!(report.ok, "{:?} {:?}", report.error, report.stderr);
+        assert_eq!(report.tool_count, Some(2));
+        assert_eq!(report.server_info.unwrap().name, "sh");
+
+        let server = McpServer {
+            command: Some("sh".to_string()),
+            args: Some(vec!["-c".to_string(), "echo 'Cannot find module' >&2; exit 3".to_string()]),
+            ..Default::default()
+        };
+        let report = probe_local(&server, Duration::from_secs(10)).await;
+        assert!(!report.ok);
+        let error = report.error.unwrap();
+        assert!(error.contains("exit status: 3"), "{}", error);
+        assert_eq!(report.failure, Some(FailureKind::Exited));
+        assert!(report.stderr.unwrap().contains("Cannot find module"));
+    }
+}
@@ -0,0 +1,189 @@
+use crate::db::known_hosts;
+use base64::{engine::general_purpose::STANDARD_NO_PAD as BASE64_NO_PAD, Engine as _};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Sqlite};
+use ssh2::{HostKeyType, Session};
+use crate::ssh::connection::{self, SshTarget};
+
+/// Host key presented by a server during the handshake
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct HostKeyInfo {
+    pub key_type: String,
+    pub fingerprint: String, // OpenSSH style "SHA256:<base64>"
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HostKeyError {
+    #[error(
+        "HOST_KEY_MISMATCH: The host key for {host}:{port} has changed!\n\n\
+        Trusted:   {expected}\n\
+        Presented: {actual}\n\n\
+        Someone may be intercepting the connection (man-in-the-middle), or the server was reinstalled.\n\
+        Review the new key and re-accept it only if you trust the change."
+    )]
+    Mismatch {
+        host: String,
+        port: u16,
+        expected: String,
+        actual: String,
+    },
+    #[error("Server {host}:{port} did not present a host key")]
+    Missing { host: String, port: u16 },
+}
+
+impl From<HostKeyError> for String {
+    fn from(e: HostKeyError) -> Self {
+        e.to_string()
+    }
+}
+
+/// SHA256 fingerprint of a raw host key blob, formatted like `ssh-keygen -l`
+pub fn fingerprint(key_blob: &[u8]) -> String {
+    let digest = Sha256::digest(key_blob);
+    format!("SHA256:{}", BASE64_NO_PAD.encode(digest))
+}
+
+fn key_type_name(kind: HostKeyType) -> &'static str {
+    match kind {
+        HostKeyType::Rsa => "ssh-rsa",
+        HostKeyType::Dss => "ssh-dss",
+        HostKeyType::Ecdsa256 => "ecdsa-sha2-nistp256",
+        HostKeyType::Ecdsa384 => "ecdsa-sha2-nistp384",
+        HostKeyType::Ecdsa521 => "ecdsa-sha2-nistp521",
+        HostKeyType::Ed25519 => "ssh-ed25519",
+        HostKeyType::Unknown => "unknown",
+    }
+}
+
+/// Read the host key of an already handshaken session
+pub fn session_host_key(sess: &Session) -> Option<HostKeyInfo> {
+    sess.host_key().map(|(blob, kind)| HostKeyInfo {
+        key_type: key_type_name(kind).to_string(),
+        fingerprint: fingerprint(blob),
+    })
+}
+
+/// Fail the connection unless the server presented the trusted key
+pub fn verify_host_key(sess: &Session, host: &str, port: u16, trusted_fingerprint: &str) -> Result<(), HostKeyError> {
+    let presented = session_host_key(sess).ok_or_else(|| HostKeyError::Missing {
+        host: host.to_string(),
+        port,
+    })?;
+
+    if presented.fingerprint != trusted_fingerprint {
+        return Err(HostKeyError::Mismatch {
+            host: host.to_string(),
+            port,
+            expected: trusted_fingerprint.to_string(),
+            actual: presented.fingerprint,
+        });
+    }
+
+    println!("[SSH] ✓ Host key verified ({} {})", presented.key_type, presented.fingerprint);
+    Ok(())
+}
+
+/// Handshake only (no authentication) to see which key the server presents.
+/// Hosts behind a bastion are probed through a tunnel on the (already trusted) jump target.
+pub fn fetch_host_key(host: &str, port: u16, jump: Option<&SshTarget>) -> Result<HostKeyInfo, String> {
+    let tcp = connection::open_transport(host, port, jump, None)?;
+
+    let mut sess = Session::new()
+        .map_err(|e| format!("Session creation failed: {}", e))?;
+    sess.set_tcp_stream(tcp);
+    sess.handshake()
+        .map_err(|e| format!("SSH handshake failed: {}", e))?;
+
+    session_host_key(&sess)
+        .ok_or_else(|| HostKeyError::Missing { host: host.to_string(), port }.to_string())
+}
+
+/// The bastions in front of a host in ProxyJump order (outermost first), e.g. `ops@outer:22,ops@bastion:22`;
+/// empty for a direct connection. Host keys are trusted per route: like the connection pool, the same address
+/// behind different bastions is a different server.
+pub fn jump_route(jump: Option<&SshTarget>) -> String {
+    let mut hops = Vec::new();
+    let mut next = jump;
+    while let Some(hop) = next {
+        hops.push(format!("{}@{}:{}", hop.username, hop.host, hop.port));
+        next = hop.jump.as_deref();
+    }
+    hops.reverse();
+    hops.join(",")
+}
+
+/// Trusted fingerprint for host:port reached through `jump`. On first contact the presented key is recorded
+/// (trust on first use).
+pub async fn trusted_host_key(pool: &Pool<Sqlite>, host: &str, port: u16, jump: Option<&SshTarget>) -> Result<String, String> {
+    let via = jump_route(jump);
+    if let Some(known) = known_hosts::get_known_host(pool, host, port, &via).await? {
+        return Ok(known.fingerprint);
+    }
+
+    let (h, p, j) = (host.to_string(), port, jump.cloned());
+    let presented = tokio::task::spawn_blocking(move || fetch_host_key(&h, p, j.as_ref()))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))??;
+
+    println!(
+        "[SSH] First connection to {}:{}, trusting {} key {}",
+        host, port, presented.key_type, presented.fingerprint
+    );
+    known_hosts::insert_known_host_if_absent(pool, host, port, &via, &presented.key_type, &presented.fingerprint).await?;
+
+    // Re-read in case a concurrent first connection recorded it before us
+    known_hosts::get_known_host(pool, host, port, &via)
+        .await?
+        .map(|known| known.fingerprint)
+        .ok_or_else(|| format!("Failed to record host key for {}:{}", host, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_format() {
+        // Known vector: SHA256 of empty input
+        assert_eq!(
+            fingerprint(b""),
+            "SHA256:47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU"
+        );
+        assert_ne!(fingerprint(b"key-a"), fingerprint(b"key-b"));
+    }
+
+    fn target(host: &str, jump: Option<SshTarget>) -> SshTarget {
+        SshTarget {
+            host: host.to_string(),
+            port: 22,
+            username: "ops".to_string(),
+            auth: connection::SshAuth::Agent,
+            host_key: String::new(),
+            jump: jump.map(Box::new),
+        }
+    }
+
+    #[test]
+    fn test_jump_route_lists_hops_outermost_first() {
+        assert_eq!(jump_route(None), "");
+        let bastion = target("bastion", Some(target("outer", None)));
+        assert_eq!(jump_route(Some(&bastion)), "ops@outer:22,ops@bastion:22");
+        assert_ne!(jump_route(Some(&target("bastion-a", None))), jump_route(Some(&target("bastion-b", None))));
+    }
+
+    #[test]
+    fn test_mismatch_error_is_tagged() {
+        let err: String = HostKeyError::Mismatch {
+            host: "10.0.0.5".to_string(),
+            port: 22,
+            expected: "SHA256:old".to_string(),
+            actual: "SHA256:new".to_string(),
+        }
+        .into();
+
+        assert!(err.starts_with("HOST_KEY_MISMATCH"));
+        assert!(err.contains("SHA256:old"));
+        assert!(err.contains("SHA256:new"));
+    }
+}
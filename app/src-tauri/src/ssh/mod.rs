@@ -1,5 +1,9 @@
 pub mod encryption;
 pub mod key_manager;
 pub mod connection;
+pub mod host_keys;
+pub mod passphrase;
+pub mod ssh_config;
 pub mod sftp;
 pub mod pool;
+pub mod tunnel;
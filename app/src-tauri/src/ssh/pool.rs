@@ -1,3 +1,4 @@
+use crate::ssh::connection::SshTarget;
 use ssh2::Session;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -7,6 +8,23 @@ pub struct PoolKey {
     host: String,
     port: u16,
     username: String,
+    // The same address behind different bastions is a different server
+    via: Option<Box<PoolKey>>,
+}
+
+impl PoolKey {
+    fn for_target(target: &SshTarget) -> Self {
+        PoolKey {
+            host: target.host.clone(),
+            port: target.port,
+            username: target.username.clone(),
+            via: target.jump.as_deref().map(|jump| Box::new(PoolKey::for_target(jump))),
+        }
+    }
+
+    fn is(&self, host: &str, port: u16, username: &str) -> bool {
+        self.host == host && self.port == port && self.username == username
+    }
 }
 
 #[derive(Clone)]
@@ -26,43 +44,31 @@ impl SshPool {
     /// Check if the pool has a connection for the given key
     #[allow(dead_code)]
     pub fn has_connection(&self, host: &str, port: u16, username: &str) -> bool {
-        let key = PoolKey {
-            host: host.to_string(),
-            port,
-            username: username.to_string(),
-        };
         let sessions = self.sessions.lock().unwrap();
-        sessions.contains_key(&key)
+        sessions.keys().any(|key| key.is(host, port, username))
     }
 
-    /// Remove a connection from the pool (e.g. if known bad)
+    /// Remove a connection from the pool (e.g. if known bad), whichever jump host it goes through
     pub fn remove(&self, host: &str, port: u16, username: &str) {
-        let key = PoolKey {
-            host: host.to_string(),
-            port,
-            username: username.to_string(),
-        };
         let mut sessions = self.sessions.lock().unwrap();
-        sessions.remove(&key);
+        sessions.retain(|key, _| !key.is(host, port, username));
         println!(
             "[SSH Pool] Removed connection for {}@{}:{}",
             username, host, port
         );
     }
 
+    /// Remove every pooled connection to a host, whatever the user (e.g. after its host key changed)
+    pub fn remove_host(&self, host: &str, port: u16) {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|key, _| !(key.host == host && key.port == port));
+        println!("[SSH Pool] Removed all connections for {}:{}", host, port);
+    }
+
     /// Get an existing connection or create a new one
-    pub fn get_connection(
-        &self,
-        host: &str,
-        port: u16,
-        username: &str,
-        private_key: &str,
-    ) -> Result<Arc<Mutex<Session>>, String> {
-        let key = PoolKey {
-            host: host.to_string(),
-            port,
-            username: username.to_string(),
-        };
+    pub fn get_connection(&self, target: &SshTarget) -> Result<Arc<Mutex<Session>>, String> {
+        let (host, port, username) = (target.host.as_str(), target.port, target.username.as_str());
+        let key = PoolKey::for_target(target);
 
         // 1. Try to get existing session
         {
@@ -82,7 +88,8 @@ impl SshPool {
             "[SSH Pool] Creating NEW connection for {}@{}:{}",
             username, host, port
         );
-        match crate::ssh::connection::create_ssh_session(host, port, username, private_key) {
+        // A jump host session is itself fetched from (or added to) this pool, so bastions are shared
+        match crate::ssh::connection::create_ssh_session(target, Some(self)) {
             Ok(session) => {
                 let session_arc = Arc::new(Mutex::new(session));
 
@@ -107,16 +114,19 @@ mod tests {
             host: "127.0.0.1".to_string(),
             port: 22,
             username: "user".to_string(),
+            via: None,
         };
         let key2 = PoolKey {
             host: "127.0.0.1".to_string(),
             port: 22,
             username: "user".to_string(),
+            via: None,
         };
         let key3 = PoolKey {
             host: "192.168.1.1".to_string(),
             port: 22,
             username: "user".to_string(),
+            via: None,
         };
 
         assert_eq!(key1, key2);
@@ -135,4 +145,21 @@ mod tests {
         // However, we verify the remove logic runs without panic.
         pool.remove("localhost", 22, "root");
     }
+
+    #[test]
+    fn test_pool_key_includes_jump_chain() {
+        let bastion = SshTarget {
+            host: "bastion.example.com".to_string(),
+            port: 22,
+            username: "ops".to_string(),
+            auth: crate::ssh::connection::SshAuth::Agent,
+            host_key: String::new(),
+            jump: None,
+        };
+        let direct = SshTarget { host: "10.0.0.5".to_string(), ..bastion.clone() };
+        let via_bastion = SshTarget { jump: Some(Box::new(bastion.clone())), ..direct.clone() };
+
+        assert_ne!(PoolKey::for_target(&direct), PoolKey::for_target(&via_bastion));
+        assert!(PoolKey::for_target(&via_bastion).is("10.0.0.5", 22, "ops"));
+    }
 }
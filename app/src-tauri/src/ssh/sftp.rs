@@ -1,7 +1,14 @@
+use std::collections::VecDeque;
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use ssh2::{FileStat, OpenFlags, OpenType, RenameFlags, Session};
+use crate::file_version::{self, FileVersion};
 use crate::ssh::pool::SshPool;
-use crate::ssh::connection::is_fatal_error;
+use crate::ssh::connection::{is_fatal_error, SshTarget};
+
+const LIBSSH2_FX_NO_SUCH_FILE: i32 = 2;
+/// Upper bound on directories listed by one `sftp_find_files_with_pool` walk
+const MAX_WALK_DIRS: usize = 5000;
 
 /// SFTP-based file operations module
 /// 
@@ -9,17 +16,15 @@ use crate::ssh::connection::is_fatal_error;
 /// and handle binary files/special characters correctly.
 
 /// Read a file from remote server using SFTP
-pub async fn sftp_read_file_with_pool(pool: SshPool, host: &str, port: u16, username: &str, private_key: &str, remote_path: &str) -> Result<String, String> {
-    let host = host.to_string();
-    let username = username.to_string();
-    let private_key = private_key.to_string();
+pub async fn sftp_read_file_with_pool(pool: SshPool, target: &SshTarget, remote_path: &str) -> Result<String, String> {
+    let target = target.clone();
     let remote_path = remote_path.to_string();
     let pool = pool.clone();
     
     tokio::task::spawn_blocking(move || {
         // Retry logic
         for attempt in 0..2 {
-            let session_arc = pool.get_connection(&host, port, &username, &private_key)?;
+            let session_arc = pool.get_connection(&target)?;
             let sess = session_arc.lock().map_err(|_| "Poisoned lock".to_string())?;
             
             match sess.sftp() {
@@ -35,7 +40,7 @@ pub async fn sftp_read_file_with_pool(pool: SshPool, host: &str, port: u16, user
                                         
                                         if is_fatal_error(&err_msg) {
                                             println!("[SFTP Pool] Error is fatal. Removing connection.");
-                                            drop(sess); pool.remove(&host, port, &username);
+                                            drop(sess); pool.remove(&target.host, target.port, &target.username);
                                             if attempt == 0 { continue; }
                                         }
                                         return Err(err_msg);
@@ -48,7 +53,7 @@ pub async fn sftp_read_file_with_pool(pool: SshPool, host: &str, port: u16, user
                                     
                                     if is_fatal_error(&err_msg) {
                                         println!("[SFTP Pool] Error is fatal. Removing connection.");
-                                        drop(sess); pool.remove(&host, port, &username);
+                                        drop(sess); pool.remove(&target.host, target.port, &target.username);
                                         if attempt == 0 { continue; }
                                     }
                                     return Err(format!("Failed to open remote file '{}': {}", expanded_path, err_msg));
@@ -59,7 +64,7 @@ pub async fn sftp_read_file_with_pool(pool: SshPool, host: &str, port: u16, user
                              let err_msg = e; // String
                              println!("[SFTP Pool] Expand path error: {}.", err_msg);
                              if is_fatal_error(&err_msg) {
-                                 drop(sess); pool.remove(&host, port, &username);
+                                 drop(sess); pool.remove(&target.host, target.port, &target.username);
                                  if attempt == 0 { continue; }
                              }
                              return Err(err_msg);
@@ -69,7 +74,7 @@ pub async fn sftp_read_file_with_pool(pool: SshPool, host: &str, port: u16, user
                 Err(e) => {
                     let err_msg = e.to_string();
                     println!("[SFTP Pool] SFTP init error: {}. Retrying...", err_msg);
-                    drop(sess); pool.remove(&host, port, &username);
+                    drop(sess); pool.remove(&target.host, target.port, &target.username);
                     if attempt == 0 { continue; } else { return Err(err_msg); }
                 }
             }
@@ -80,159 +85,255 @@ pub async fn sftp_read_file_with_pool(pool: SshPool, host: &str, port: u16, user
     .map_err(|e| format!("Task join error: {}", e))?
 }
 
-pub async fn sftp_write_file_with_pool(pool: SshPool, host: &str, port: u16, username: &str, private_key: &str, remote_path: &str, content: &str) -> Result<(), String> {
-    let host = host.to_string();
-    let username = username.to_string();
-    let private_key = private_key.to_string();
+/// Read a file along with the version a later `sftp_write_file_with_pool` should expect.
+/// A missing file is not an error: it yields `None` and `FileVersion::missing()`.
+pub async fn sftp_read_file_versioned_with_pool(pool: SshPool, target: &SshTarget, remote_path: &str) -> Result<(Option<String>, FileVersion), String> {
+    let target = target.clone();
+    let remote_path = remote_path.to_string();
+
+    tokio::task::spawn_blocking(move || {
+        with_sftp(&pool, &target, |_, sftp| {
+            let expanded_path = expand_tilde_path(sftp, &remote_path)?;
+            read_current(sftp, &expanded_path)
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Replace a remote file atomically: the content goes to a temp file in the same directory (keeping the
+/// original permissions) which is then renamed over the target, so readers never see a partial file.
+///
+/// With `expected`, the write is refused with a `WriteConflict` when the file changed since the caller read it.
+/// Returns the version of the newly written file.
+pub async fn sftp_write_file_with_pool(
+    pool: SshPool,
+    target: &SshTarget,
+    remote_path: &str,
+    content: &str,
+    expected: Option<FileVersion>,
+) -> Result<FileVersion, String> {
+    let target = target.clone();
     let remote_path = remote_path.to_string();
     let content = content.to_string();
-    let pool = pool.clone();
-    
+
     tokio::task::spawn_blocking(move || {
-        for attempt in 0..2 {
-            let session_arc = pool.get_connection(&host, port, &username, &private_key)?;
-            let sess = session_arc.lock().map_err(|_| "Poisoned lock".to_string())?;
-            
-            match sess.sftp() {
-                Ok(sftp) => {
-                    match expand_tilde_path(&sftp, &remote_path) {
-                        Ok(expanded_path) => {
-                             match sftp.create(Path::new(&expanded_path)) {
-                                 Ok(mut file) => {
-                                     if let Err(e) = file.write_all(content.as_bytes()) {
-                                          let err_msg = e.to_string();
-                                          println!("[SFTP Pool] Write error: {}.", err_msg);
-                                          if is_fatal_error(&err_msg) {
-                                              drop(sess); pool.remove(&host, port, &username);
-                                              if attempt == 0 { continue; }
-                                          }
-                                          return Err(err_msg);
-                                     }
-                                     return Ok(());
-                                 },
-                                 Err(e) => {
-                                      let err_msg = e.to_string();
-                                      println!("[SFTP Pool] Create error: {}.", err_msg);
-                                      if is_fatal_error(&err_msg) {
-                                          drop(sess); pool.remove(&host, port, &username);
-                                          if attempt == 0 { continue; }
-                                      }
-                                      return Err(format!("Failed to create remote file '{}': {}", expanded_path, err_msg));
-                                 }
-                             }
-                        },
-                        Err(e) => {
-                             let err_msg = e;
-                             if is_fatal_error(&err_msg) {
-                                 drop(sess); pool.remove(&host, port, &username);
-                                 if attempt == 0 { continue; }
-                             }
-                             return Err(err_msg);
-                        }
+        with_sftp(&pool, &target, |sess, sftp| {
+            let expanded_path = expand_tilde_path(sftp, &remote_path)?;
+            let (_, current) = read_current(sftp, &expanded_path)?;
+
+            if let Some(expected) = &expected {
+                if let Err(conflict) = file_version::check_version(&expanded_path, expected, &current) {
+                    // A retry after a dropped connection may find our own write already in place
+                    if current.hash.as_deref() != Some(file_version::content_hash(&content).as_str()) {
+                        return Err(conflict.into());
                     }
-                },
-                Err(e) => {
-                    let err_msg = e.to_string();
-                    drop(sess); pool.remove(&host, port, &username);
-                    if attempt == 0 { continue; } else { return Err(err_msg); }
+                    return Ok(current);
                 }
             }
-        }
-        Err("Max retries exceeded".to_string())
+
+            let perm = sftp.stat(Path::new(&expanded_path)).ok().and_then(|s| s.perm).map(|p| p & 0o7777);
+            replace_file(sess, sftp, &expanded_path, content.as_bytes(), perm)?;
+
+            let mtime = sftp.stat(Path::new(&expanded_path)).ok().and_then(|s| s.mtime);
+            Ok(FileVersion::of(&content, mtime))
+        })
     })
     .await
     .map_err(|e| format!("Task join error: {}", e))?
 }
 
-pub async fn sftp_copy_file_with_pool(pool: SshPool, host: &str, port: u16, username: &str, private_key: &str, src_path: &str, dst_path: &str) -> Result<(), String> {
-    let host = host.to_string();
-    let username = username.to_string();
-    let private_key = private_key.to_string();
-    let src_path = src_path.to_string();
-    let dst_path = dst_path.to_string();
-    let pool = pool.clone();
-    
+/// Find files named `file_name` under `root`, at most `max_depth` directories down. Symlinks are not followed,
+/// and hidden directories or ones named in `skip_dirs` are not entered. Unreadable subdirectories are skipped.
+pub async fn sftp_find_files_with_pool(
+    pool: SshPool,
+    target: &SshTarget,
+    root: &str,
+    file_name: &str,
+    max_depth: usize,
+    skip_dirs: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let target = target.clone();
+    let root = root.to_string();
+    let file_name = file_name.to_string();
+
     tokio::task::spawn_blocking(move || {
-        for attempt in 0..2 {
-            let session_arc = pool.get_connection(&host, port, &username, &private_key)?;
-            let sess = session_arc.lock().map_err(|_| "Poisoned lock".to_string())?;
-            
-            match sess.sftp() {
-                Ok(sftp) => {
-                    let expanded_src = match expand_tilde_path(&sftp, &src_path) {
-                        Ok(p) => p,
-                        Err(e) => { 
-                            if is_fatal_error(&e) {
-                                drop(sess); pool.remove(&host, port, &username); 
-                                if attempt == 0 { continue; } 
-                            }
-                            return Err(format!("Failed to expand src: {}", e)); 
-                        }
-                    };
-                    let expanded_dst = match expand_tilde_path(&sftp, &dst_path) {
-                         Ok(p) => p,
-                         Err(e) => { 
-                             if is_fatal_error(&e) {
-                                 drop(sess); pool.remove(&host, port, &username); 
-                                 if attempt == 0 { continue; } 
-                             }
-                             return Err(format!("Failed to expand dst: {}", e)); 
-                         }
-                    };
-                    
-                    let mut src_file = match sftp.open(Path::new(&expanded_src)) {
-                         Ok(f) => f,
-                         Err(e) => { 
-                             let err_msg = e.to_string();
-                             if is_fatal_error(&err_msg) {
-                                 drop(sess); pool.remove(&host, port, &username); 
-                                 if attempt == 0 { continue; } 
-                             }
-                             return Err(err_msg); 
-                         }
-                    };
-                    
-                    let mut contents = Vec::new();
-                    if let Err(e) = src_file.read_to_end(&mut contents) {
-                         let err_msg = e.to_string();
-                         if is_fatal_error(&err_msg) {
-                             drop(sess); pool.remove(&host, port, &username);
-                             if attempt == 0 { continue; } 
-                         }
-                         return Err(err_msg);
+        with_sftp(&pool, &target, |_, sftp| {
+            let root = PathBuf::from(expand_tilde_path(sftp, &root)?);
+            let mut found = Vec::new();
+            let mut queue = VecDeque::from([(root.clone(), 0usize)]);
+            let mut visited = 0;
+
+            while let Some((dir, depth)) = queue.pop_front() {
+                visited += 1;
+                if visited > MAX_WALK_DIRS {
+                    println!("[SFTP Pool] Stopped walking {} after {} directories", root.display(), MAX_WALK_DIRS);
+                    break;
+                }
+
+                let entries = match sftp.readdir(&dir) {
+                    Ok(entries) => entries,
+                    Err(e) if dir == root || is_fatal_error(&e.to_string()) => {
+                        return Err(format!("Failed to list remote directory '{}': {}", dir.display(), e));
                     }
-                    
-                    let mut dst_file = match sftp.create(Path::new(&expanded_dst)) {
-                         Ok(f) => f,
-                         Err(e) => { 
-                             let err_msg = e.to_string();
-                             if is_fatal_error(&err_msg) {
-                                 drop(sess); pool.remove(&host, port, &username); 
-                                 if attempt == 0 { continue; } 
-                             }
-                             return Err(err_msg); 
-                         }
-                    };
-                    
-                    if let Err(e) = dst_file.write_all(&contents) {
-                         let err_msg = e.to_string();
-                         if is_fatal_error(&err_msg) {
-                             drop(sess); pool.remove(&host, port, &username);
-                             if attempt == 0 { continue; } 
-                         }
-                         return Err(err_msg);
+                    Err(_) => continue,
+                };
+
+                for (path, stat) in entries {
+                    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                    if stat.is_file() && name == file_name {
+                        found.push(path.to_string_lossy().to_string());
+                    } else if stat.is_dir() && depth < max_depth && !name.starts_with('.') && !skip_dirs.contains(&name) {
+                        queue.push_back((path, depth + 1));
                     }
-                    
-                    return Ok(());
-                },
-                Err(e) => {
-                    let err_msg = e.to_string();
-                    drop(sess); pool.remove(&host, port, &username);
-                    if attempt == 0 { continue; } else { return Err(err_msg); }
                 }
             }
+
+            found.sort();
+            Ok(found)
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Run an SFTP operation on a pooled session, reconnecting once if the session turned out to be dead
+fn with_sftp<T>(pool: &SshPool, target: &SshTarget, op: impl Fn(&Session, &ssh2::Sftp) -> Result<T, String>) -> Result<T, String> {
+    for attempt in 0..2 {
+        let session_arc = pool.get_connection(target)?;
+        let sess = session_arc.lock().map_err(|_| "Poisoned lock".to_string())?;
+
+        let (result, retry) = match sess.sftp() {
+            Ok(sftp) => {
+                let result = op(&sess, &sftp);
+                let retry = matches!(&result, Err(e) if is_fatal_error(e));
+                (result, retry)
+            }
+            Err(e) => (Err(e.to_string()), true),
+        };
+
+        if retry {
+            println!("[SFTP Pool] Error is fatal: {}. Removing connection.", result.as_ref().err().map(String::as_str).unwrap_or(""));
+            drop(sess);
+            pool.remove(&target.host, target.port, &target.username);
+            if attempt == 0 {
+                continue;
+            }
         }
-        Err("Max retries exceeded".to_string())
+        return result;
+    }
+    Err("Max retries exceeded".to_string())
+}
+
+/// Current content and version of a file, `None` content when it doesn't exist
+fn read_current(sftp: &ssh2::Sftp, path: &str) -> Result<(Option<String>, FileVersion), String> {
+    let mut file = match sftp.open(Path::new(path)) {
+        Ok(f) => f,
+        Err(e) if e.code() == ssh2::ErrorCode::SFTP(LIBSSH2_FX_NO_SUCH_FILE) => return Ok((None, FileVersion::missing())),
+        Err(e) => return Err(format!("Failed to open remote file '{}': {}", path, e)),
+    };
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).map_err(|e| e.to_string())?;
+    let mtime = file.stat().ok().and_then(|s| s.mtime);
+    let version = FileVersion::of(&contents, mtime);
+    Ok((Some(contents), version))
+}
+
+/// Write `data` to a sibling temp file and rename it over `path`
+fn replace_file(sess: &Session, sftp: &ssh2::Sftp, path: &str, data: &[u8], perm: Option<u32>) -> Result<(), String> {
+    let tmp_path = file_version::temp_path_for(path);
+    // New files may hold API keys in server env vars, so they start private
+    let mode = perm.unwrap_or(0o600);
+
+    let written = (|| {
+        let mut tmp = sftp
+            .open_mode(
+                Path::new(&tmp_path),
+                OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::EXCLUSIVE | OpenFlags::TRUNCATE,
+                mode as i32,
+                OpenType::File,
+            )
+            .map_err(|e| format!("Failed to create temp file '{}': {}", tmp_path, e))?;
+        tmp.write_all(data).map_err(|e| format!("Failed to write temp file '{}': {}", tmp_path, e))?;
+        // Not every server supports fsync@openssh.com
+        let _ = tmp.fsync();
+        drop(tmp);
+
+        // The server umask applies on create; set the mode explicitly to match the original
+        let _ = sftp.setstat(Path::new(&tmp_path), FileStat { size: None, uid: None, gid: None, perm: Some(mode), atime: None, mtime: None });
+        rename_over(sess, sftp, &tmp_path, path)
+    })();
+
+    if written.is_err() {
+        let _ = sftp.unlink(Path::new(&tmp_path));
+    }
+    written
+}
+
+/// Move the temp file over `path` in one step. SFTPv3 servers (OpenSSH included) ignore rename flags and
+/// refuse to overwrite, and libssh2 doesn't expose `posix-rename@openssh.com`, so an existing target is
+/// replaced by `mv -f` (`Move-Item -Force` on Windows) over an exec channel. There is no two-step swap:
+/// if that fails too, the write fails and the original file stays untouched.
+fn rename_over(sess: &Session, sftp: &ssh2::Sftp, tmp_path: &str, path: &str) -> Result<(), String> {
+    let flags = Some(RenameFlags::OVERWRITE | RenameFlags::ATOMIC | RenameFlags::NATIVE);
+    if sftp.rename(Path::new(tmp_path), Path::new(path), flags).is_ok() {
+        return Ok(());
+    }
+
+    let command = move_command(tmp_path, path);
+    let failed = |e: String| format!("Failed to move temp file into place at '{}': {}", path, e);
+
+    let mut channel = sess.channel_session().map_err(|e| failed(e.to_string()))?;
+    channel.exec(&command).map_err(|e| failed(e.to_string()))?;
+    let mut errors = String::new();
+    let _ = channel.read_to_string(&mut String::new());
+    let _ = channel.stderr().read_to_string(&mut errors);
+    channel.wait_close().map_err(|e| failed(e.to_string()))?;
+
+    match channel.exit_status() {
+        Ok(0) => Ok(()),
+        Ok(status) => Err(failed(format!("exit status {}: {}", status, errors.trim()))),
+        Err(e) => Err(failed(e.to_string())),
+    }
+}
+
+/// Shell command that renames `from` over `to`. SFTP paths on Windows servers look like `/C:/Users/...`.
+fn move_command(from: &str, to: &str) -> String {
+    match (windows_path(from), windows_path(to)) {
+        (Some(from), Some(to)) => format!(
+            "powershell -NoProfile -Command \"Move-Item -LiteralPath '{}' -Destination '{}' -Force\"",
+            from.replace('\'', "''"),
+            to.replace('\'', "''")
+        ),
+        _ => format!("mv -f -- {} {}", sh_quote(from), sh_quote(to)),
+    }
+}
+
+fn windows_path(path: &str) -> Option<&str> {
+    let path = path.strip_prefix('/').unwrap_or(path);
+    let bytes = path.as_bytes();
+    (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':').then_some(path)
+}
+
+fn sh_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Delete a remote file; a file that is already gone is not an error
+pub async fn sftp_remove_file_with_pool(pool: SshPool, target: &SshTarget, remote_path: &str) -> Result<(), String> {
+    let target = target.clone();
+    let remote_path = remote_path.to_string();
+
+    tokio::task::spawn_blocking(move || {
+        with_sftp(&pool, &target, |_, sftp| {
+            let expanded_path = expand_tilde_path(sftp, &remote_path)?;
+            match sftp.unlink(Path::new(&expanded_path)) {
+                Ok(()) => Ok(()),
+                Err(e) if e.code() == ssh2::ErrorCode::SFTP(LIBSSH2_FX_NO_SUCH_FILE) => Ok(()),
+                Err(e) => Err(format!("Failed to delete remote file '{}': {}", expanded_path, e)),
+            }
+        })
     })
     .await
     .map_err(|e| format!("Task join error: {}", e))?
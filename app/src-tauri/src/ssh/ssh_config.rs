@@ -0,0 +1,272 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A concrete `Host` entry of an OpenSSH client config, with matching defaults applied
+#[derive(Debug, Clone, Serialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SshConfigHost {
+    pub alias: String,
+    pub host_name: Option<String>,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub identity_files: Vec<String>, // `~` already expanded
+    pub proxy_jump: Option<String>,
+}
+
+impl SshConfigHost {
+    /// Address to connect to (HostName, falling back to the alias like ssh does)
+    pub fn address(&self) -> &str {
+        self.host_name.as_deref().unwrap_or(&self.alias)
+    }
+}
+
+/// One `Host` block as written: its patterns and `key value` options in file order
+struct Block {
+    patterns: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+pub fn default_config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh").join("config"))
+}
+
+/// Read and parse a config file (following `Include`), returning every concrete host alias
+pub fn load_ssh_config(path: &Path) -> Result<Vec<SshConfigHost>, String> {
+    let mut blocks = vec![Block { patterns: vec!["*".to_string()], options: vec![] }];
+    read_blocks(path, &mut blocks, 0)?;
+    Ok(resolve_hosts(&blocks))
+}
+
+fn read_blocks(path: &Path, blocks: &mut Vec<Block>, depth: usize) -> Result<(), String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read SSH config '{}': {}", path.display(), e))?;
+    parse_into(&content, Some(path), blocks, depth);
+    Ok(())
+}
+
+fn parse_into(content: &str, source: Option<&Path>, blocks: &mut Vec<Block>, depth: usize) {
+    for raw_line in content.lines() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // "Key value", "Key=value" or "Key = value"
+        let (key, value) = match line.find(|c: char| c.is_whitespace() || c == '=') {
+            Some(idx) => {
+                let value = line[idx..].trim_start_matches(|c: char| c.is_whitespace() || c == '=');
+                (line[..idx].to_lowercase(), value.trim().to_string())
+            }
+            None => continue,
+        };
+
+        match key.as_str() {
+            "host" => blocks.push(Block {
+                patterns: value.split_whitespace().map(|p| p.trim_matches('"').to_string()).collect(),
+                options: vec![],
+            }),
+            // Match blocks need runtime criteria (exec, user, ...); nothing in them is imported
+            "match" => blocks.push(Block { patterns: vec![], options: vec![] }),
+            "include" if depth < 8 => {
+                for pattern in value.split_whitespace() {
+                    for file in include_files(pattern, source) {
+                        let _ = read_blocks(&file, blocks, depth + 1);
+                    }
+                }
+            }
+            _ => {
+                if let Some(block) = blocks.last_mut() {
+                    block.options.push((key, value.trim_matches('"').to_string()));
+                }
+            }
+        }
+    }
+}
+
+/// Files named by an `Include` argument. Relative paths resolve against the including file's directory
+/// (~/.ssh for the user config); `*`/`?` globs apply to the file name.
+fn include_files(pattern: &str, source: Option<&Path>) -> Vec<PathBuf> {
+    let expanded = expand_home(pattern);
+    let path = if Path::new(&expanded).is_absolute() {
+        PathBuf::from(expanded)
+    } else {
+        match source.and_then(|s| s.parent()).map(Path::to_path_buf).or_else(|| dirs::home_dir().map(|h| h.join(".ssh"))) {
+            Some(dir) => dir.join(expanded),
+            None => return vec![],
+        }
+    };
+
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    if !file_name.contains('*') && !file_name.contains('?') {
+        return vec![path];
+    }
+
+    let dir = match path.parent() {
+        Some(dir) => dir,
+        None => return vec![],
+    };
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| glob_match(&file_name, &e.file_name().to_string_lossy()))
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// Build one entry per concrete alias. As in ssh, the first value found for an option wins,
+/// scanning every block whose patterns match the alias in file order.
+fn resolve_hosts(blocks: &[Block]) -> Vec<SshConfigHost> {
+    let mut aliases: Vec<String> = Vec::new();
+    for block in blocks {
+        for pattern in &block.patterns {
+            let concrete = !pattern.contains(['*', '?', '!']);
+            if concrete && !aliases.contains(pattern) {
+                aliases.push(pattern.clone());
+            }
+        }
+    }
+
+    aliases
+        .into_iter()
+        .map(|alias| {
+            let mut host = SshConfigHost { alias: alias.clone(), ..Default::default() };
+            for block in blocks.iter().filter(|b| block_matches(&b.patterns, &alias)) {
+                for (key, value) in &block.options {
+                    match key.as_str() {
+                        "hostname" if host.host_name.is_none() => host.host_name = Some(value.clone()),
+                        "port" if host.port.is_none() => host.port = value.parse().ok(),
+                        "user" if host.user.is_none() => host.user = Some(value.clone()),
+                        "proxyjump" if host.proxy_jump.is_none() && value != "none" => {
+                            host.proxy_jump = Some(value.clone())
+                        }
+                        // IdentityFile accumulates rather than first-wins
+                        "identityfile" if value != "none" => host.identity_files.push(expand_home(value)),
+                        _ => {}
+                    }
+                }
+            }
+            host
+        })
+        .collect()
+}
+
+fn block_matches(patterns: &[String], alias: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if glob_match(negated, alias) {
+                return false;
+            }
+        } else if glob_match(pattern, alias) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// ssh_config pattern matching: `*` any sequence, `?` one character
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let (p, t): (Vec<char>, Vec<char>) = (pattern.chars().collect(), text.chars().collect());
+    let (mut pi, mut ti) = (0, 0);
+    let (mut star, mut mark) = (None, 0);
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some(pi);
+            mark = ti;
+            pi += 1;
+        } else if let Some(s) = star {
+            pi = s + 1;
+            mark += 1;
+            ti = mark;
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/// Expand a leading `~` and the `%d` (home directory) token
+fn expand_home(path: &str) -> String {
+    let home = match dirs::home_dir() {
+        Some(home) => home.to_string_lossy().to_string(),
+        None => return path.to_string(),
+    };
+
+    let path = path.replace("%d", &home);
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\') => format!("{}{}", home, rest),
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse config text without `Include` support
+    fn parse_ssh_config(content: &str) -> Vec<SshConfigHost> {
+        let mut blocks = vec![Block { patterns: vec!["*".to_string()], options: vec![] }];
+        parse_into(content, None, &mut blocks, 0);
+        resolve_hosts(&blocks)
+    }
+
+    #[test]
+    fn test_parse_hosts_with_defaults() {
+        let config = "\
+# Work boxes
+Host bastion
+    HostName bastion.example.com
+    User ops
+
+Host web1 web2
+    HostName=10.0.0.10
+    Port 2222
+    ProxyJump bastion
+    IdentityFile ~/.ssh/id_work
+
+Host *.internal !skip.internal
+    User internal
+
+Host *
+    User fallback
+    IdentityFile ~/.ssh/id_default
+";
+        let hosts = parse_ssh_config(config);
+        let aliases: Vec<&str> = hosts.iter().map(|h| h.alias.as_str()).collect();
+        assert_eq!(aliases, vec!["bastion", "web1", "web2"]);
+
+        let bastion = &hosts[0];
+        assert_eq!(bastion.address(), "bastion.example.com");
+        assert_eq!(bastion.user.as_deref(), Some("ops"));
+        assert_eq!(bastion.port, None);
+        assert_eq!(bastion.identity_files.len(), 1);
+        assert!(bastion.identity_files[0].ends_with("id_default"));
+
+        let web2 = &hosts[2];
+        assert_eq!(web2.host_name.as_deref(), Some("10.0.0.10"));
+        assert_eq!(web2.port, Some(2222));
+        assert_eq!(web2.user.as_deref(), Some("fallback"));
+        assert_eq!(web2.proxy_jump.as_deref(), Some("bastion"));
+        assert!(web2.identity_files[0].ends_with("id_work"));
+        assert!(!web2.identity_files[0].starts_with('~'));
+    }
+
+    #[test]
+    fn test_glob_and_negation() {
+        assert!(glob_match("*.internal", "db.internal"));
+        assert!(glob_match("web?", "web1"));
+        assert!(!glob_match("web?", "web10"));
+        assert!(block_matches(&["*.internal".to_string()], "db.internal"));
+        assert!(!block_matches(&["*.internal".to_string(), "!db.internal".to_string()], "db.internal"));
+    }
+}
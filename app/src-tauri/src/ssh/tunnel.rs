@@ -0,0 +1,165 @@
+use ssh2::{Channel, Session};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Cap on bytes buffered in either direction before we stop reading that side
+const MAX_PENDING: usize = 256 * 1024;
+/// Wait between polls of an idle channel, doubled up to `MAX_IDLE_WAIT` while nothing moves
+pub(crate) const MIN_IDLE_WAIT: Duration = Duration::from_millis(2);
+pub(crate) const MAX_IDLE_WAIT: Duration = Duration::from_millis(100);
+
+/// Open a `direct-tcpip` channel to host:port on the jump session and expose it as a local socket.
+///
+/// libssh2 can only run a session over a real socket, so the channel is bridged to a loopback
+/// connection by a pump thread. The thread owns a clone of the jump session and exits when either
+/// side closes, which happens when the session using the returned stream is dropped.
+pub fn open_tunnel(jump: Arc<Mutex<Session>>, host: &str, port: u16) -> Result<TcpStream, String> {
+    let channel = {
+        let sess = jump.lock().map_err(|_| "Poisoned lock".to_string())?;
+        sess.channel_direct_tcpip(host, port, None)
+            .map_err(|e| format!("Jump host could not open a channel to {}:{}: {}", host, port, e))?
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .map_err(|e| format!("Failed to bind tunnel listener: {}", e))?;
+    let local_addr = listener.local_addr().map_err(|e| e.to_string())?;
+
+    let client = TcpStream::connect(local_addr)
+        .map_err(|e| format!("Failed to connect tunnel socket: {}", e))?;
+    let (server_side, peer) = listener.accept()
+        .map_err(|e| format!("Failed to accept tunnel socket: {}", e))?;
+
+    // SECURITY: Only our own socket may attach to the tunnel, not another local process racing us
+    if Some(peer) != client.local_addr().ok() {
+        return Err("Unexpected connection on tunnel socket".to_string());
+    }
+
+    println!("[SSH Tunnel] Forwarding {} -> {}:{}", local_addr, host, port);
+    let target = format!("{}:{}", host, port);
+    thread::spawn(move || {
+        pump(&jump, channel, server_side);
+        println!("[SSH Tunnel] Closed tunnel to {}", target);
+    });
+
+    Ok(client)
+}
+
+/// Shuttle bytes between the loopback socket and the channel until either side closes.
+///
+/// The jump session is shared with other users (its own commands, other tunnels), so it is only
+/// switched to non-blocking mode while we hold its lock, and switched back before releasing it.
+/// Between steps the thread waits on the local socket, outside the lock, for longer the longer
+/// the tunnel stays idle.
+fn pump(jump: &Arc<Mutex<Session>>, mut channel: Channel, mut local: TcpStream) {
+    if local.set_write_timeout(Some(MIN_IDLE_WAIT)).is_err() {
+        return;
+    }
+
+    let mut buf = vec![0u8; 32 * 1024];
+    let mut to_remote: Vec<u8> = Vec::new();
+    let mut to_local: Vec<u8> = Vec::new();
+    let mut idle = MIN_IDLE_WAIT;
+
+    loop {
+        let mut progressed = false;
+
+        // Local socket -> pending remote bytes. Only wait briefly while there is output to deliver.
+        if to_remote.len() < MAX_PENDING {
+            let wait = if to_local.is_empty() { idle } else { MIN_IDLE_WAIT };
+            if local.set_read_timeout(Some(wait)).is_err() {
+                break;
+            }
+            match local.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    to_remote.extend_from_slice(&buf[..n]);
+                    progressed = true;
+                }
+                Err(e) if is_timeout(&e) => {}
+                Err(_) => break,
+            }
+        } else {
+            thread::sleep(idle);
+        }
+
+        // Pending local bytes -> local socket
+        if !to_local.is_empty() {
+            match local.write(&to_local) {
+                Ok(n) => {
+                    to_local.drain(..n);
+                    progressed = true;
+                }
+                Err(e) if is_timeout(&e) => {}
+                Err(_) => break,
+            }
+        }
+
+        // Channel side, under the session lock
+        let step = {
+            let sess = match jump.lock() {
+                Ok(sess) => sess,
+                Err(_) => break,
+            };
+            sess.set_blocking(false);
+            let step = channel_step(&mut channel, &mut buf, &mut to_remote, &mut to_local);
+            sess.set_blocking(true);
+            step
+        };
+
+        match step {
+            Some(true) => progressed = true,
+            Some(false) => {}
+            None => {
+                // Remote closed: flush what we have, then stop
+                let _ = local.set_write_timeout(None);
+                let _ = local.write_all(&to_local);
+                break;
+            }
+        }
+
+        idle = if progressed { MIN_IDLE_WAIT } else { (idle * 2).min(MAX_IDLE_WAIT) };
+    }
+
+    if let Ok(_sess) = jump.lock() {
+        let _ = channel.close();
+    }
+}
+
+/// Socket timeouts surface as `WouldBlock` on Unix and `TimedOut` on Windows
+fn is_timeout(e: &std::io::Error) -> bool {
+    matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
+
+/// One non-blocking exchange with the channel. `None` once the channel is closed or broken.
+fn channel_step(channel: &mut Channel, buf: &mut [u8], to_remote: &mut Vec<u8>, to_local: &mut Vec<u8>) -> Option<bool> {
+    let mut progressed = false;
+
+    if !to_remote.is_empty() {
+        match channel.write(to_remote) {
+            Ok(n) => {
+                to_remote.drain(..n);
+                progressed = true;
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(_) => return None,
+        }
+    }
+
+    if to_local.len() < MAX_PENDING {
+        match channel.read(buf) {
+            Ok(0) if channel.eof() => return None,
+            Ok(0) => {}
+            Ok(n) => {
+                to_local.extend_from_slice(&buf[..n]);
+                progressed = true;
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(_) => return None,
+        }
+    }
+
+    Some(progressed)
+}
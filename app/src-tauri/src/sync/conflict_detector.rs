@@ -1,4 +1,6 @@
-use crate::config::{ClaudeMCPServer, OpenCodeMCPServer};
+use crate::config::converter::Platform;
+use crate::config::json_edit::sort_keys;
+use crate::config::{ConfigSource, McpServer};
 use serde::Serialize;
 use std::collections::BTreeMap;
 
@@ -8,9 +10,8 @@ struct FingerprintData {
     args: Option<Vec<String>>,
     env: Option<BTreeMap<String, String>>, // BTreeMap for stable ordering
     url: Option<String>,
-    // we don't include enabled/disabled in fingerprint for content conflict?
-    // PRD says: "Hash: name + command + args + env (sorted)"
-    // If enabled state changes, is it a conflict? Usually yes.
+    // Always true: the enabled state isn't part of the content. Kept so hashes already stored in
+    // sync_history still match.
     enabled: bool,
 }
 
@@ -24,48 +25,31 @@ pub enum ChangeStatus {
 pub struct ConflictDetector;
 
 impl ConflictDetector {
-    pub fn fingerprint_claude(server: &ClaudeMCPServer) -> String {
+    /// Content fingerprint; the enabled state is left out so toggling a server is not a content conflict
+    pub fn fingerprint_canonical(server: &McpServer) -> String {
         let data = FingerprintData {
             command: server.command.clone(),
             args: server.args.clone(),
             env: server.env.clone().map(|m| m.into_iter().collect()),
             url: server.url.clone(),
-            // Exclude enabled state from content fingerprint to avoid false content conflicts
             enabled: true,
         };
         Self::hash(&data)
     }
 
-    pub fn fingerprint_opencode(server: &OpenCodeMCPServer) -> String {
-        let (command, args) = if let Some(cmd_vec) = &server.command {
-            if !cmd_vec.is_empty() {
-                (Some(cmd_vec[0].clone()), Some(cmd_vec[1..].to_vec()))
-            } else {
-                (None, None)
-            }
-        } else {
-            (None, None)
-        };
-
-        let data = FingerprintData {
-            command,
-            args,
-            env: server.environment.clone().map(|m| m.into_iter().collect()),
-            url: server.url.clone(),
-            // Exclude enabled state from content fingerprint
-            enabled: true,
-        };
-        Self::hash(&data)
+    /// Fingerprint that ignores `cmd /c` wrapping, so the Windows and Linux copies
+    /// of the same server compare equal during cross-machine sync.
+    pub fn portable_fingerprint_canonical(server: &McpServer) -> String {
+        Self::fingerprint_canonical(&server.adapted_for(Platform::Linux))
     }
 
-    /// Canonical fingerprint for cross-source content matching.
-    /// Normalizes both Claude and OpenCode formats to the same structure.
-    pub fn canonical_fingerprint_claude(server: &ClaudeMCPServer) -> String {
-        Self::fingerprint_claude(server)
-    }
-
-    pub fn canonical_fingerprint_opencode(server: &OpenCodeMCPServer) -> String {
-        Self::fingerprint_opencode(server)
+    /// `portable_fingerprint_canonical` of a server in any source's own format
+    pub fn portable_fingerprint(source: &dyn ConfigSource, server: &serde_json::Value) -> String {
+        match source.to_canonical(server, Platform::Linux) {
+            Ok(canonical) => Self::fingerprint_canonical(&canonical),
+            // Key order is the file's, which says nothing about the server
+            Err(_) => Self::hash(&sort_keys(server)),
+        }
     }
 
     fn hash<T: Serialize>(data: &T) -> String {
@@ -112,3 +96,19 @@ impl ConflictDetector {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SourceKind;
+
+    #[test]
+    fn test_portable_fingerprint_ignores_key_order() {
+        // `args` isn't a list, so neither entry converts and both fall back to hashing the JSON
+        let source = SourceKind::Claude.source();
+        let a: serde_json::Value = serde_json::from_str(r#"{"command": "npx", "x": 1, "args": {"b": 2, "a": 3}}"#).unwrap();
+        let b: serde_json::Value = serde_json::from_str(r#"{"args": {"a": 3, "b": 2}, "x": 1, "command": "npx"}"#).unwrap();
+        assert!(source.to_canonical(&a, Platform::Linux).is_err());
+        assert_eq!(ConflictDetector::portable_fingerprint(source, &a), ConflictDetector::portable_fingerprint(source, &b));
+    }
+}
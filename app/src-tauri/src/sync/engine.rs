@@ -1,12 +1,11 @@
+use crate::config::McpServer;
 use crate::db::sync_history::{self, SyncHistory};
 use crate::sync::diff_generator::DiffLine;
+use crate::sync::merge::{self, ServerMerge};
 use serde::{Deserialize, Serialize};
 use sqlx::{Pool, Sqlite};
 use std::collections::HashMap;
 
-
-
-
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum SyncStatus {
     Synced,
@@ -16,6 +15,8 @@ pub enum SyncStatus {
     CreatedInA,
     DeletedFromA,
     UpdatedInA,
+    /// Both sides changed different fields; the merge applies to both
+    Merged,
     Conflict,
 }
 
@@ -31,7 +32,11 @@ pub struct SyncItem {
     pub opencode_json: Option<String>,  // Pretty-printed OpenCode config JSON
     pub claude_as_opencode_json: Option<String>, // Claude converted to OpenCode format
     pub opencode_as_claude_json: Option<String>, // OpenCode converted to Claude format
+    pub source_json: Option<String>,    // Side A config JSON (cross-machine sync)
+    pub destination_json: Option<String>, // Side B config JSON (cross-machine sync)
     pub content_matches: Vec<String>,   // Names of items in the OTHER list that have identical content
+    pub lost_fields: Vec<String>,       // Fields left out of the other source's file (cross-source sync), kept by the app
+    pub merge: Option<ServerMerge>,     // Field-by-field merge when both sides changed
 }
 
 pub struct SyncEngine<'a> {
@@ -132,7 +137,11 @@ impl<'a> SyncEngine<'a> {
                     opencode_json: None,
                     claude_as_opencode_json: None,
                     opencode_as_claude_json: None,
+                    source_json: None,
+                    destination_json: None,
                     content_matches: matches,
+                    lost_fields: Vec::new(),
+                    merge: None,
                 });
             }
         }
@@ -140,6 +149,48 @@ impl<'a> SyncEngine<'a> {
         Ok(plan)
     }
 
+    /// Merge the items both sides changed (`Conflict` with a server on each side) field by field against the
+    /// snapshot of their last sync. Items whose edits don't overlap become `Merged`; the others stay `Conflict`
+    /// with the fields in conflict listed. `canonical_a`/`canonical_b` hold each side's `merge::snapshot`s.
+    pub async fn resolve_merges(
+        &self,
+        items: &mut [SyncItem],
+        canonical_a: &HashMap<String, McpServer>,
+        canonical_b: &HashMap<String, McpServer>,
+    ) -> Result<(), String> {
+        for item in items.iter_mut().filter(|item| item.status == SyncStatus::Conflict) {
+            let (Some(a), Some(b)) = (canonical_a.get(&item.name), canonical_b.get(&item.name)) else {
+                continue;
+            };
+            let base = self.last_snapshot(&item.name).await?;
+            let merge = merge::merge_servers(base.as_ref(), a, b, &HashMap::new())?;
+            if merge.merged.is_some() {
+                item.status = SyncStatus::Merged;
+                item.action_description = self.describe_action(&item.status, &item.name);
+            }
+            item.merge = Some(merge);
+        }
+        Ok(())
+    }
+
+    /// The server as it was last synced, if that sync recorded a snapshot
+    pub async fn last_snapshot(&self, server_name: &str) -> Result<Option<McpServer>, String> {
+        let history = sync_history::get_sync_history(self.pool, &self.scope, &self.target_id, server_name).await?;
+        Ok(history
+            .and_then(|h| h.snapshot)
+            .and_then(|snapshot| serde_json::from_str(&snapshot).ok()))
+    }
+
+    /// Remember `server_name` as synced with this content
+    pub async fn record_synced(&self, server_name: &str, hash: &str, snapshot: Option<&str>) -> Result<(), String> {
+        sync_history::upsert_sync_history(self.pool, &self.scope, &self.target_id, server_name, hash, snapshot).await
+    }
+
+    /// Drop `server_name`'s history, after it was deleted on a side
+    pub async fn forget(&self, server_name: &str) -> Result<(), String> {
+        sync_history::delete_sync_history(self.pool, &self.scope, &self.target_id, server_name).await
+    }
+
     async fn determine_status<A, B, FA, FB>(
         &self,
         a: Option<&A>,
@@ -228,6 +279,7 @@ impl<'a> SyncEngine<'a> {
             SyncStatus::CreatedInA => format!("Create {} in Source", key),
             SyncStatus::DeletedFromA => format!("Delete {} from Source", key),
             SyncStatus::UpdatedInA => format!("Update {} in Source", key),
+            SyncStatus::Merged => format!("Merge changes to {} into both sides", key),
             SyncStatus::Conflict => format!("Conflict in {}", key),
         }
     }
@@ -236,30 +288,15 @@ impl<'a> SyncEngine<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use sqlx::sqlite::SqlitePoolOptions;
+    use crate::sync::conflict_detector::ConflictDetector;
+    use crate::db::test_pool;
 
     #[tokio::test]
     async fn test_sync_plan_same_content_different_name() {
         // Setup in-memory DB
-        let pool = SqlitePoolOptions::new()
-            .connect("sqlite::memory:")
-            .await
-            .unwrap();
-        
-        // Initialize DB schema matching `sync_history.rs`
-        sqlx::query("CREATE TABLE sync_history (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            scope TEXT NOT NULL,
-            target_id TEXT NOT NULL,
-            server_name TEXT NOT NULL,
-            last_hash TEXT NOT NULL,
-            last_synced_at TEXT NOT NULL
-        )")
-            .execute(&pool)
-            .await
-            .unwrap();
-
-        let engine = SyncEngine::new(&pool, "test", "local");
+        let pool = test_pool().await;
+
+        let engine = SyncEngine::new(&pool, "cross-source", "claude:opencode:local");
 
         // Data
         let mut source_a = HashMap::new();
@@ -289,24 +326,9 @@ mod tests {
 
     #[tokio::test]
     async fn test_sync_plan_same_name_different_content() {
-        let pool = SqlitePoolOptions::new()
-            .connect("sqlite::memory:")
-            .await
-            .unwrap();
-        
-        sqlx::query("CREATE TABLE sync_history (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            scope TEXT NOT NULL,
-            target_id TEXT NOT NULL,
-            server_name TEXT NOT NULL,
-            last_hash TEXT NOT NULL,
-            last_synced_at TEXT NOT NULL
-        )")
-            .execute(&pool)
-            .await
-            .unwrap();
-
-        let engine = SyncEngine::new(&pool, "test", "local");
+        let pool = test_pool().await;
+
+        let engine = SyncEngine::new(&pool, "cross-source", "claude:opencode:local");
 
         let mut source_a = HashMap::new();
         source_a.insert("server1".to_string(), "content_a");
@@ -322,4 +344,70 @@ mod tests {
         // Since no history, this should be Conflict
         assert_eq!(item.status, SyncStatus::Conflict);
     }
+
+    #[tokio::test]
+    async fn test_resolve_merges_against_last_snapshot() {
+        let pool = test_pool().await;
+
+        let engine = SyncEngine::new(&pool, "cross-source", "claude:opencode:local");
+        let base = merge::snapshot(
+            &McpServer {
+                command: Some("npx".to_string()),
+                args: Some(vec!["-y".to_string(), "server-fs".to_string()]),
+                ..Default::default()
+            },
+            true,
+        );
+        for name in ["fs", "db"] {
+            let snapshot = serde_json::to_string(&base).unwrap();
+            engine.record_synced(name, &ConflictDetector::fingerprint_canonical(&base), Some(&snapshot)).await.unwrap();
+        }
+
+        // fs: A adds an env var, B changes an arg. db: both change the same arg.
+        let mut a_fs = base.clone();
+        a_fs.env = Some(HashMap::from([("TOKEN".to_string(), "x".to_string())]));
+        let mut b_fs = base.clone();
+        b_fs.args = Some(vec!["-y".to_string(), "server-fs@2".to_string()]);
+        let mut a_db = base.clone();
+        a_db.args = Some(vec!["-y".to_string(), "server-db".to_string()]);
+
+        let source_a = HashMap::from([("fs".to_string(), a_fs), ("db".to_string(), a_db)]);
+        let source_b = HashMap::from([("fs".to_string(), b_fs.clone()), ("db".to_string(), b_fs)]);
+        let hasher = |s: &McpServer| ConflictDetector::fingerprint_canonical(s);
+
+        let mut plan = engine.plan(&source_a, &source_b, hasher, hasher, hasher, hasher).await.unwrap();
+        assert!(plan.iter().all(|i| i.status == SyncStatus::Conflict));
+        engine.resolve_merges(&mut plan, &source_a, &source_b).await.unwrap();
+
+        let fs = plan.iter().find(|i| i.name == "fs").unwrap();
+        assert_eq!(fs.status, SyncStatus::Merged);
+        let merged = fs.merge.as_ref().unwrap().merged.clone().unwrap();
+        assert_eq!(merged.args.unwrap()[1], "server-fs@2");
+        assert_eq!(merged.env.unwrap()["TOKEN"], "x");
+
+        let db = plan.iter().find(|i| i.name == "db").unwrap();
+        assert_eq!(db.status, SyncStatus::Conflict);
+        let conflicts = &db.merge.as_ref().unwrap().conflicts;
+        assert_eq!(conflicts.iter().map(|c| c.field.as_str()).collect::<Vec<_>>(), vec!["args[1]"]);
+    }
+
+    #[tokio::test]
+    async fn test_forget_after_deletion() {
+        let pool = test_pool().await;
+
+        let engine = SyncEngine::new(&pool, "cross-source", "claude:opencode:local");
+        let hasher = |s: &&str| format!("hash_{}", s);
+        engine.record_synced("server1", "hash_content", None).await.unwrap();
+
+        // Deleted in A since the last sync
+        let source_a: HashMap<String, &str> = HashMap::new();
+        let source_b = HashMap::from([("server1".to_string(), "content")]);
+        let plan = engine.plan(&source_a, &source_b, hasher, hasher, hasher, hasher).await.unwrap();
+        assert_eq!(plan[0].status, SyncStatus::DeletedFromA);
+
+        // Without history the same state reads as a new server in B
+        engine.forget("server1").await.unwrap();
+        let plan = engine.plan(&source_a, &source_b, hasher, hasher, hasher, hasher).await.unwrap();
+        assert_eq!(plan[0].status, SyncStatus::CreatedInB);
+    }
 }
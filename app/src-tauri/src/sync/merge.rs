@@ -0,0 +1,236 @@
+use crate::config::converter::Platform;
+use crate::config::McpServer;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Fields whose entries are merged one key at a time
+const KEYED_FIELDS: [&str; 3] = ["env", "headers", "extensions"];
+
+/// Which side of a sync a conflicting field is taken from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeSide {
+    A,
+    B,
+}
+
+/// A field both sides changed, to different values. `None` means the field is absent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldConflict {
+    /// `command`, `args[1]`, `env.API_KEY`, `headers.Authorization`, `url`, `enabled`...
+    pub field: String,
+    pub base: Option<Value>,
+    pub a: Option<Value>,
+    pub b: Option<Value>,
+}
+
+/// Three-way merge of one server against the snapshot of its last sync
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerMerge {
+    /// Fields changed only on side A, whose value the merge takes
+    pub from_a: Vec<String>,
+    /// Fields changed only on side B, whose value the merge takes
+    pub from_b: Vec<String>,
+    pub conflicts: Vec<FieldConflict>,
+    /// The merged server, once there is no conflict left
+    pub merged: Option<McpServer>,
+}
+
+/// The form servers are merged and recorded in: command as on Linux (so `cmd /c` wrapping doesn't count as a
+/// change) and the enabled state spelled out, whichever way the file stores it
+pub fn snapshot(server: &McpServer, enabled: bool) -> McpServer {
+    let mut snapshot = server.adapted_for(Platform::Linux);
+    snapshot.enabled = Some(enabled);
+    snapshot
+}
+
+fn to_fields(server: &McpServer, split_args: bool) -> BTreeMap<String, Value> {
+    let mut fields = BTreeMap::new();
+    let Ok(Value::Object(object)) = serde_json::to_value(server) else {
+        return fields;
+    };
+    for (key, value) in object {
+        match value {
+            Value::Object(entries) if KEYED_FIELDS.contains(&key.as_str()) => {
+                for (entry, value) in entries {
+                    fields.insert(format!("{}.{}", key, entry), value);
+                }
+            }
+            Value::Array(args) if key == "args" && split_args => {
+                for (index, arg) in args.into_iter().enumerate() {
+                    fields.insert(format!("args[{}]", index), arg);
+                }
+            }
+            value => {
+                fields.insert(key, value);
+            }
+        }
+    }
+    fields
+}
+
+fn from_fields(fields: BTreeMap<String, Value>) -> Result<McpServer, String> {
+    let mut object = Map::new();
+    let mut args: BTreeMap<usize, Value> = BTreeMap::new();
+    for (field, value) in fields {
+        if let Some(index) = field.strip_prefix("args[").and_then(|rest| rest.strip_suffix(']')) {
+            args.insert(index.parse().map_err(|_| format!("Invalid merge field '{}'", field))?, value);
+            continue;
+        }
+        match field.split_once('.') {
+            Some((key, entry)) if KEYED_FIELDS.contains(&key) => {
+                let entries = object.entry(key).or_insert_with(|| Value::Object(Map::new()));
+                if let Some(entries) = entries.as_object_mut() {
+                    entries.insert(entry.to_string(), value);
+                }
+            }
+            _ => {
+                object.insert(field, value);
+            }
+        }
+    }
+    if !args.is_empty() {
+        object.insert("args".to_string(), Value::Array(args.into_values().collect()));
+    }
+    serde_json::from_value(Value::Object(object)).map_err(|e| format!("Invalid merged server: {}", e))
+}
+
+/// Merge `a` and `b` field by field against `base` (the last synced snapshot; `None` if they were never synced,
+/// in which case every difference is a conflict). A field changed on one side only takes that side's value;
+/// a field changed on both is taken from `choices` or reported as a conflict.
+///
+/// Args are merged one by one while all versions have the same number of them, and as one list otherwise,
+/// since an inserted arg shifts every position after it.
+pub fn merge_servers(
+    base: Option<&McpServer>,
+    a: &McpServer,
+    b: &McpServer,
+    choices: &HashMap<String, MergeSide>,
+) -> Result<ServerMerge, String> {
+    let arg_counts: BTreeSet<Option<usize>> = base
+        .into_iter()
+        .chain([a, b])
+        .map(|server| server.args.as_ref().map(Vec::len))
+        .collect();
+    let split_args = arg_counts.len() == 1;
+
+    let base_fields = base.map(|server| to_fields(server, split_args));
+    let a_fields = to_fields(a, split_args);
+    let b_fields = to_fields(b, split_args);
+    let names: BTreeSet<&String> = base_fields.iter().flatten().chain(&a_fields).chain(&b_fields).map(|(k, _)| k).collect();
+
+    let mut merge = ServerMerge { from_a: Vec::new(), from_b: Vec::new(), conflicts: Vec::new(), merged: None };
+    let mut merged = BTreeMap::new();
+    for name in names {
+        let in_a = a_fields.get(name);
+        let in_b = b_fields.get(name);
+        let in_base = base_fields.as_ref().map(|fields| fields.get(name));
+
+        let value = if in_a == in_b {
+            in_a
+        } else if in_base == Some(in_a) {
+            merge.from_b.push(name.clone());
+            in_b
+        } else if in_base == Some(in_b) {
+            merge.from_a.push(name.clone());
+            in_a
+        } else {
+            match choices.get(name) {
+                Some(MergeSide::A) => in_a,
+                Some(MergeSide::B) => in_b,
+                None => {
+                    merge.conflicts.push(FieldConflict {
+                        field: name.clone(),
+                        base: in_base.flatten().cloned(),
+                        a: in_a.cloned(),
+                        b: in_b.cloned(),
+                    });
+                    continue;
+                }
+            }
+        };
+        if let Some(value) = value {
+            merged.insert(name.clone(), value.clone());
+        }
+    }
+
+    if merge.conflicts.is_empty() {
+        merge.merged = Some(from_fields(merged)?);
+    }
+    Ok(merge)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Transport;
+    use serde_json::json;
+
+    fn server(args: &[&str], env: &[(&str, &str)]) -> McpServer {
+        McpServer {
+            transport: Transport::Stdio,
+            command: Some("npx".to_string()),
+            args: Some(args.iter().map(|s| s.to_string()).collect()),
+            env: Some(env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()),
+            enabled: Some(true),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_non_overlapping_edits_merge() {
+        let base = server(&["-y", "server-fs", "/home"], &[("LOG", "info")]);
+        // A changed an arg and added an env key, B changed another arg and disabled the server
+        let a = server(&["-y", "server-fs@2", "/home"], &[("LOG", "info"), ("TOKEN", "x")]);
+        let mut b = server(&["-y", "server-fs", "/srv"], &[("LOG", "info")]);
+        b.enabled = Some(false);
+
+        let merge = merge_servers(Some(&base), &a, &b, &HashMap::new()).unwrap();
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(merge.from_a, vec!["args[1]", "env.TOKEN"]);
+        assert_eq!(merge.from_b, vec!["args[2]", "enabled"]);
+
+        let mut expected = server(&["-y", "server-fs@2", "/srv"], &[("LOG", "info"), ("TOKEN", "x")]);
+        expected.enabled = Some(false);
+        assert_eq!(merge.merged, Some(expected));
+    }
+
+    #[test]
+    fn test_overlapping_edits_conflict_per_field() {
+        let base = server(&["-y", "server-fs"], &[("LOG", "info")]);
+        let a = server(&["-y", "server-fs"], &[("LOG", "debug")]);
+        let b = server(&["-y", "server-fs", "/srv"], &[]);
+
+        let merge = merge_servers(Some(&base), &a, &b, &HashMap::new()).unwrap();
+        assert_eq!(merge.from_b, vec!["args"]);
+        assert_eq!(
+            merge.conflicts,
+            vec![FieldConflict {
+                field: "env.LOG".to_string(),
+                base: Some(json!("info")),
+                a: Some(json!("debug")),
+                b: None,
+            }]
+        );
+        assert!(merge.merged.is_none());
+
+        let choices = HashMap::from([("env.LOG".to_string(), MergeSide::A)]);
+        let merged = merge_servers(Some(&base), &a, &b, &choices).unwrap().merged.unwrap();
+        assert_eq!(merged, server(&["-y", "server-fs", "/srv"], &[("LOG", "debug")]));
+    }
+
+    #[test]
+    fn test_without_base_every_difference_conflicts() {
+        let a = server(&["-y", "server-fs"], &[]);
+        let mut b = a.clone();
+        b.url = Some("https://example.com/mcp".to_string());
+
+        let merge = merge_servers(None, &a, &b, &HashMap::new()).unwrap();
+        assert_eq!(merge.conflicts.len(), 1);
+        assert_eq!(merge.conflicts[0].field, "url");
+        assert_eq!(merge.conflicts[0].a, None);
+    }
+}
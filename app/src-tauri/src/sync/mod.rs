@@ -1,4 +1,5 @@
 pub mod conflict_detector;
 pub mod diff_generator;
 pub mod engine;
+pub mod merge;
 
@@ -24,9 +24,25 @@ fn main() {
         .replace("\r\n", "\n")
         .replace("\r", "\n");
 
-    // Decode the private key
+    // Decode the private key, asking for the passphrase if it is encrypted
     let private_key = match keys::decode_secret_key(&normalized_key, None) {
         Ok(key) => key,
+        Err(keys::Error::KeyIsEncrypted) => {
+            print!("\nThis key is passphrase-protected. Passphrase: ");
+            std::io::stdout().flush().unwrap();
+
+            let mut passphrase = String::new();
+            std::io::stdin().read_line(&mut passphrase).unwrap();
+            let passphrase = passphrase.trim_end_matches(['\r', '\n']);
+
+            match keys::decode_secret_key(&normalized_key, Some(passphrase)) {
+                Ok(key) => key,
+                Err(e) => {
+                    eprintln!("ERROR: Failed to decrypt private key (wrong passphrase?): {:?}", e);
+                    return;
+                }
+            }
+        }
         Err(e) => {
             eprintln!("ERROR: Failed to decode private key: {:?}", e);
             return;
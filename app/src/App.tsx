@@ -36,7 +36,7 @@ import { useConfigPaths, useRemoteConfigPaths, useClaudeConfig, useOpenCodeConfi
 import { useMachines } from "@/hooks/useMachines";
 import { useEnvironmentCheck } from "@/hooks/useSystem";
 import { transformClaudeConfig, transformOpenCodeConfig } from "@/lib/transformers";
-import { cn } from "@/lib/utils";
+import { cn, describeError } from "@/lib/utils";
 import { tauriApi } from "@/lib/tauri";
 import "./App.css";
 
@@ -390,7 +390,7 @@ function App() {
             { name: server.name, enabled, remotePath: remotePaths.claudePath || undefined },
             {
                 onSuccess: () => toast.success(`Server ${server.name} ${enabled ? 'enabled' : 'disabled'}`),
-                onError: (e: Error) => toast.error(`Failed to toggle server: ${e.message}`)
+                onError: (e: Error) => toast.error(`Failed to toggle server: ${describeError(e)}`)
             }
         );
     } else {
@@ -406,7 +406,7 @@ function App() {
              },
              {
                  onSuccess: () => toast.success(`Server ${server.name} ${enabled ? 'enabled' : 'disabled'}`),
-                 onError: (e: Error) => toast.error(`Failed to toggle server: ${e.message}`)
+                 onError: (e: Error) => toast.error(`Failed to toggle server: ${describeError(e)}`)
              }
          );
     }
@@ -421,7 +421,7 @@ function App() {
         
      mutation.mutate({ name: server.name, remotePath: (server.source === "claude" ? remotePaths.claudePath : remotePaths.opencodePath) || undefined }, {
         onSuccess: () => toast.success("Server deleted"),
-        onError: (e: Error) => toast.error(`Failed to delete: ${e.message}`)
+        onError: (e: Error) => toast.error(`Failed to delete: ${describeError(e)}`)
      });
   };
 
@@ -446,7 +446,7 @@ function App() {
     toast.promise(Promise.all(promises), {
       loading: `${enabled ? 'Enabling' : 'Disabling'} ${servers.length} server(s)...`,
       success: () => `${servers.length} server(s) ${enabled ? 'enabled' : 'disabled'}`,
-      error: (e: Error) => `Failed: ${e.message}`
+      error: (e: Error) => `Failed: ${describeError(e)}`
     });
   };
 
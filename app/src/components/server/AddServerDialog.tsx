@@ -9,6 +9,7 @@ import {
 import { ServerForm, ServerFormValues, formValuesToConfig } from "./ServerForm";
 import { useClaudeMutations, useOpenCodeMutations } from "@/hooks/useConfig";
 import { toast } from "sonner";
+import { describeError } from "@/lib/utils";
 import { Tabs, TabsContent, TabsList, TabsTrigger } from "@/components/ui/tabs";
 import { Textarea } from "@/components/ui/textarea";
 import { Button } from "@/components/ui/button";
@@ -60,7 +61,7 @@ export function AddServerDialog({ open, onOpenChange, source, path, machineId, p
             setJsonInput("");
             setPrefilledValues(undefined);
           },
-          onError: (e) => toast.error(`Failed to add server: ${e.message}`),
+          onError: (e) => toast.error(`Failed to add server: ${describeError(e)}`),
         }
       );
     } else {
@@ -82,7 +83,7 @@ export function AddServerDialog({ open, onOpenChange, source, path, machineId, p
                     setJsonInput("");
                     setPrefilledValues(undefined);
                 },
-                onError: (e) => toast.error(`Failed to add server: ${e.message}`),
+                onError: (e) => toast.error(`Failed to add server: ${describeError(e)}`),
             }
         );
     }
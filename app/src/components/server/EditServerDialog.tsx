@@ -8,6 +8,7 @@ import {
 import { ServerForm, ServerFormValues, formValuesToConfig, serverToFormValues } from "./ServerForm";
 import { useClaudeMutations, useOpenCodeMutations } from "@/hooks/useConfig";
 import { toast } from "sonner";
+import { describeError } from "@/lib/utils";
 import { MCPServer } from "@/types/config";
 
 interface EditServerDialogProps {
@@ -44,7 +45,7 @@ export function EditServerDialog({ open, onOpenChange, server, path, onSuccess }
             onOpenChange(false);
             onSuccess?.();
           },
-          onError: (e) => toast.error(`Failed to update server: ${e.message}`),
+          onError: (e) => toast.error(`Failed to update server: ${describeError(e)}`),
         }
       );
     } else {
@@ -64,7 +65,7 @@ export function EditServerDialog({ open, onOpenChange, server, path, onSuccess }
                     onOpenChange(false);
                     onSuccess?.();
                 },
-                onError: (e) => toast.error(`Failed to update server: ${e.message}`),
+                onError: (e) => toast.error(`Failed to update server: ${describeError(e)}`),
             }
         );
     }
@@ -8,6 +8,7 @@ import { Card, CardContent, CardHeader, CardTitle } from '@/components/ui/card';
 import { Badge } from '@/components/ui/badge';
 import { Loader2, ArrowRight, ArrowLeft, AlertTriangle, CheckCircle2, Info, Check, Plus, Pencil, Trash2, FileText, RefreshCw, Eye, ArrowLeftRight, ChevronDown } from 'lucide-react';
 import { toast } from 'sonner';
+import { describeError } from '@/lib/utils';
 import { ConflictDiffViewer } from './ConflictDiffViewer';
 
 interface SyncPreviewProps {
@@ -62,7 +63,7 @@ export function SyncPreview({ claudePath, opencodePath, machineId, syncMode, onS
       setSelectedToClaude(toClaude);
       setSelectedToOpencode(toOpencode);
     } catch (error) {
-      toast.error("Analysis Failed: " + String(error));
+      toast.error("Analysis Failed: " + describeError(error));
     } finally {
       setLoading(false);
     }
@@ -89,7 +90,7 @@ export function SyncPreview({ claudePath, opencodePath, machineId, syncMode, onS
       onSyncComplete();
       analyze();
     } catch (error) {
-      toast.error("Sync Failed: " + String(error));
+      toast.error("Sync Failed: " + describeError(error));
       setLoading(false);
     }
   };
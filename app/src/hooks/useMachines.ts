@@ -98,7 +98,7 @@ export function useMachines() {
 
     const addMachine = useCallback(async (name: string, host: string, username: string, sshKeyId: number, port: number = 22, platform: string = "linux") => {
         try {
-            const newMachineId = await invoke<number>("add_machine", { name, host, username, sshKeyId, port, platform });
+            const newMachineId = await invoke<number>("add_machine", { machine: { name, host, username, sshKeyId, port, platform } });
             toast.success("Machine added");
             
             // Prefetch the new machine's config immediately in the background
@@ -128,7 +128,7 @@ export function useMachines() {
         }
 
         try {
-            await invoke("update_machine", { id: parseInt(id), name, host, username, sshKeyId, port, platform });
+            await invoke("update_machine", { id: parseInt(id), machine: { name, host, username, sshKeyId, port, platform } });
             toast.success("Machine updated");
             fetchMachines();
             return true;
@@ -52,9 +52,7 @@ export function useSSH() {
     try {
       setTestingConnection(true);
       const output = await invoke<string>("test_ssh_connection", {
-        host,
-        username,
-        privateKey,
+        target: { host, username, privateKey },
       });
       toast.success("Connection successful!");
       console.log("SSH Output:", output);
@@ -1,6 +1,22 @@
 import { clsx, type ClassValue } from "clsx"
 import { twMerge } from "tailwind-merge"
+import type { SchemaRejection } from "@/types/config"
 
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs))
 }
+
+const SCHEMA_VIOLATION = "SCHEMA_VIOLATION: "
+
+/** Text for an error from a Tauri command, with schema violations listed by JSON pointer */
+export function describeError(error: unknown): string {
+  const text = error instanceof Error ? error.message : String(error)
+  if (!text.startsWith(SCHEMA_VIOLATION)) return text
+  try {
+    const rejection: SchemaRejection = JSON.parse(text.slice(SCHEMA_VIOLATION.length))
+    const details = rejection.violations.map(v => `${v.pointer || "/"}: ${v.message}`)
+    return `${rejection.client} would reject ${rejection.path}:\n${details.join("\n")}`
+  } catch {
+    return text
+  }
+}
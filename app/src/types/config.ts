@@ -63,9 +63,41 @@ export interface DiffLine {
   content: string;
 }
 
+/** A field both sides changed to different values; `null` means the field is absent */
+export interface FieldConflict {
+  field: string;
+  base?: unknown;
+  a?: unknown;
+  b?: unknown;
+}
+
+/** Three-way merge of a server both sides changed since the last sync */
+export interface ServerMerge {
+  fromA: string[];
+  fromB: string[];
+  conflicts: FieldConflict[];
+  /** The merged server, once there is no conflict left */
+  merged?: Record<string, unknown>;
+}
+
+/** One reason a client would reject a server entry */
+export interface SchemaViolation {
+  server: string;
+  /** JSON pointer to the offending value in the config file */
+  pointer: string;
+  message: string;
+}
+
+/** A write refused by schema validation, sent as `SCHEMA_VIOLATION: ` followed by this as JSON */
+export interface SchemaRejection {
+  client: string;
+  path: string;
+  violations: SchemaViolation[];
+}
+
 export interface SyncItem {
   name: string;
-  status: "Synced" | "CreatedInB" | "DeletedFromB" | "UpdatedInB" | "CreatedInA" | "DeletedFromA" | "UpdatedInA" | "Conflict";
+  status: "Synced" | "CreatedInB" | "DeletedFromB" | "UpdatedInB" | "CreatedInA" | "DeletedFromA" | "UpdatedInA" | "Merged" | "Conflict";
   actionDescription: string;
   diff?: string;
   diffLines?: DiffLine[];
@@ -74,6 +106,9 @@ export interface SyncItem {
   claudeAsOpencodeJson?: string;
   opencodeAsClaudeJson?: string;
   contentMatches?: string[];
+  /** Fields left out of the other source's file; the app keeps them, so copying back restores them */
+  lostFields?: string[];
+  merge?: ServerMerge;
 }
 
 export interface SyncPlan {